@@ -0,0 +1,191 @@
+use super::series::SERIES_COLORS;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Five-number summary plus mean and dispersion. Whiskers are the 5th and 95th percentiles.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct BoxStats {
+    pub n: usize,
+    pub mean: f64,
+    pub sd: f64,
+    pub p5: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub p95: f64,
+}
+
+/// Linear-interpolation quantile of sorted values, `q` in [0, 1].
+fn quantile_sorted(sorted: &[f64], q: f64) -> f64 {
+    let pos = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+}
+
+impl BoxStats {
+    /// Summary of the finite values; `None` when there are none.
+    pub fn from_values(values: impl IntoIterator<Item = f64>) -> Option<Self> {
+        let mut v: Vec<f64> = values.into_iter().filter(|x| x.is_finite()).collect();
+        if v.is_empty() {
+            return None;
+        }
+        v.sort_by(f64::total_cmp);
+        let n = v.len();
+        let mean = v.iter().sum::<f64>() / n as f64;
+        let sd = if n > 1 {
+            (v.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n as f64 - 1.0)).sqrt()
+        } else {
+            f64::NAN
+        };
+        Some(BoxStats {
+            n,
+            mean,
+            sd,
+            p5: quantile_sorted(&v, 0.05),
+            p25: quantile_sorted(&v, 0.25),
+            median: quantile_sorted(&v, 0.5),
+            p75: quantile_sorted(&v, 0.75),
+            p95: quantile_sorted(&v, 0.95),
+        })
+    }
+}
+
+/// Box plots in groups (e.g. one group per factor, one box per country). Each group is scaled
+/// to its own range, so groups measured in different units can sit side by side; the group's
+/// range is printed under its label. `boxes[g][m]` belongs to `groups[g]` and `members[m]`.
+#[component]
+pub fn GroupedBoxPlot(
+    title: String,
+    groups: Vec<String>,
+    members: Vec<String>,
+    boxes: Vec<Vec<Option<BoxStats>>>,
+) -> Element {
+    if groups.is_empty() || members.is_empty() {
+        return rsx!(div { "No data available" });
+    }
+    let view_w = 600.0_f64;
+    let view_h = 300.0_f64;
+    let axis_left = 20.0;
+    let axis_right = view_w - 90.0;
+    let axis_top = 25.0;
+    let axis_bottom = view_h - 45.0;
+    let slot = (axis_right - axis_left) / groups.len() as f64;
+    let box_w = (slot * 0.8 / members.len() as f64).min(24.0);
+
+    let drawn: Vec<_> = groups
+        .iter()
+        .enumerate()
+        .map(|(g, name)| {
+            let row: Vec<Option<BoxStats>> = boxes.get(g).cloned().unwrap_or_default();
+            let lo = row.iter().flatten().map(|b| b.p5).fold(f64::INFINITY, f64::min);
+            let hi = row.iter().flatten().map(|b| b.p95).fold(f64::NEG_INFINITY, f64::max);
+            let denom = (hi - lo).abs().max(1e-12);
+            let scale_y = |y: f64| axis_bottom - ((y - lo) / denom) * (axis_bottom - axis_top);
+            let left = axis_left + slot * g as f64 + (slot - box_w * members.len() as f64) / 2.0;
+            let centre = axis_left + slot * (g as f64 + 0.5);
+            let range = if lo.is_finite() {
+                format!("{lo:.3} to {hi:.3}")
+            } else {
+                "no data".to_string()
+            };
+            let shapes: Vec<_> = row
+                .iter()
+                .enumerate()
+                .filter_map(|(m, b)| b.map(|b| (m, b)))
+                .map(|(m, b)| {
+                    let color = SERIES_COLORS[m % SERIES_COLORS.len()];
+                    let x = left + box_w * m as f64 + box_w * 0.1;
+                    let w = box_w * 0.8;
+                    let mid = x + w / 2.0;
+                    let (top, bottom) = (scale_y(b.p75), scale_y(b.p25));
+                    let tip = format!(
+                        "{name} / {}: n {}, mean {:.4}, median {:.4}, IQR {:.4} to {:.4}",
+                        members[m], b.n, b.mean, b.median, b.p25, b.p75
+                    );
+                    rsx!(
+                        g { key: "{g}-{m}",
+                            title { "{tip}" }
+                            line {
+                                x1: "{mid}", y1: "{scale_y(b.p95)}", x2: "{mid}", y2: "{scale_y(b.p5)}",
+                                stroke: "{color}", stroke_width: "1"
+                            }
+                            rect {
+                                x: "{x}", y: "{top}", width: "{w}", height: "{(bottom - top).max(0.5)}",
+                                fill: "{color}", fill_opacity: "0.35", stroke: "{color}", stroke_width: "1"
+                            }
+                            line {
+                                x1: "{x}", y1: "{scale_y(b.median)}", x2: "{x + w}", y2: "{scale_y(b.median)}",
+                                stroke: "white", stroke_width: "1.2"
+                            }
+                        }
+                    )
+                })
+                .collect();
+            rsx!(
+                g { key: "{name}",
+                    { shapes.into_iter() }
+                    text {
+                        x: "{centre}", y: "{axis_bottom + 14.0}", font_size: "8", font_family: "Georgia",
+                        font_weight: "700", text_anchor: "middle", fill: "white",
+                        "{name}"
+                    }
+                    text {
+                        x: "{centre}", y: "{axis_bottom + 25.0}", font_size: "6", font_family: "Georgia",
+                        text_anchor: "middle", fill: "#90A4AE",
+                        "{range}"
+                    }
+                }
+            )
+        })
+        .collect();
+    let separators: Vec<_> = (1..groups.len())
+        .map(|g| {
+            let x = axis_left + slot * g as f64;
+            rsx!(line {
+                key: "{g}",
+                x1: "{x}",
+                y1: "{axis_top}",
+                x2: "{x}",
+                y2: "{axis_bottom}",
+                stroke: "#455A64",
+                stroke_dasharray: "3 3"
+            })
+        })
+        .collect();
+    let legend: Vec<_> = members
+        .iter()
+        .enumerate()
+        .map(|(m, name)| {
+            let color = SERIES_COLORS[m % SERIES_COLORS.len()];
+            let y = axis_top + 12.0 * m as f64;
+            rsx!(
+                rect { key: "{name}", x: "{axis_right + 10.0}", y: "{y - 6.0}", width: "8", height: "8", fill: "{color}" }
+                text {
+                    x: "{axis_right + 22.0}", y: "{y + 1.0}", font_size: "8",
+                    font_family: "Georgia", fill: "white",
+                    "{name}"
+                }
+            )
+        })
+        .collect();
+
+    rsx! {
+        svg {
+            view_box: "0 0 {view_w} {view_h}",
+            width: "100%",
+            style: "background-color: #000;",
+            text {
+                x: "{axis_left}", y: "15", font_size: "10",
+                font_family: "Georgia", font_weight: "700", fill: "white",
+                "{title}"
+            }
+            line {
+                x1: "{axis_left}", y1: "{axis_bottom}", x2: "{axis_right}", y2: "{axis_bottom}",
+                stroke: "#90A4AE", stroke_width: "1"
+            }
+            { separators.into_iter() }
+            { drawn.into_iter() }
+            { legend.into_iter() }
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::{mem::MaybeUninit, ops::Deref};
-use crate::ops::MyMatrix;
+use crate::ops::{calendar::Exchange, MyMatrix};
 use crate::Serialize;
-use chrono::{NaiveDateTime, TimeZone, Utc};
+use chrono::NaiveDateTime;
 use dioxus::prelude::*;
 use nalgebra::DMatrix;
 use serde::Deserialize;
@@ -17,6 +17,10 @@ pub struct LcMatrix {
     pub matrix: MyMatrix,
     pub y_axis: String,
     pub parallel: bool,
+    /// Exchange whose time zone is used for the x-axis labels.
+    #[props(default)]
+    #[serde(default)]
+    pub exchange: Exchange,
 }
 
 #[derive(Debug, Props, PartialEq, Clone, Serialize, Deserialize)]
@@ -163,6 +167,7 @@ pub fn CandlesChart(props: LcMatrix) -> Element {
                 matrix: props.matrix.clone(),
                 y_axis: props.y_axis.clone(),
                 parallel: props.parallel,
+                exchange: props.exchange,
             }, page)
     }) {
         Ok(r) => r,
@@ -270,8 +275,7 @@ let x_tick_elements: Vec<_> = x_tick_values
 
         // interpret xv as ns since epoch; be careful with casts
         let ns = xv.round() as i64;
-        let timestamp = chrono::Utc.timestamp_nanos(ns);
-        let formatted = timestamp.format("%Y-%m-%d %H:%M:%S").to_string();
+        let formatted = props.exchange.axis_label(ns);
 
         rsx!(
             // small tick on axis
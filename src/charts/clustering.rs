@@ -1,3 +1,4 @@
+use crate::charts::series::SERIES_COLORS;
 use crate::ops::{multi_type_mat::MyMmMatrix, MyMatrix};
 use dioxus::{html::optgroup::label, prelude::*};
 use ndarray::Array2;
@@ -7,7 +8,35 @@ pub struct NmsPca {
     pub components: Array2<f64>,
     pub records: Array2<f64>,
     pub labels: Option<Array2<f64>>,
+    /// Point labels (one per row of `records`); empty for none.
     pub nms: Vec<String>,
+    /// Score columns plotted on the x and y axes.
+    pub axes: (usize, usize),
+    /// Explained-variance share per component, for the axis titles.
+    pub explained: Vec<f64>,
+}
+
+impl NmsPca {
+    /// Axis title for component `c`, e.g. "PC1 (34.2%)".
+    pub fn axis_title(&self, c: usize) -> String {
+        match self.explained.get(c) {
+            Some(r) => format!("PC{} ({:.1}%)", c + 1, r * 100.0),
+            None => format!("PC{}", c + 1),
+        }
+    }
+}
+
+/// Fill for a cluster label: the series palette first, then hues spaced by the golden angle
+/// so any number of clusters stays distinguishable. Negative labels (noise) are grey.
+pub fn cluster_color(group: f64) -> String {
+    if !group.is_finite() || group < 0.0 {
+        return "#9E9E9E".to_string();
+    }
+    let l = group.round() as usize;
+    match SERIES_COLORS.get(l) {
+        Some(c) => c.to_string(),
+        None => format!("hsl({:.0}, 70%, 55%)", (l as f64 * 137.508) % 360.0),
+    }
 }
 
 #[component]
@@ -18,14 +47,22 @@ pub fn PcaChart(pca_nms: NmsPca) -> Element {
     let axis_right = 0.95 * view_w;
     let axis_top = 0.05 * view_h;
     let axis_bottom = 0.75 * view_h;
+    let x_title = pca_nms.axis_title(pca_nms.axes.0);
+    let y_title = pca_nms.axis_title(pca_nms.axes.1);
     let mut labels: Vec<f64> = Vec::new();
     if let Some(lbls) = pca_nms.labels {
         (labels, _) = lbls.into_raw_vec_and_offset();
     }
+    // Without cluster labels every point shares the first colour.
+    labels.resize(pca_nms.records.nrows(), 0.0);
     tracing::debug!("{:?}", &labels);
+    let point_names = pca_nms.nms;
     let record = pca_nms.records;
-    let comp1: Vec<f64> = record.column(0).to_owned().to_vec();
-    let comp2: Vec<f64> = record.column(1).to_owned().to_vec();
+    if pca_nms.axes.0 >= record.ncols() || pca_nms.axes.1 >= record.ncols() {
+        return rsx!(div { "Selected components are not in the fit" });
+    }
+    let comp1: Vec<f64> = record.column(pca_nms.axes.0).to_owned().to_vec();
+    let comp2: Vec<f64> = record.column(pca_nms.axes.1).to_owned().to_vec();
 
     let x_min = comp1
         .iter()
@@ -115,21 +152,6 @@ pub fn PcaChart(pca_nms: NmsPca) -> Element {
             })
             .collect();
 
-    fn color_circle(group: &f64) -> &'static str {
-        // Treat group as an integer label (e.g., 0.0, 1.0, 2.0...)
-        match group.round() as i64 {
-            0 => "#4CAF50", // green
-            1 => "#2196F3", // blue
-            2 => "#FF9800", // orange
-            3 => "#E91E63", // pink
-            4 => "#9C27B0", // purple
-            5 => "#00BCD4", // cyan
-            6 => "#FFC107", // amber
-            7 => "#F44336", // red
-            _ => "#9E9E9E", // gray (fallback)
-        }
-    }
-
     let circles: Vec<_> = comp1
         .iter()
         .zip(comp2.iter().zip(labels.iter()))
@@ -138,7 +160,8 @@ pub fn PcaChart(pca_nms: NmsPca) -> Element {
             let scaled_x = scale_x(x);
             let scaled_y = scale_y(y);
             let r = 3.0;
-            let cc = color_circle(l);
+            let cc = cluster_color(*l);
+            let name = point_names.get(i).cloned().unwrap_or_default();
             rsx!(
             g {
                 key: "{i}",
@@ -163,7 +186,7 @@ pub fn PcaChart(pca_nms: NmsPca) -> Element {
                     text_anchor: "start",
                     dominant_baseline: "middle",
                     pointer_events: "none",
-                    "hello"
+                    "{name}"
                 }
             })
         })
@@ -208,14 +231,15 @@ pub fn PcaChart(pca_nms: NmsPca) -> Element {
             { circles.into_iter() },
 
             text {
-            x: "4%",
-            y: "45%",
+            x: "8",
+            y: "120",
             font_size: "9",
             font_family: "Georgia",
             font_weight: "700",
-            text_anchor: "end",
+            text_anchor: "middle",
+            transform: "rotate(-90, 8, 120)",
             fill: "white",  // dark gray text for readability on light strip
-            "PC1"
+            "{y_title}"
             }
             text {
                 x: "55%",
@@ -225,159 +249,306 @@ pub fn PcaChart(pca_nms: NmsPca) -> Element {
                 font_weight: "700",
                 text_anchor: "end",
                 fill: "white",  // dark gray text for readability on light strip
-                "PC2"
+                "{x_title}"
             }
         }
     }
 }
 
-#[component]
-pub fn CharPlot(mat: MyMmMatrix) -> Element {
-    let x_padding = 30;
-    let y_padding = 5;
-    let pr_comp_data = mat.data_f64;
-    /*
+/// Factor whose raw firm mean sets the bubble area in `CharPlot`.
+pub const SIZE_SOURCE_COL: &str = "dolvol";
+/// Its column in the grouped factor matrix: the per-firm mean of `dolvol` before preprocessing.
+/// It sizes the bubbles only and is never a factor (see `MyMmMatrix::pca_candidate_columns`).
+pub const BUBBLE_SIZE_COL: &str = "dolvol_raw_mean";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CharColor {
+    #[default]
+    Cluster,
+    Country,
+}
 
-    let x_values: Vec<f64> = combined.iter().map(|(x, _)| *x).collect();
-    let y_values: Vec<f64> = combined.iter().map(|(_, y)| *y).collect();
+impl CharColor {
+    pub const ALL: [CharColor; 2] = [CharColor::Cluster, CharColor::Country];
 
-    if x_values.is_empty() || y_values.is_empty() {
-        return rsx!(div { "No data available" });
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CharColor::Cluster => "cluster",
+            CharColor::Country => "country",
+        }
     }
 
-    let x_min = *x_values
-        .iter()
-        .min_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
-    let x_max = *x_values
-        .iter()
-        .max_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
-    let y_min = *y_values
-        .iter()
-        .min_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
-    let y_max = *y_values
-        .iter()
-        .max_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
+    pub fn label(&self) -> &'static str {
+        match self {
+            CharColor::Cluster => "Cluster",
+            CharColor::Country => "Country",
+        }
+    }
 
-    let scale_x = |x: f64| ((x - x_min) / (x_max - x_min) * (width - 2.0 * x_padding)) + x_padding;
-    let scale_y =
-        |y: f64| height - ((y - y_min) / (y_max - y_min) * (height - 2.0 * y_padding)) - y_padding;
+    pub fn from_label(s: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.as_str() == s)
+            .unwrap_or_default()
+    }
+}
 
-    let x_ticks = 6;
-    let y_ticks = 20;
+/// Firm-characteristic explorer over the grouped factor matrix: any two factors on the axes,
+/// bubbles sized by the firm's mean raw `dolvol` (`BUBBLE_SIZE_COL`) and coloured by cluster (`clusters`, one label per row) or by
+/// country. Hovering a bubble shows the firm identifiers and its values.
+#[component]
+pub fn CharPlot(mat: MyMmMatrix, clusters: Option<Vec<Option<usize>>>) -> Element {
+    let mut x_col: Signal<Option<String>> = use_signal(|| None);
+    let mut y_col: Signal<Option<String>> = use_signal(|| None);
+    let mut color_by = use_signal(CharColor::default);
+
+    let candidates: Vec<String> = mat.pca_candidate_columns();
+    if candidates.len() < 2 {
+        return rsx!(div { "Select at least two factor columns" });
+    }
+    // Fall back to the first columns when the selection is not in the current matrix.
+    let pick = |sel: Option<String>, fallback: usize| {
+        sel.filter(|c| candidates.contains(c))
+            .unwrap_or_else(|| candidates[fallback].clone())
+    };
+    let x_name = pick(x_col(), 0);
+    let y_name = pick(y_col(), 1);
+    let (Some(xj), Some(yj)) = (mat.find_index_f64(&x_name), mat.find_index_f64(&y_name)) else {
+        return rsx!(div { "No data available" });
+    };
+    let size_j = mat.find_index_f64(BUBBLE_SIZE_COL);
+    let n = mat.nrows();
+
+    // Colour group per row, with the legend entries in group order.
+    let mut legend: Vec<String> = Vec::new();
+    let groups: Vec<f64> = match color_by() {
+        CharColor::Cluster => {
+            let labels = clusters.unwrap_or_default();
+            let groups: Vec<f64> = (0..n)
+                .map(|i| match labels.get(i) {
+                    Some(Some(c)) => *c as f64,
+                    Some(None) => -1.0,
+                    None => 0.0,
+                })
+                .collect();
+            let k = groups.iter().fold(0.0_f64, |m, g| m.max(*g)) as usize;
+            if !labels.is_empty() {
+                legend = (0..=k).map(|c| format!("Cluster {}", c + 1)).collect();
+            }
+            groups
+        }
+        CharColor::Country => (0..n)
+            .map(|i| {
+                let country = mat.cell(i, "excntry");
+                match legend.iter().position(|c| *c == country) {
+                    Some(g) => g as f64,
+                    None => {
+                        legend.push(country);
+                        (legend.len() - 1) as f64
+                    }
+                }
+            })
+            .collect(),
+    };
 
-    let x_tick_values: Vec<f64> = (0..x_ticks)
-        .map(|i| x_min + (i as f64 / (x_ticks - 1) as f64) * (x_max - x_min))
+    let points: Vec<(usize, f64, f64)> = (0..n)
+        .map(|i| (i, mat.data_f64[(i, xj)], mat.data_f64[(i, yj)]))
+        .filter(|(_, x, y)| x.is_finite() && y.is_finite())
         .collect();
+    if points.is_empty() {
+        return rsx!(div { "No data available" });
+    }
 
-    let y_tick_values: Vec<f64> = (0..y_ticks)
-        .map(|i| y_min + (i as f64 / (y_ticks - 1) as f64) * (y_max - y_min))
+    let view_w = 400.0_f64;
+    let view_h = 300.0_f64;
+    let axis_left = 0.12 * view_w;
+    let axis_right = 0.78 * view_w;
+    let axis_top = 0.05 * view_h;
+    let axis_bottom = 0.8 * view_h;
+
+    let x_min = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let x_max = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+    let y_min = points.iter().map(|p| p.2).fold(f64::INFINITY, f64::min);
+    let y_max = points.iter().map(|p| p.2).fold(f64::NEG_INFINITY, f64::max);
+    let denom_x = (x_max - x_min).abs().max(1e-12);
+    let denom_y = (y_max - y_min).abs().max(1e-12);
+    let scale_x = |x: f64| axis_left + ((x - x_min) / denom_x) * (axis_right - axis_left);
+    let scale_y = |y: f64| axis_bottom - ((y - y_min) / denom_y) * (axis_bottom - axis_top);
+
+    // Bubble area proportional to dollar volume; rows without it get the smallest bubble.
+    let sizes: Vec<f64> = (0..n)
+        .map(|i| size_j.map_or(f64::NAN, |j| mat.data_f64[(i, j)]))
         .collect();
+    let size_max = sizes
+        .iter()
+        .copied()
+        .filter(|v| v.is_finite() && *v > 0.0)
+        .fold(0.0_f64, f64::max);
+    let radius = |i: usize| {
+        let s = sizes[i];
+        if size_max > 0.0 && s.is_finite() && s > 0.0 {
+            2.0 + 7.0 * (s / size_max).sqrt()
+        } else {
+            2.0
+        }
+    };
 
-        let x_tick_elements: Vec<_> = x_tick_values
-            .iter()
-            .map(|&xv| {
-                let x_pos = scale_x(xv);
-                let label = format!("{:.2}", xv); // format as price
+    let ticks = 5;
+    let tick_at = |mn: f64, mx: f64, i: usize| mn + (i as f64 / (ticks as f64 - 1.0)) * (mx - mn);
+    let x_tick_elements: Vec<_> = (0..ticks)
+        .map(|i| {
+            let xv = tick_at(x_min, x_max, i);
+            let x_pos = scale_x(xv);
+            rsx!(
+                line {
+                    x1: "{x_pos}", y1: "{axis_bottom}", x2: "{x_pos}", y2: "{axis_bottom + 4.0}",
+                    stroke: "#90A4AE", stroke_width: "1"
+                },
+                text {
+                    x: "{x_pos}", y: "{axis_bottom + 14.0}", font_size: "7", font_family: "Georgia",
+                    text_anchor: "middle", fill: "white",
+                    {format!("{xv:.2}")}
+                }
+            )
+        })
+        .collect();
+    let y_tick_elements: Vec<_> = (0..ticks)
+        .map(|i| {
+            let yv = tick_at(y_min, y_max, i);
+            let y_pos = scale_y(yv);
+            rsx!(
+                line {
+                    x1: "{axis_left}", y1: "{y_pos}", x2: "{axis_right}", y2: "{y_pos}",
+                    stroke: "#00bcd4", stroke_width: "0.5", stroke_dasharray: "3 3", stroke_opacity: "0.25"
+                },
+                text {
+                    x: "{axis_left - 4.0}", y: "{y_pos + 3.0}", font_size: "7", font_family: "Georgia",
+                    text_anchor: "end", fill: "white",
+                    {format!("{yv:.2}")}
+                }
+            )
+        })
+        .collect();
 
-                rsx!(
-                    line {
-                    x1: "{x_pos}", y1: "{height - y_padding}", x2: "{x_pos}",
-                    y2: "{height - y_padding + 5.0}",  stroke: "black", stroke_width: "1"
-                    },
-                    text {
-                    x: "{x_pos}",  y: "{height - y_padding + 20.0}", font_size: "11",
-                    font_family: "Georgia", font_weight: "700", text_anchor: "middle", fill: "black",
-                    "{label}"
+    // Largest bubbles first so small ones stay on top and hoverable.
+    let mut order = points.clone();
+    order.sort_by(|a, b| radius(b.0).total_cmp(&radius(a.0)));
+    let bubbles: Vec<_> = order
+        .iter()
+        .map(|&(i, x, y)| {
+            let cx = scale_x(x);
+            let cy = scale_y(y);
+            let r = radius(i);
+            let fill = cluster_color(groups[i]);
+            let id = format!(
+                "{}/{} {}",
+                mat.cell(i, "gvkey"),
+                mat.cell(i, "iid"),
+                mat.cell(i, "excntry")
+            );
+            let tip = format!(
+                "{id}\n{x_name}: {x:.4}\n{y_name}: {y:.4}\n{BUBBLE_SIZE_COL}: {:.0}",
+                sizes[i]
+            );
+            rsx!(
+                g { key: "{i}", class: "pt",
+                    circle {
+                        cx: "{cx}", cy: "{cy}", r: "{r}", fill: "{fill}",
+                        stroke: "black", stroke_width: "0.3", opacity: "0.75",
+                        title { "{tip}" }
                     }
-                )
-            })
-            .collect();
-
-        let y_tick_elements: Vec<_> = y_tick_values
-            .iter()
-            .map(|&yv| {
-                let y_pos = scale_y(yv);
-                let label = format!("{}", yv.round() as i64);
-                rsx!(
-                    // Horizontal grid line
-                    line {
-                        x1: "{x_padding}", y1: "{y_pos}", x2: "{width - x_padding}", y2: "{y_pos}",
-                        stroke: "#00bcd4", stroke_width: "0.5", stroke_dasharray: "3 3", stroke_opacity: "0.25"
-                    },
-                    // Y-axis tick mark
-                    line {
-                        x1: "{x_padding - 6.0}", y1: "{y_pos}", x2: "{x_padding}",
-                        y2: "{y_pos}", stroke: "#90A4AE", stroke_width: "1"
-                    },
-                    // Label in dark color
                     text {
-                        x: "{x_padding - 10.0}", y: "{y_pos + 4.0}", font_size: "11",
-                        font_family: "Georgia", font_weight: "700", text_anchor: "end", fill: "#222",
-                        "{label}"
+                        class: "pt-label", x: "{cx + r + 2.0}", y: "{cy}", font_size: "7",
+                        font_family: "Georgia", fill: "white", dominant_baseline: "middle",
+                        pointer_events: "none",
+                        "{id}"
                     }
-                )
-            })
-            .collect();
-    */
+                }
+            )
+        })
+        .collect();
+    let legend_elements: Vec<_> = legend
+        .iter()
+        .enumerate()
+        .take(20)
+        .map(|(g, name)| {
+            let y = axis_top + 4.0 + 11.0 * g as f64;
+            let fill = cluster_color(g as f64);
+            rsx!(
+                circle { key: "{name}", cx: "{axis_right + 14.0}", cy: "{y}", r: "3", fill: "{fill}" }
+                text {
+                    x: "{axis_right + 21.0}", y: "{y}", font_size: "7", font_family: "Georgia",
+                    dominant_baseline: "middle", fill: "white",
+                    "{name}"
+                }
+            )
+        })
+        .collect();
+
     rsx! {
-        svg {
-            view_box: "0 0 400 250",
-            width:"100%",
-            height: "100%",
-            rect {
-                x: "{x_padding}",
-                y: "{y_padding}",
-                width:"90%",
-                height: "75%",
-                stroke: "black",
-                stroke_width: "2",
-                fill: "#FAF9FF",
-                fill_opacity: "92%",
+        div { class: "multi-card-div",
+            label { "X" }
+            select {
+                value: "{x_name}",
+                onchange: move |evt| x_col.set(Some(evt.value())),
+                for c in candidates.iter() {
+                    option { value: "{c}", "{c}" }
+                }
+            }
+            label { "Y" }
+            select {
+                value: "{y_name}",
+                onchange: move |evt| y_col.set(Some(evt.value())),
+                for c in candidates.iter() {
+                    option { value: "{c}", "{c}" }
+                }
             }
+            label { "Colour" }
+            select {
+                value: "{color_by().as_str()}",
+                onchange: move |evt| color_by.set(CharColor::from_label(&evt.value())),
+                for c in CharColor::ALL {
+                    option { value: "{c.as_str()}", "{c.label()}" }
+                }
+            }
+        }
+        svg {
+            view_box: "0 0 {view_w} {view_h}",
+            width: "100%",
             line {
-                x1: "9%",
-                y1: "5%",
-                x2: "9%",
-                y2: "75.3%",
-                stroke: "black",
-                stroke_width: 4,
+                x1: "{axis_left}", y1: "{axis_top}", x2: "{axis_left}", y2: "{axis_bottom}",
+                stroke: "#90A4AE", stroke_width: "1"
             }
             line {
-                x1: "9%",
-                y1: "75%",
-                x2: "95%",
-                y2: "75%",
-                stroke: "black",
-                stroke_width: 4,
+                x1: "{axis_left}", y1: "{axis_bottom}", x2: "{axis_right}", y2: "{axis_bottom}",
+                stroke: "#90A4AE", stroke_width: "1"
             }
+            { x_tick_elements.into_iter() }
+            { y_tick_elements.into_iter() }
+            { bubbles.into_iter() }
+            { legend_elements.into_iter() }
             text {
-            x: "4%",
-            y: "45%",
-            font_size: "9",
-            font_family: "Georgia",
-            font_weight: "700",
-            text_anchor: "end",
-            fill: "white",  // dark gray text for readability on light strip
-            "PC1"
+                x: "{(axis_left + axis_right) / 2.0}", y: "{view_h - 12.0}", font_size: "9",
+                font_family: "Georgia", font_weight: "700", text_anchor: "middle", fill: "white",
+                "{x_name}"
             }
             text {
-                x: "55%",
-                y: "88%",
-                font_size: "9",
-                font_family: "Georgia",
-                font_weight: "700",
-                text_anchor: "end",
-                fill: "white",  // dark gray text for readability on light strip
-                "PC2"
+                x: "10", y: "{(axis_top + axis_bottom) / 2.0}", font_size: "9", font_family: "Georgia",
+                font_weight: "700", text_anchor: "middle", fill: "white",
+                transform: "rotate(-90, 10, {(axis_top + axis_bottom) / 2.0})",
+                "{y_name}"
+            }
+            if size_j.is_some() {
+                text {
+                    x: "{axis_right}", y: "{view_h - 12.0}", font_size: "7", font_family: "Georgia",
+                    text_anchor: "end", fill: "#90A4AE",
+                    "bubble area: {BUBBLE_SIZE_COL}"
+                }
             }
-
         }
     }
 }
+
 #[component]
 pub fn ScatterPlot(props: MyMatrix) -> Element {
     let width = 470.0;
@@ -599,3 +770,85 @@ pub fn ScatterPlot(props: MyMatrix) -> Element {
         }
     }
 }
+
+/// Loadings biplot: each factor as an arrow at its (x, y) component loadings, over the
+/// row scores rescaled into the same unit square.
+#[component]
+pub fn LoadingsBiplot(pca_nms: NmsPca, names: Vec<String>) -> Element {
+    let size = 320.0_f64;
+    let c = size / 2.0;
+    let r = size / 2.0 - 30.0;
+    let (a, b) = pca_nms.axes;
+    let comps = &pca_nms.components;
+    if a >= comps.nrows()
+        || b >= comps.nrows()
+        || a >= pca_nms.records.ncols()
+        || b >= pca_nms.records.ncols()
+    {
+        return rsx!(div { "Selected components are not in the fit" });
+    }
+    let x_title = pca_nms.axis_title(a);
+    let y_title = pca_nms.axis_title(b);
+
+    // Scale the largest loading to the circle edge, and the scores to fit inside it.
+    let max_load = (0..comps.ncols())
+        .map(|f| comps[(a, f)].hypot(comps[(b, f)]))
+        .fold(0.0_f64, f64::max)
+        .max(1e-12);
+    let max_score = (0..pca_nms.records.nrows())
+        .map(|i| pca_nms.records[(i, a)].hypot(pca_nms.records[(i, b)]))
+        .fold(0.0_f64, f64::max)
+        .max(1e-12);
+
+    let points: Vec<_> = (0..pca_nms.records.nrows())
+        .map(|i| {
+            let x = c + pca_nms.records[(i, a)] / max_score * r;
+            let y = c - pca_nms.records[(i, b)] / max_score * r;
+            rsx!(circle {
+                key: "{i}",
+                cx: "{x}",
+                cy: "{y}",
+                r: "1.5",
+                fill: "#90A4AE",
+                opacity: "0.4"
+            })
+        })
+        .collect();
+    let arrows: Vec<_> = (0..comps.ncols())
+        .map(|f| {
+            let x = c + comps[(a, f)] / max_load * r;
+            let y = c - comps[(b, f)] / max_load * r;
+            let name = names.get(f).cloned().unwrap_or_default();
+            let anchor = if x >= c { "start" } else { "end" };
+            let dx = if x >= c { 3.0 } else { -3.0 };
+            rsx!(
+                line { key: "{name}", x1: "{c}", y1: "{c}", x2: "{x}", y2: "{y}", stroke: "#FF9800", stroke_width: "1.2" }
+                circle { cx: "{x}", cy: "{y}", r: "2", fill: "#FF9800" }
+                text {
+                    x: "{x + dx}", y: "{y}", font_size: "7", font_family: "Georgia",
+                    text_anchor: "{anchor}", dominant_baseline: "middle", fill: "white",
+                    "{name}"
+                }
+            )
+        })
+        .collect();
+
+    rsx! {
+        svg {
+            view_box: "0 0 {size} {size}",
+            width: "100%",
+            style: "background-color: #000;",
+            circle { cx: "{c}", cy: "{c}", r: "{r}", fill: "none", stroke: "#455A64", stroke_dasharray: "3 3" }
+            line { x1: "{c - r}", y1: "{c}", x2: "{c + r}", y2: "{c}", stroke: "#455A64" }
+            line { x1: "{c}", y1: "{c - r}", x2: "{c}", y2: "{c + r}", stroke: "#455A64" }
+            { points.into_iter() }
+            { arrows.into_iter() }
+            text { x: "{c}", y: "{size - 8.0}", font_size: "9", font_family: "Georgia", text_anchor: "middle", fill: "white", "{x_title}" }
+            text {
+                x: "10", y: "{c}", font_size: "9", font_family: "Georgia", text_anchor: "middle",
+                transform: "rotate(-90, 10, {c})", fill: "white",
+                "{y_title}"
+            }
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use crate::research::correlation::Dendrogram;
+use dioxus::prelude::*;
+
+/// Diverging fill for a value in [-1, 1]: blue for negative, white at zero, red for positive.
+pub fn diverging_color(v: f64) -> String {
+    if !v.is_finite() {
+        return "#555".to_string();
+    }
+    let v = v.clamp(-1.0, 1.0);
+    let fade = (255.0 * (1.0 - v.abs())).round() as u8;
+    if v >= 0.0 {
+        format!("rgb(255,{fade},{fade})")
+    } else {
+        format!("rgb({fade},{fade},255)")
+    }
+}
+
+/// Correlation heatmap with rows and columns in `dendrogram.order` and the dendrogram drawn
+/// above the columns.
+#[component]
+pub fn CorrelationHeatmap(
+    names: Vec<String>,
+    corr: Vec<Vec<f64>>,
+    dendrogram: Dendrogram,
+) -> Element {
+    let n = names.len();
+    if n == 0 {
+        return rsx!(div { "No data available" });
+    }
+    let order: Vec<usize> = if dendrogram.order.len() == n {
+        dendrogram.order.clone()
+    } else {
+        (0..n).collect()
+    };
+    let label_w = 110.0_f64;
+    let dendro_h = 80.0_f64;
+    let grid = 420.0_f64;
+    let cell = grid / n as f64;
+    let view_w = label_w + grid + 10.0;
+    let view_h = dendro_h + grid + 10.0;
+    let font = (cell * 0.6).clamp(4.0, 9.0);
+
+    // Leaf x centres, then merge positions as the dendrogram is built bottom-up.
+    let mut pos_x = vec![0.0_f64; n + dendrogram.merges.len()];
+    let mut pos_h = vec![0.0_f64; n + dendrogram.merges.len()];
+    for (slot, &leaf) in order.iter().enumerate() {
+        pos_x[leaf] = label_w + cell * (slot as f64 + 0.5);
+    }
+    let max_h = dendrogram
+        .merges
+        .iter()
+        .map(|m| m.height)
+        .fold(0.0_f64, f64::max)
+        .max(1e-12);
+    let scale_h = |h: f64| dendro_h - 5.0 - (h / max_h) * (dendro_h - 10.0);
+    let mut links: Vec<(f64, f64, f64, f64, f64)> = Vec::new();
+    for (k, m) in dendrogram.merges.iter().enumerate() {
+        let id = n + k;
+        pos_x[id] = (pos_x[m.left] + pos_x[m.right]) / 2.0;
+        pos_h[id] = m.height;
+        links.push((
+            pos_x[m.left],
+            scale_h(pos_h[m.left]),
+            pos_x[m.right],
+            scale_h(pos_h[m.right]),
+            scale_h(m.height),
+        ));
+    }
+
+    let cells: Vec<_> = order
+        .iter()
+        .enumerate()
+        .flat_map(|(r, &a)| order.iter().enumerate().map(move |(c, &b)| (r, c, a, b)))
+        .map(|(r, c, a, b)| {
+            let v = corr[a][b];
+            let x = label_w + cell * c as f64;
+            let y = dendro_h + cell * r as f64;
+            let fill = diverging_color(v);
+            let tip = format!("{} / {}: {:.3}", names[a], names[b], v);
+            rsx!(
+                rect {
+                    key: "{r}-{c}", x: "{x}", y: "{y}", width: "{cell}", height: "{cell}",
+                    fill: "{fill}", stroke: "#000", stroke_width: "0.2",
+                    title { "{tip}" }
+                }
+            )
+        })
+        .collect();
+
+    let row_labels: Vec<_> = order
+        .iter()
+        .enumerate()
+        .map(|(r, &a)| {
+            let y = dendro_h + cell * (r as f64 + 0.5);
+            rsx!(
+                text {
+                    key: "{a}", x: "{label_w - 4.0}", y: "{y}", font_size: "{font}",
+                    font_family: "Georgia", text_anchor: "end", dominant_baseline: "middle", fill: "white",
+                    "{names[a]}"
+                }
+            )
+        })
+        .collect();
+
+    let branches: Vec<_> = links
+        .iter()
+        .enumerate()
+        .map(|(k, &(x1, y1, x2, y2, y_top))| {
+            rsx!(polyline {
+                key: "{k}",
+                points: "{x1},{y1} {x1},{y_top} {x2},{y_top} {x2},{y2}",
+                fill: "none",
+                stroke: "#90A4AE",
+                stroke_width: "1"
+            })
+        })
+        .collect();
+
+    rsx! {
+        svg {
+            view_box: "0 0 {view_w} {view_h}",
+            width: "100%",
+            style: "background-color: #000;",
+            { branches.into_iter() }
+            { cells.into_iter() }
+            { row_labels.into_iter() }
+        }
+    }
+}
+
+/// Labelled table-style heatmap. Each column is coloured against its own largest absolute
+/// value, so columns in different units stay readable; cells print the raw value.
+#[component]
+pub fn ValueHeatmap(
+    row_labels: Vec<String>,
+    col_labels: Vec<String>,
+    values: Vec<Vec<f64>>,
+) -> Element {
+    let (n_rows, n_cols) = (row_labels.len(), col_labels.len());
+    if n_rows == 0 || n_cols == 0 {
+        return rsx!(div { "No data available" });
+    }
+    let label_w = 60.0_f64;
+    let header_h = 70.0_f64;
+    let cell_w = (480.0 / n_cols as f64).min(70.0);
+    let cell_h = 18.0_f64;
+    let view_w = label_w + cell_w * n_cols as f64 + 10.0;
+    let view_h = header_h + cell_h * n_rows as f64 + 10.0;
+    let value = |r: usize, c: usize| {
+        values
+            .get(r)
+            .and_then(|v| v.get(c))
+            .copied()
+            .unwrap_or(f64::NAN)
+    };
+    let col_max: Vec<f64> = (0..n_cols)
+        .map(|c| {
+            (0..n_rows)
+                .map(|r| value(r, c))
+                .filter(|v| v.is_finite())
+                .fold(0.0_f64, |m, v| m.max(v.abs()))
+                .max(1e-12)
+        })
+        .collect();
+
+    let cells: Vec<_> = (0..n_rows)
+        .flat_map(|r| (0..n_cols).map(move |c| (r, c)))
+        .map(|(r, c)| {
+            let v = value(r, c);
+            let x = label_w + cell_w * c as f64;
+            let y = header_h + cell_h * r as f64;
+            let fill = diverging_color(v / col_max[c]);
+            let shown = if v.is_finite() { format!("{v:.3}") } else { String::new() };
+            rsx!(
+                rect {
+                    key: "{r}-{c}", x: "{x}", y: "{y}", width: "{cell_w}", height: "{cell_h}",
+                    fill: "{fill}", stroke: "#000", stroke_width: "0.5",
+                    title { "{row_labels[r]} / {col_labels[c]}: {shown}" }
+                }
+                text {
+                    x: "{x + cell_w / 2.0}", y: "{y + cell_h / 2.0}", font_size: "7", font_family: "Georgia",
+                    text_anchor: "middle", dominant_baseline: "middle", fill: "#000", pointer_events: "none",
+                    "{shown}"
+                }
+            )
+        })
+        .collect();
+    let row_text: Vec<_> = row_labels
+        .iter()
+        .enumerate()
+        .map(|(r, name)| {
+            let y = header_h + cell_h * (r as f64 + 0.5);
+            rsx!(
+                text {
+                    key: "{name}", x: "{label_w - 4.0}", y: "{y}", font_size: "8", font_family: "Georgia",
+                    text_anchor: "end", dominant_baseline: "middle", fill: "white",
+                    "{name}"
+                }
+            )
+        })
+        .collect();
+    let col_text: Vec<_> = col_labels
+        .iter()
+        .enumerate()
+        .map(|(c, name)| {
+            let x = label_w + cell_w * (c as f64 + 0.5);
+            let y = header_h - 4.0;
+            rsx!(
+                text {
+                    key: "{name}", x: "{x}", y: "{y}", font_size: "8", font_family: "Georgia",
+                    text_anchor: "start", fill: "white", transform: "rotate(-45, {x}, {y})",
+                    "{name}"
+                }
+            )
+        })
+        .collect();
+
+    rsx! {
+        svg {
+            view_box: "0 0 {view_w} {view_h}",
+            width: "100%",
+            style: "background-color: #000;",
+            { cells.into_iter() }
+            { row_text.into_iter() }
+            { col_text.into_iter() }
+        }
+    }
+}
@@ -1,5 +1,8 @@
+pub mod boxplot;
 pub mod candle_stick;
 pub mod clustering;
+pub mod heatmap;
+pub mod series;
 pub mod single_asset_lc;
 
 pub enum ChartType {
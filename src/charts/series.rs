@@ -0,0 +1,208 @@
+use dioxus::prelude::*;
+
+/// Line colours, cycled per series.
+pub const SERIES_COLORS: [&str; 8] = [
+    "#4CAF50", "#2196F3", "#FF9800", "#E91E63", "#9C27B0", "#00BCD4", "#FFC107", "#F44336",
+];
+
+/// Several named series over a shared categorical x-axis (dates, horizons, ...).
+/// Non-finite values are skipped.
+#[component]
+pub fn SeriesChart(
+    title: String,
+    x_labels: Vec<String>,
+    series: Vec<(String, Vec<f64>)>,
+) -> Element {
+    let view_w = 600.0_f64;
+    let view_h = 320.0_f64;
+    let axis_left = 55.0;
+    let axis_right = view_w - 130.0;
+    let axis_top = 25.0;
+    let axis_bottom = view_h - 45.0;
+
+    let values: Vec<f64> = series
+        .iter()
+        .flat_map(|(_, v)| v.iter().copied())
+        .filter(|v| v.is_finite())
+        .collect();
+    if x_labels.is_empty() || values.is_empty() {
+        return rsx!(div { "No data available" });
+    }
+    let y_min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let y_max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let denom_y = (y_max - y_min).abs().max(1e-12);
+    let n = x_labels.len();
+    let scale_x = |i: usize| {
+        if n <= 1 {
+            return (axis_left + axis_right) / 2.0;
+        }
+        axis_left + (i as f64 / (n as f64 - 1.0)) * (axis_right - axis_left)
+    };
+    let scale_y = |y: f64| axis_bottom - ((y - y_min) / denom_y) * (axis_bottom - axis_top);
+
+    let y_ticks = 6;
+    let y_tick_elements: Vec<_> = (0..y_ticks)
+        .map(|i| {
+            let yv = y_min + (i as f64 / (y_ticks as f64 - 1.0)) * (y_max - y_min);
+            let y_pos = scale_y(yv);
+            rsx!(
+                line {
+                    x1: "{axis_left}", y1: "{y_pos}", x2: "{axis_right}", y2: "{y_pos}",
+                    stroke: "#00bcd4", stroke_width: "0.5", stroke_dasharray: "3 3", stroke_opacity: "0.25"
+                },
+                text {
+                    x: "{axis_left - 6.0}", y: "{y_pos + 3.0}", font_size: "8",
+                    font_family: "Georgia", font_weight: "700", text_anchor: "end", fill: "white",
+                    "{yv:.3}"
+                }
+            )
+        })
+        .collect();
+
+    // At most ~8 x labels so dates stay readable.
+    let step = n.div_ceil(8).max(1);
+    let x_tick_elements: Vec<_> = (0..n)
+        .step_by(step)
+        .map(|i| {
+            let x_pos = scale_x(i);
+            rsx!(
+                text {
+                    x: "{x_pos}", y: "{axis_bottom + 14.0}", font_size: "8",
+                    font_family: "Georgia", font_weight: "700", text_anchor: "middle", fill: "white",
+                    "{x_labels[i]}"
+                }
+            )
+        })
+        .collect();
+
+    let lines: Vec<_> = series
+        .iter()
+        .enumerate()
+        .map(|(s, (name, v))| {
+            let color = SERIES_COLORS[s % SERIES_COLORS.len()];
+            let points = v
+                .iter()
+                .enumerate()
+                .filter(|(_, y)| y.is_finite())
+                .map(|(i, &y)| format!("{},{}", scale_x(i), scale_y(y)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let legend_y = axis_top + 12.0 * s as f64;
+            rsx!(
+                polyline { key: "{name}", points: "{points}", fill: "none", stroke: "{color}", stroke_width: "1.5" }
+                rect { x: "{axis_right + 10.0}", y: "{legend_y - 6.0}", width: "8", height: "8", fill: "{color}" }
+                text {
+                    x: "{axis_right + 22.0}", y: "{legend_y + 1.0}", font_size: "8",
+                    font_family: "Georgia", fill: "white",
+                    "{name}"
+                }
+            )
+        })
+        .collect();
+
+    let zero_line = (y_min < 0.0 && y_max > 0.0).then(|| scale_y(0.0));
+
+    rsx! {
+        svg {
+            view_box: "0 0 {view_w} {view_h}",
+            width: "100%",
+            style: "background-color: #000;",
+            text {
+                x: "{axis_left}", y: "15", font_size: "10",
+                font_family: "Georgia", font_weight: "700", fill: "white",
+                "{title}"
+            }
+            line {
+                x1: "{axis_left}", y1: "{axis_top}", x2: "{axis_left}", y2: "{axis_bottom}",
+                stroke: "#90A4AE", stroke_width: "1"
+            }
+            line {
+                x1: "{axis_left}", y1: "{axis_bottom}", x2: "{axis_right}", y2: "{axis_bottom}",
+                stroke: "#90A4AE", stroke_width: "1"
+            }
+            if let Some(y0) = zero_line {
+                line {
+                    x1: "{axis_left}", y1: "{y0}", x2: "{axis_right}", y2: "{y0}",
+                    stroke: "#90A4AE", stroke_width: "0.75"
+                }
+            }
+            { y_tick_elements.into_iter() }
+            { x_tick_elements.into_iter() }
+            { lines.into_iter() }
+        }
+    }
+}
+
+/// Vertical bars for a handful of labelled values (quantile returns, loadings, ...), drawn
+/// from zero so negative bars hang below the axis.
+#[component]
+pub fn BarChart(title: String, labels: Vec<String>, values: Vec<f64>) -> Element {
+    let view_w = 400.0_f64;
+    let view_h = 260.0_f64;
+    let axis_left = 55.0;
+    let axis_right = view_w - 15.0;
+    let axis_top = 25.0;
+    let axis_bottom = view_h - 35.0;
+
+    let finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if finite.is_empty() {
+        return rsx!(div { "No data available" });
+    }
+    let y_min = finite.iter().copied().fold(0.0, f64::min);
+    let y_max = finite.iter().copied().fold(0.0, f64::max);
+    let denom_y = (y_max - y_min).abs().max(1e-12);
+    let scale_y = |y: f64| axis_bottom - ((y - y_min) / denom_y) * (axis_bottom - axis_top);
+    let zero = scale_y(0.0);
+    let slot = (axis_right - axis_left) / values.len().max(1) as f64;
+
+    let bars: Vec<_> = values
+        .iter()
+        .zip(labels.iter())
+        .enumerate()
+        .map(|(i, (&v, label))| {
+            let x = axis_left + slot * i as f64 + slot * 0.15;
+            let w = slot * 0.7;
+            let (y, h) = if v.is_finite() {
+                (scale_y(v).min(zero), (scale_y(v) - zero).abs())
+            } else {
+                (zero, 0.0)
+            };
+            let color = if v >= 0.0 { "#4CAF50" } else { "#F44336" };
+            rsx!(
+                rect { key: "{label}", x: "{x}", y: "{y}", width: "{w}", height: "{h}", fill: "{color}" }
+                text {
+                    x: "{x + w / 2.0}", y: "{axis_bottom + 14.0}", font_size: "8",
+                    font_family: "Georgia", font_weight: "700", text_anchor: "middle", fill: "white",
+                    "{label}"
+                }
+                text {
+                    x: "{x + w / 2.0}", y: "{y - 3.0}", font_size: "7",
+                    font_family: "Georgia", text_anchor: "middle", fill: "white",
+                    "{v:.4}"
+                }
+            )
+        })
+        .collect();
+
+    rsx! {
+        svg {
+            view_box: "0 0 {view_w} {view_h}",
+            width: "100%",
+            style: "background-color: #000;",
+            text {
+                x: "{axis_left}", y: "15", font_size: "10",
+                font_family: "Georgia", font_weight: "700", fill: "white",
+                "{title}"
+            }
+            line {
+                x1: "{axis_left}", y1: "{axis_top}", x2: "{axis_left}", y2: "{axis_bottom}",
+                stroke: "#90A4AE", stroke_width: "1"
+            }
+            line {
+                x1: "{axis_left}", y1: "{zero}", x2: "{axis_right}", y2: "{zero}",
+                stroke: "#90A4AE", stroke_width: "1"
+            }
+            { bars.into_iter() }
+        }
+    }
+}
@@ -1,6 +1,6 @@
-use crate::ops::MyMatrix;
+use crate::ops::{calendar::Exchange, MyMatrix};
 use crate::Serialize;
-use chrono::{NaiveDateTime, TimeZone, Utc};
+use chrono::NaiveDateTime;
 use dioxus::prelude::*;
 use serde::Deserialize;
 use tracing::info;
@@ -9,6 +9,10 @@ use tracing::info;
 pub struct LcMatrix {
     pub matrix: MyMatrix,
     pub y_axis: String,
+    /// Exchange whose time zone is used for the x-axis labels.
+    #[props(default)]
+    #[serde(default)]
+    pub exchange: Exchange,
 }
 
 #[component]
@@ -102,8 +106,7 @@ pub fn PlottersChart(props: LcMatrix) -> Element {
         .map(|&xv| {
             let x_pos = scale_x(xv);
 
-            let timestamp = chrono::Utc.timestamp_nanos(xv as i64);
-            let formatted = timestamp.format("%Y-%m-%d %H:%M:%S").to_string();
+            let formatted = props.exchange.axis_label(xv as i64);
 
             rsx!(
                 line {
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
-struct SecurityData {
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct SecurityData {
     pub instrument_id: u32,
     pub ts_in_delta: i32,
     pub size: i64,
@@ -39,11 +40,49 @@ impl SecurityData {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
 pub struct Hd {
-    instrument_id: u32,
-    length: u8,
-    publisher_id: u32,
-    rtype: u32,
-    ts_event: u64,
+    pub instrument_id: u32,
+    pub length: u8,
+    pub publisher_id: u32,
+    pub rtype: u32,
+    pub ts_event: u64,
+}
+
+/// One trade print: the Databento record header plus the trade body, as stored in `trades`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct TradeRecord {
+    pub hd: Hd,
+    pub data: SecurityData,
+    /// Venue sequence number; with `instrument_id` and `ts_recv` it identifies the print.
+    #[serde(default)]
+    pub sequence: u32,
+}
+
+impl TradeRecord {
+    /// Key of the print's `trades` record, so re-ingesting a file overwrites rather than
+    /// duplicates it.
+    pub fn record_key(&self) -> serde_json::Value {
+        serde_json::json!([self.hd.instrument_id, self.data.ts_recv, self.sequence])
+    }
+
+    /// Row shape used by the Surreal `trades` table (`hd` nested, `bin_1m` as an RFC3339 string).
+    pub fn to_surreal_row(&self) -> serde_json::Value {
+        let bin_1m = self.data.ts_recv - self.data.ts_recv.rem_euclid(60_000_000_000);
+        serde_json::json!({
+            "hd": {
+                "instrument_id": self.hd.instrument_id,
+                "length": self.hd.length,
+                "publisher_id": self.hd.publisher_id,
+                "rtype": self.hd.rtype,
+                "ts_event": self.hd.ts_event,
+            },
+            "price": self.data.price,
+            "size": self.data.size,
+            "ts_in_delta": self.data.ts_in_delta,
+            "ts_recv": self.data.ts_recv,
+            "bin_1m": chrono::DateTime::from_timestamp_nanos(bin_1m).to_rfc3339(),
+            "key": self.record_key(),
+        })
+    }
 }
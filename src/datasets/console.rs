@@ -0,0 +1,444 @@
+// Read-only SQL console over the loaded DuckDB datasets, with a row cap, a timeout and an
+// in-memory history of executed and saved queries.
+use super::DatasetKind;
+#[cfg(feature = "server")]
+use crate::dbinst::{registry, sql};
+use crate::ops::multi_type_mat::MyMmMatrix;
+use crate::tables::grid::DataGrid;
+use chrono::{DateTime, Utc};
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_ROW_CAP: u32 = 1_000;
+pub const MAX_ROW_CAP: u32 = 100_000;
+pub const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+/// Statements the console accepts; anything else (DDL, DML, COPY, ATTACH, SET...) is rejected.
+const READ_ONLY_PREFIXES: &[&str] = &["select", "with", "from", "values", "describe", "summarize", "show"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConsoleResult {
+    pub matrix: MyMmMatrix,
+    /// True when the query returned more rows than the cap and was cut off.
+    pub truncated: bool,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedQuery {
+    /// Set for queries saved explicitly; `None` for plain history entries.
+    pub name: Option<String>,
+    pub sql: String,
+    pub dataset: DatasetKind,
+    pub ran_at: DateTime<Utc>,
+    pub rows: Option<usize>,
+    pub error: Option<String>,
+}
+
+impl SavedQuery {
+    pub fn outcome(&self) -> String {
+        match (&self.error, self.rows) {
+            (Some(e), _) => format!("error: {e}"),
+            (None, Some(n)) => format!("{n} rows"),
+            (None, None) => "saved".to_string(),
+        }
+    }
+}
+
+/// Trim a trailing `;` and reject multiple statements or anything that is not a query.
+pub fn check_read_only(query: &str) -> Result<String, String> {
+    let q = query.trim().trim_end_matches(';').trim();
+    if q.is_empty() {
+        return Err("empty query".to_string());
+    }
+    if q.contains(';') {
+        return Err("only a single statement is allowed".to_string());
+    }
+    let first = q
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if !READ_ONLY_PREFIXES.contains(&first.as_str()) {
+        return Err(format!("'{first}' statements are not allowed in the read-only console"));
+    }
+    Ok(q.to_string())
+}
+
+#[cfg(feature = "server")]
+mod history {
+    use super::SavedQuery;
+    use std::sync::Mutex;
+
+    const HISTORY_LEN: usize = 100;
+
+    static HISTORY: Mutex<Vec<SavedQuery>> = Mutex::new(Vec::new());
+
+    /// Record a query; unnamed entries beyond `HISTORY_LEN` are dropped oldest first.
+    pub fn push(entry: SavedQuery) {
+        let mut h = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+        h.push(entry);
+        while h.iter().filter(|q| q.name.is_none()).count() > HISTORY_LEN {
+            if let Some(i) = h.iter().position(|q| q.name.is_none()) {
+                h.remove(i);
+            }
+        }
+    }
+
+    pub fn list() -> Vec<SavedQuery> {
+        let h = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+        h.iter().rev().cloned().collect()
+    }
+}
+
+/// Polars types `MyMmMatrix` can't hold directly (dates, timestamps, booleans, lists...) as text.
+#[cfg(feature = "server")]
+fn stringify_non_numeric(df: &mut polars::prelude::DataFrame) -> polars::prelude::PolarsResult<()> {
+    use polars::prelude::*;
+    let names: Vec<PlSmallStr> = df
+        .schema()
+        .iter()
+        .filter(|(_, dt)| !(dt.is_primitive_numeric() || matches!(dt, DataType::String)))
+        .map(|(name, _)| name.clone())
+        .collect();
+    for name in names {
+        df.try_apply(name.as_str(), |s| s.cast(&DataType::String))?;
+    }
+    Ok(())
+}
+
+/// A second connection to a dataset's instance for console queries. File, network and extension
+/// access are switched off and the configuration is locked, so a query can only read the tables
+/// already loaded; `read_csv`, `read_text`, `read_parquet('s3://...')` and the like fail.
+/// The settings hold for the whole instance, which only needs external access while loading.
+#[cfg(feature = "server")]
+fn sandboxed(conn: &duckdb::Connection) -> Result<duckdb::Connection, String> {
+    let console = conn.try_clone().map_err(|e| e.to_string())?;
+    let locked: bool = console
+        .query_row("SELECT current_setting('lock_configuration')", [], |r| r.get(0))
+        .map_err(|e| e.to_string())?;
+    if !locked {
+        console
+            .execute_batch(
+                "SET autoinstall_known_extensions = false;
+                 SET autoload_known_extensions = false;
+                 SET enable_external_access = false;
+                 SET lock_configuration = true;",
+            )
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(console)
+}
+
+/// Run a checked query, fetching one row past `cap` so truncation can be detected.
+#[cfg(feature = "server")]
+fn query_capped(
+    console: &duckdb::Connection,
+    query: &str,
+    cap: usize,
+) -> Result<polars::prelude::DataFrame, String> {
+    // Wrapping in a subquery also keeps DuckDB from accepting anything but a query.
+    let wrapped = format!("SELECT * FROM ({query}) AS console_q LIMIT {}", cap + 1);
+    sql::query_polars(console, wrapped.as_str(), []).map_err(|e| e.to_string())
+}
+
+/// Run a read-only query against a loaded dataset. At most `row_cap` rows come back and the
+/// query is interrupted after `timeout_ms`.
+#[server]
+pub async fn run_console_query(
+    dataset: DatasetKind,
+    query: String,
+    row_cap: Option<u32>,
+    timeout_ms: Option<u64>,
+) -> Result<ConsoleResult, ServerFnError> {
+    let cap = row_cap.unwrap_or(DEFAULT_ROW_CAP).clamp(1, MAX_ROW_CAP) as usize;
+    let timeout = std::time::Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+    let mut entry = SavedQuery {
+        name: None,
+        sql: query.clone(),
+        dataset,
+        ran_at: Utc::now(),
+        rows: None,
+        error: None,
+    };
+    let outcome = async {
+        let q = check_read_only(query.as_str()).map_err(ServerFnError::new)?;
+        let conn = registry::get(dataset).await.ok_or_else(|| {
+            ServerFnError::new(format!("{} is not loaded", dataset.as_str()))
+        })?;
+        let console = {
+            let conn = conn.lock().map_err(|e| ServerFnError::new(e.to_string()))?;
+            sandboxed(&conn).map_err(ServerFnError::new)?
+        };
+        let interrupt = console.interrupt_handle();
+        let started = std::time::Instant::now();
+        let task =
+            tokio::task::spawn_blocking(move || query_capped(&console, q.as_str(), cap));
+        let mut df = match tokio::time::timeout(timeout, task).await {
+            Ok(joined) => joined
+                .map_err(|e| ServerFnError::new(e.to_string()))?
+                .map_err(ServerFnError::new)?,
+            Err(_) => {
+                interrupt.interrupt();
+                return Err(ServerFnError::new(format!(
+                    "query timed out after {} ms",
+                    timeout.as_millis()
+                )));
+            }
+        };
+        let truncated = df.height() > cap;
+        if truncated {
+            df = df.head(Some(cap));
+        }
+        stringify_non_numeric(&mut df).map_err(|e| ServerFnError::new(e.to_string()))?;
+        let matrix = MyMmMatrix::from_polars_dataframe(&mut df)
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        Ok(ConsoleResult {
+            matrix,
+            truncated,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+    .await;
+    match &outcome {
+        Ok(res) => entry.rows = Some(res.matrix.nrows()),
+        Err(e) => entry.error = Some(e.to_string()),
+    }
+    history::push(entry);
+    outcome
+}
+
+/// Executed and saved queries, newest first.
+#[server]
+pub async fn console_history() -> Result<Vec<SavedQuery>, ServerFnError> {
+    Ok(history::list())
+}
+
+#[server]
+pub async fn save_console_query(
+    name: String,
+    dataset: DatasetKind,
+    query: String,
+) -> Result<(), ServerFnError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(ServerFnError::new("a saved query needs a name"));
+    }
+    check_read_only(query.as_str()).map_err(ServerFnError::new)?;
+    history::push(SavedQuery {
+        name: Some(name),
+        sql: query,
+        dataset,
+        ran_at: Utc::now(),
+        rows: None,
+        error: None,
+    });
+    Ok(())
+}
+
+/// Percent-encode for a `data:` URL.
+fn url_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+#[component]
+pub fn SqlConsole() -> Element {
+    let mut dataset = use_signal(|| DatasetKind::FactorsMonthly);
+    let mut query = use_signal(|| {
+        String::from("SELECT table_name, estimated_size, column_count FROM duckdb_tables()")
+    });
+    let mut row_cap = use_signal(|| DEFAULT_ROW_CAP);
+    let mut timeout_ms = use_signal(|| DEFAULT_TIMEOUT_MS);
+    let mut save_name = use_signal(String::new);
+    let mut result = use_signal(|| None::<Result<ConsoleResult, String>>);
+    let mut running = use_signal(|| false);
+    let mut history_rev = use_signal(|| 0);
+    let history = use_resource(move || async move {
+        let _ = history_rev();
+        console_history().await.unwrap_or_default()
+    });
+
+    let run = move |_| {
+        running.set(true);
+        spawn(async move {
+            let res = run_console_query(dataset(), query(), Some(row_cap()), Some(timeout_ms())).await;
+            result.set(Some(res.map_err(|e| e.to_string())));
+            running.set(false);
+            *history_rev.write() += 1;
+        });
+    };
+    let save = move |_| {
+        spawn(async move {
+            match save_console_query(save_name(), dataset(), query()).await {
+                Ok(()) => save_name.set(String::new()),
+                Err(e) => result.set(Some(Err(e.to_string()))),
+            }
+            *history_rev.write() += 1;
+        });
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "multi-card-div",
+                label { "Dataset" }
+                select {
+                    onchange: move |evt| {
+                        if let Some(kind) = DatasetKind::ALL.into_iter().find(|k| k.as_str() == evt.value()) {
+                            dataset.set(kind);
+                        }
+                    },
+                    for kind in DatasetKind::ALL {
+                        option { value: "{kind.as_str()}", selected: kind == dataset(), "{kind.as_str()}" }
+                    }
+                }
+                label { "Row cap" }
+                input {
+                    r#type: "number",
+                    min: 1,
+                    max: MAX_ROW_CAP as i64,
+                    value: "{row_cap()}",
+                    oninput: move |evt| {
+                        if let Ok(v) = evt.value().parse::<u32>() {
+                            row_cap.set(v);
+                        }
+                    }
+                }
+                label { "Timeout (ms)" }
+                input {
+                    r#type: "number",
+                    min: 100,
+                    value: "{timeout_ms()}",
+                    oninput: move |evt| {
+                        if let Ok(v) = evt.value().parse::<u64>() {
+                            timeout_ms.set(v);
+                        }
+                    }
+                }
+            }
+            div { class: "multi-card-div",
+                textarea {
+                    rows: 6,
+                    cols: 80,
+                    value: "{query()}",
+                    oninput: move |evt| query.set(evt.value()),
+                }
+                div {
+                    button { class: "ma-btn", disabled: running(), onclick: run, "Run" }
+                    input {
+                        r#type: "text",
+                        placeholder: "name",
+                        value: "{save_name()}",
+                        oninput: move |evt| save_name.set(evt.value()),
+                    }
+                    button { class: "ma-btn", onclick: save, "Save query" }
+                }
+            }
+        }
+        match result() {
+            Some(Ok(res)) => rsx! {
+                p {
+                    "{res.matrix.nrows()} rows in {res.elapsed_ms} ms"
+                    if res.truncated { " (truncated at the row cap)" }
+                    " "
+                    a {
+                        href: "data:text/csv;charset=utf-8,{url_encode(&res.matrix.to_csv())}",
+                        download: "query.csv",
+                        "Export CSV"
+                    }
+                }
+                DataGrid { mat: res.matrix.clone() }
+            },
+            Some(Err(e)) => rsx! { div { class: "error", "{e}" } },
+            None => rsx! {},
+        }
+        if let Some(entries) = history() {
+            if !entries.is_empty() {
+                table { class: "trade-table",
+                    thead {
+                        tr {
+                            th { "Saved / recent queries" }
+                            th { "Dataset" }
+                            th { "Ran at" }
+                            th { "Result" }
+                        }
+                    }
+                    tbody {
+                        for entry in entries {
+                            tr { class: "ind-trade",
+                                td {
+                                    a {
+                                        href: "#",
+                                        onclick: {
+                                            let sql = entry.sql.clone();
+                                            let kind = entry.dataset;
+                                            move |evt: MouseEvent| {
+                                                evt.prevent_default();
+                                                query.set(sql.clone());
+                                                dataset.set(kind);
+                                            }
+                                        },
+                                        if let Some(name) = &entry.name {
+                                            b { "{name}: " }
+                                        }
+                                        "{entry.sql}"
+                                    }
+                                }
+                                td { "{entry.dataset.as_str()}" }
+                                td { "{entry.ran_at.to_rfc3339()}" }
+                                td { "{entry.outcome()}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_single_queries_pass_the_read_only_check() {
+        assert_eq!(check_read_only("  SELECT 1; ").unwrap(), "SELECT 1");
+        assert!(check_read_only("with t as (select 1) select * from t").is_ok());
+        assert!(check_read_only("").is_err());
+        assert!(check_read_only("select 1; drop table x").is_err());
+        for q in ["COPY t TO 'out.csv'", "ATTACH 'x.db'", "SET threads = 1", "INSTALL httpfs"] {
+            assert!(check_read_only(q).is_err(), "{q}");
+        }
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn console_connection_cannot_reach_files_or_the_network() {
+        let conn = duckdb::Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE factors AS SELECT range AS id FROM range(5)")
+            .unwrap();
+        let console = sandboxed(&conn).unwrap();
+        let df = query_capped(&console, "SELECT * FROM factors", 3).unwrap();
+        assert_eq!(df.height(), 4);
+        for q in [
+            "SELECT * FROM read_text('/etc/passwd')",
+            "SELECT * FROM read_csv('/etc/hosts')",
+            "SELECT * FROM read_parquet('s3://bucket/data.parquet')",
+            "SELECT * FROM read_json('https://example.com/data.json')",
+            "SELECT * FROM glob('/*')",
+        ] {
+            let q = check_read_only(q).unwrap();
+            assert!(query_capped(&console, q.as_str(), 10).is_err(), "{q}");
+        }
+        // The configuration is locked, for this and every later console connection.
+        assert!(console.execute_batch("SET enable_external_access = true").is_err());
+        assert!(sandboxed(&conn).is_ok());
+    }
+}
@@ -0,0 +1,387 @@
+// Named DuckDB datasets (factors, fundamentals, headers) held server-side, plus the server
+// functions and panel used to load, reload, unload and list them.
+pub mod console;
+pub mod panel;
+pub mod screener;
+
+#[cfg(feature = "server")]
+use crate::dbinst::{self, registry, sql, SharedDuck};
+#[cfg(feature = "server")]
+use crate::helpers::paths;
+use chrono::{DateTime, NaiveDate, Utc};
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DatasetKind {
+    /// JKP global factors, one row per security and month.
+    FactorsMonthly,
+    /// Compustat-style fundamentals.
+    Fundamentals,
+    /// Security headers (identifiers, exchange, country).
+    Headers,
+}
+
+impl DatasetKind {
+    pub const ALL: [DatasetKind; 3] = [
+        DatasetKind::FactorsMonthly,
+        DatasetKind::Fundamentals,
+        DatasetKind::Headers,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DatasetKind::FactorsMonthly => "Factors (monthly)",
+            DatasetKind::Fundamentals => "Fundamentals",
+            DatasetKind::Headers => "Headers",
+        }
+    }
+
+    /// DuckDB table the dataset is loaded into.
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            DatasetKind::FactorsMonthly => "equity_factors_monthly",
+            DatasetKind::Fundamentals => "fundamentals",
+            DatasetKind::Headers => "headers",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum DatasetStatus {
+    #[default]
+    Unloaded,
+    Loading,
+    Loaded,
+    Failed(String),
+}
+
+impl DatasetStatus {
+    pub fn label(&self) -> String {
+        match self {
+            DatasetStatus::Unloaded => "unloaded".to_string(),
+            DatasetStatus::Loading => "loading".to_string(),
+            DatasetStatus::Loaded => "loaded".to_string(),
+            DatasetStatus::Failed(e) => format!("failed: {e}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatasetInfo {
+    pub kind: DatasetKind,
+    /// Source parquet path; kept after unloading so the dataset can be reloaded.
+    pub path: Option<String>,
+    pub status: DatasetStatus,
+    pub rows: u64,
+    pub mem_bytes: u64,
+    pub loaded_at: Option<DateTime<Utc>>,
+}
+
+impl DatasetInfo {
+    pub fn unloaded(kind: DatasetKind) -> Self {
+        DatasetInfo {
+            kind,
+            path: None,
+            status: DatasetStatus::Unloaded,
+            rows: 0,
+            mem_bytes: 0,
+            loaded_at: None,
+        }
+    }
+}
+
+/// Environment variable naming the directory dataset paths are resolved in (default `data`).
+pub const DATASET_DIR_ENV: &str = "DATASET_DIR";
+
+/// Canonical form of a client-supplied dataset path, confined to the dataset directory. An
+/// empty path stays empty (words_db then uses its configured factors file).
+#[cfg(feature = "server")]
+fn resolve_source(path: &str) -> Result<String, ServerFnError> {
+    if path.trim().is_empty() {
+        return Ok(String::new());
+    }
+    let root = paths::root_dir(DATASET_DIR_ENV, "data");
+    paths::confine(&root, path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .map_err(ServerFnError::new)
+}
+
+/// Load `path` into a new instance and swap it into the registry, replacing any previous one.
+/// The registry's path and connection only change once the load has succeeded. Callers hold
+/// `registry::lock_loads`.
+#[cfg(feature = "server")]
+async fn load_into_registry(
+    kind: DatasetKind,
+    path: String,
+) -> Result<(DatasetInfo, SharedDuck), ServerFnError> {
+    tracing::debug!("loading {} from {path}", kind.as_str());
+    registry::set_loading(kind).await;
+    match dbinst::open(kind, path.as_str()).await {
+        Ok(conn) => {
+            let (rows, mem_bytes) = dbinst::measure(&conn);
+            let info = DatasetInfo {
+                kind,
+                path: Some(path),
+                status: DatasetStatus::Loaded,
+                rows,
+                mem_bytes,
+                loaded_at: Some(Utc::now()),
+            };
+            registry::insert(info.clone(), conn.clone()).await;
+            Ok((info, conn))
+        }
+        Err(e) => {
+            registry::set_failed(kind, e.to_string()).await;
+            Err(e)
+        }
+    }
+}
+
+/// Connection for `kind`, loading it first if nothing is loaded or `path` names a different
+/// source than the loaded one. An empty `path` accepts whatever is already loaded.
+#[cfg(feature = "server")]
+pub async fn ensure_loaded(kind: DatasetKind, path: &str) -> Result<SharedDuck, ServerFnError> {
+    let path = resolve_source(path)?;
+    let _guard = registry::lock_loads().await;
+    let info = registry::info(kind).await;
+    if let Some(conn) = registry::get(kind).await {
+        if path.is_empty() || info.path.as_deref() == Some(path.as_str()) {
+            tracing::debug!("{} already loaded, no need to rebuild", kind.as_str());
+            return Ok(conn);
+        }
+        tracing::debug!("{} path changed, reloading", kind.as_str());
+    }
+    Ok(load_into_registry(kind, path).await?.1)
+}
+
+#[server]
+pub async fn list_datasets() -> Result<Vec<DatasetInfo>, ServerFnError> {
+    Ok(registry::list().await)
+}
+
+#[server]
+pub async fn load_dataset(kind: DatasetKind, path: String) -> Result<DatasetInfo, ServerFnError> {
+    let path = resolve_source(path.as_str())?;
+    let _guard = registry::lock_loads().await;
+    Ok(load_into_registry(kind, path).await?.0)
+}
+
+/// Re-read the dataset from the path it was last loaded from.
+#[server]
+pub async fn reload_dataset(kind: DatasetKind) -> Result<DatasetInfo, ServerFnError> {
+    let _guard = registry::lock_loads().await;
+    let Some(path) = registry::info(kind).await.path else {
+        return Err(ServerFnError::new(format!(
+            "{} has never been loaded",
+            kind.as_str()
+        )));
+    };
+    Ok(load_into_registry(kind, path).await?.0)
+}
+
+#[server]
+pub async fn unload_dataset(kind: DatasetKind) -> Result<DatasetInfo, ServerFnError> {
+    let _guard = registry::lock_loads().await;
+    Ok(registry::unload(kind).await)
+}
+
+/// Numeric columns in the JKP file that identify or classify a security rather than measure it.
+pub const ID_COLUMNS: &[&str] = &[
+    "permno", "permco", "gvkey", "iid", "id", "obs_main", "exch_main", "common", "primary_sec",
+    "gics", "naics", "sic", "ff49", "crsp_shrcd", "crsp_exchcd", "comp_exchg", "comp_tpci",
+    "source_crsp", "size_grp", "curcd", "excntry", "date", "eom",
+];
+
+/// Run `f` against the factors table (loading `path` first if needed) on a blocking thread.
+#[cfg(feature = "server")]
+async fn with_factors_table<T, F>(path: String, f: F) -> Result<T, ServerFnError>
+where
+    T: Send + 'static,
+    F: FnOnce(&duckdb::Connection, &str) -> Result<T, String> + Send + 'static,
+{
+    let conn = ensure_loaded(DatasetKind::FactorsMonthly, path.as_str()).await?;
+    tokio::task::spawn_blocking(move || {
+        let conn = conn.lock().map_err(|e| e.to_string())?;
+        let table = sql::resolve_table(&conn, DatasetKind::FactorsMonthly)?;
+        f(&conn, table.as_str())
+    })
+    .await
+    .map_err(|e| ServerFnError::new(e.to_string()))?
+    .map_err(ServerFnError::new)
+}
+
+/// What the Multi-Asset pickers need to know about the factors dataset.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct FactorSchema {
+    /// `excntry` values with their number of distinct securities.
+    pub countries: Vec<(String, u64)>,
+    /// First and last `date`.
+    pub date_bounds: Option<(NaiveDate, NaiveDate)>,
+    /// Numeric columns, excluding the identifier columns in `ID_COLUMNS`.
+    pub columns: Vec<String>,
+}
+
+/// Countries, date range and factor columns of the factors dataset, read in one round trip.
+#[server]
+pub async fn factor_schema(path: String) -> Result<FactorSchema, ServerFnError> {
+    with_factors_table(path, |conn, table| {
+        let quoted = sql::quote_ident(table)?;
+        Ok(FactorSchema {
+            countries: factor_countries(conn, quoted.as_str())?,
+            date_bounds: factor_date_bounds(conn, quoted.as_str())?,
+            columns: factor_columns(conn, table)?,
+        })
+    })
+    .await
+}
+
+#[cfg(feature = "server")]
+fn factor_countries(conn: &duckdb::Connection, table: &str) -> Result<Vec<(String, u64)>, String> {
+    let query = format!(
+        "SELECT CAST(excntry AS VARCHAR), count(DISTINCT gvkey) FROM {table} WHERE excntry IS NOT NULL GROUP BY 1 ORDER BY 1"
+    );
+    let mut stmt = conn.prepare(query.as_str()).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?.max(0) as u64)))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "server")]
+fn factor_date_bounds(
+    conn: &duckdb::Connection,
+    table: &str,
+) -> Result<Option<(NaiveDate, NaiveDate)>, String> {
+    let query =
+        format!("SELECT CAST(min(date) AS VARCHAR), CAST(max(date) AS VARCHAR) FROM {table}");
+    let (lo, hi) = conn
+        .query_row(query.as_str(), [], |r| {
+            Ok((r.get::<_, Option<String>>(0)?, r.get::<_, Option<String>>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    let parse =
+        |s: Option<String>| s.and_then(|s| NaiveDate::parse_from_str(s.get(..10)?, "%Y-%m-%d").ok());
+    Ok(parse(lo).zip(parse(hi)))
+}
+
+#[cfg(feature = "server")]
+fn factor_columns(conn: &duckdb::Connection, table: &str) -> Result<Vec<String>, String> {
+    let numeric = |ty: &str| {
+        matches!(
+            ty,
+            "TINYINT" | "SMALLINT" | "INTEGER" | "BIGINT" | "HUGEINT" | "UTINYINT" | "USMALLINT"
+                | "UINTEGER" | "UBIGINT" | "FLOAT" | "DOUBLE" | "REAL"
+        ) || ty.starts_with("DECIMAL")
+    };
+    Ok(sql::table_columns(conn, table)?
+        .into_iter()
+        .filter(|(name, ty)| numeric(ty.as_str()) && !ID_COLUMNS.contains(&name.as_str()))
+        .map(|(name, _)| name)
+        .collect())
+}
+
+fn format_bytes(bytes: u64) -> String {
+    let mb = bytes as f64 / (1024.0 * 1024.0);
+    if mb >= 1024.0 {
+        format!("{:.2} GB", mb / 1024.0)
+    } else {
+        format!("{mb:.1} MB")
+    }
+}
+
+/// Table of registered datasets with a path input and load/reload/unload actions per row.
+#[component]
+pub fn DatasetsPanel() -> Element {
+    let mut refresh = use_signal(|| 0);
+    let mut error = use_signal(|| None::<String>);
+    let mut paths = use_signal(|| vec![String::new(); DatasetKind::ALL.len()]);
+    let datasets = use_resource(move || async move {
+        let _ = refresh();
+        list_datasets().await
+    });
+    let rows: Vec<DatasetInfo> = match &*datasets.read() {
+        Some(Ok(list)) => list.clone(),
+        _ => DatasetKind::ALL.map(DatasetInfo::unloaded).to_vec(),
+    };
+
+    rsx! {
+        table { class: "trade-table",
+            thead {
+                tr {
+                    th { "Dataset" }
+                    th { "Path" }
+                    th { "Status" }
+                    th { "Rows" }
+                    th { "Memory" }
+                    th { "" }
+                }
+            }
+            tbody {
+                for (i, info) in rows.into_iter().enumerate() {
+                    tr { class: "ind-trade",
+                        td { "{info.kind.as_str()}" }
+                        td {
+                            input {
+                                r#type: "text",
+                                placeholder: "{info.path.clone().unwrap_or_default()}",
+                                value: "{paths.read()[i]}",
+                                oninput: move |e| paths.write()[i] = e.value(),
+                            }
+                        }
+                        td { "{info.status.label()}" }
+                        td { "{info.rows}" }
+                        td { "{format_bytes(info.mem_bytes)}" }
+                        td {
+                            button {
+                                class: "ma-btn",
+                                onclick: move |_| {
+                                    let kind = info.kind;
+                                    let path = paths.read()[i].clone();
+                                    spawn(async move {
+                                        match load_dataset(kind, path).await {
+                                            Ok(_) => error.set(None),
+                                            Err(e) => error.set(Some(e.to_string())),
+                                        }
+                                        *refresh.write() += 1;
+                                    });
+                                },
+                                "Load"
+                            }
+                            button {
+                                class: "ma-btn",
+                                onclick: move |_| {
+                                    let kind = info.kind;
+                                    spawn(async move {
+                                        match reload_dataset(kind).await {
+                                            Ok(_) => error.set(None),
+                                            Err(e) => error.set(Some(e.to_string())),
+                                        }
+                                        *refresh.write() += 1;
+                                    });
+                                },
+                                "Reload"
+                            }
+                            button {
+                                class: "ma-btn",
+                                onclick: move |_| {
+                                    let kind = info.kind;
+                                    spawn(async move {
+                                        if let Err(e) = unload_dataset(kind).await {
+                                            error.set(Some(e.to_string()));
+                                        }
+                                        *refresh.write() += 1;
+                                    });
+                                },
+                                "Unload"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(e) = error() {
+            p { class: "error", "{e}" }
+        }
+    }
+}
@@ -0,0 +1,187 @@
+#![cfg(feature = "server")]
+// Firm-month panels sliced out of the factors dataset, shared by the Multi-Asset tables and
+// the research tools.
+use super::{ensure_loaded, DatasetKind, ID_COLUMNS};
+use crate::dbinst::sql;
+use crate::helpers::formula::{self, DerivedFactor};
+use crate::helpers::prep::{FactorPrep, PREP_SKIP};
+use chrono::NaiveDate;
+use dioxus::prelude::ServerFnError;
+use duckdb::Connection;
+use polars::prelude::*;
+use std::sync::{Arc, Mutex};
+
+/// Country/date/security filters and the column list are pushed into the DuckDB query,
+/// so only the requested slice of the global file is materialized.
+pub async fn query_factors_range(
+    conn: Arc<Mutex<Connection>>,
+    countries: &[String],
+    factors: Vec<String>,
+    securities: Option<Vec<String>>,
+    start_iso: NaiveDate,
+    end_iso: NaiveDate,
+) -> PolarsResult<DataFrame> {
+    if countries.is_empty() {
+        return Err(PolarsError::ComputeError("no country selected".into()));
+    }
+    let countries = countries.to_vec();
+    tokio::task::spawn_blocking(move || {
+        let conn = conn
+            .lock()
+            .map_err(|e| PolarsError::ComputeError(format!("lock: {e}").into()))?;
+        let table = sql::resolve_table(&conn, DatasetKind::FactorsMonthly)
+            .map_err(|e| PolarsError::ComputeError(e.into()))?;
+        let known: Vec<String> = sql::table_columns(&conn, table.as_str())
+            .map_err(|e| PolarsError::ComputeError(e.into()))?
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        let select = factors
+            .iter()
+            .map(|c| {
+                if known.contains(c) {
+                    sql::quote_ident(c.as_str())
+                } else {
+                    Err(format!("unknown factor column '{c}'"))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| PolarsError::ColumnNotFound(e.into()))?
+            .join(", ");
+
+        let country_marks = vec!["?"; countries.len()].join(", ");
+        let mut params: Vec<String> = countries;
+        params.extend([start_iso.to_string(), end_iso.to_string()]);
+        let mut query = format!(
+            "SELECT {select} FROM {} WHERE excntry IN ({country_marks}) AND date BETWEEN CAST(? AS DATE) AND CAST(? AS DATE)",
+            sql::quote_ident(table.as_str()).map_err(|e| PolarsError::ComputeError(e.into()))?
+        );
+        if let Some(ids) = securities.filter(|ids| !ids.is_empty()) {
+            let marks = vec!["?"; ids.len()].join(", ");
+            query.push_str(&format!(" AND CAST(gvkey AS VARCHAR) IN ({marks})"));
+            params.extend(ids);
+        }
+        query.push_str(" ORDER BY excntry, gvkey, iid, date");
+        tracing::debug!("factors query: {query}");
+        let fac_df = sql::query_polars(&conn, query.as_str(), duckdb::params_from_iter(params))?;
+        tracing::debug!("shape: {:?}", &fac_df.shape());
+        Ok(fac_df)
+    })
+    .await
+    .map_err(|e| PolarsError::ComputeError(format!("join: {e}").into()))?
+}
+
+/// Query `factors` for the given countries and date range, then run `prep` per `date` (per
+/// `date` and `excntry` with several countries) on the measured (non-identifier) columns. Each
+/// column in `keep_raw` is also returned untouched as `<name>_raw` (e.g. `dolvol` used as a
+/// portfolio weight). Requested names matching a `derived` factor are computed from their
+/// formula's columns before `prep`, so they are preprocessed like native columns.
+#[allow(clippy::too_many_arguments)]
+pub async fn load_factor_panel(
+    countries: &[String],
+    factors: Vec<String>,
+    derived: &[DerivedFactor],
+    start_iso: NaiveDate,
+    end_iso: NaiveDate,
+    factors_path: &str,
+    securities: Option<Vec<String>>,
+    prep: FactorPrep,
+    keep_raw: &[&str],
+) -> Result<DataFrame, ServerFnError> {
+    prep.validate().map_err(ServerFnError::new)?;
+    let conn = ensure_loaded(DatasetKind::FactorsMonthly, factors_path).await?;
+    // Cross-sections are per country when several are mixed.
+    let mut cross_section = vec!["date".to_string()];
+    if countries.len() > 1 {
+        cross_section.push("excntry".to_string());
+    }
+    // Derived columns are not in the file; fetch what their formulas read instead.
+    let mut computed: Vec<Expr> = Vec::new();
+    let mut inputs: Vec<String> = Vec::new();
+    let mut factors = factors;
+    let requested: Vec<&DerivedFactor> = derived
+        .iter()
+        .filter(|d| factors.contains(&d.name))
+        .collect();
+    for (i, d) in requested.iter().enumerate() {
+        if requested[..i].iter().any(|e| e.name == d.name) {
+            return Err(ServerFnError::new(format!(
+                "derived factor '{}' is defined more than once",
+                d.name
+            )));
+        }
+    }
+    // A security is a gvkey/iid pair; `lag` must not reach into another share class.
+    let security = vec!["gvkey".to_string(), "iid".to_string()];
+    for d in requested {
+        let ast = formula::parse(&d.formula)
+            .map_err(|e| ServerFnError::new(format!("{}: {e}", d.name)))?;
+        if ast.uses_lag() {
+            inputs.extend(security.iter().cloned());
+        }
+        inputs.extend(ast.columns());
+        computed.push(
+            ast.to_expr(&cross_section, &security)
+                .alias(d.name.as_str()),
+        );
+        factors.retain(|f| *f != d.name);
+    }
+    // Grouping and formula input columns are needed even when they were not requested for
+    // display.
+    let mut extra: Vec<String> = Vec::new();
+    for g in cross_section
+        .iter()
+        .cloned()
+        .chain(prep.neutralize.clone())
+        .chain(inputs)
+    {
+        if !factors.contains(&g) && !extra.contains(&g) {
+            extra.push(g);
+        }
+    }
+    factors.extend(extra.iter().cloned());
+    tracing::debug!("Factors sub-sample");
+    let mut df = query_factors_range(conn, countries, factors, securities, start_iso, end_iso)
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    if !computed.is_empty() {
+        df = df
+            .lazy()
+            .with_columns(computed)
+            .collect()
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+    }
+    if !keep_raw.is_empty() {
+        let copies: Vec<Expr> = keep_raw
+            .iter()
+            .map(|c| col(*c).alias(format!("{c}_raw")))
+            .collect();
+        df = df
+            .lazy()
+            .with_columns(copies)
+            .collect()
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+    }
+    if prep.is_noop() {
+        return Ok(df.drop_many(extra.iter().map(|c| c.as_str())));
+    }
+    let prep_cols: Vec<String> = df
+        .schema()
+        .iter()
+        .filter(|(name, dtype)| {
+            dtype.is_primitive_numeric()
+                && !ID_COLUMNS.contains(&name.as_str())
+                && !PREP_SKIP.contains(&name.as_str())
+                && !keep_raw.iter().any(|c| name.as_str() == format!("{c}_raw"))
+                && prep.neutralize.as_deref() != Some(name.as_str())
+        })
+        .map(|(name, _)| name.to_string())
+        .collect();
+    let mut lf = prep
+        .apply(df.lazy(), &cross_section, prep_cols)
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    if !extra.is_empty() {
+        lf = lf.drop(cols(extra.iter().map(|c| c.as_str())));
+    }
+    lf.collect().map_err(|e| ServerFnError::new(e.to_string()))
+}
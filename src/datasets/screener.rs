@@ -0,0 +1,649 @@
+// Security screener: threshold and percentile rules over the latest factors cross-section of a
+// country, evaluated in DuckDB, plus an in-memory list of saved screens.
+#[cfg(feature = "server")]
+use crate::dbinst::sql;
+use crate::ops::multi_type_mat::MyMmMatrix;
+use crate::tables::grid::DataGrid;
+use crate::views::portfolio::{Universe, UniverseCtx};
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub const DEFAULT_LIMIT: u32 = 100;
+pub const MAX_LIMIT: u32 = 5_000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScreenOp {
+    #[default]
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    /// Within the top `value` percent of the cross-section.
+    TopPct,
+    /// Within the bottom `value` percent of the cross-section.
+    BottomPct,
+}
+
+impl ScreenOp {
+    pub const ALL: [ScreenOp; 6] = [
+        ScreenOp::Gt,
+        ScreenOp::Ge,
+        ScreenOp::Lt,
+        ScreenOp::Le,
+        ScreenOp::TopPct,
+        ScreenOp::BottomPct,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScreenOp::Gt => ">",
+            ScreenOp::Ge => ">=",
+            ScreenOp::Lt => "<",
+            ScreenOp::Le => "<=",
+            ScreenOp::TopPct => "top %",
+            ScreenOp::BottomPct => "bottom %",
+        }
+    }
+
+    pub fn from_label(s: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|o| o.as_str() == s)
+            .unwrap_or_default()
+    }
+
+    pub fn is_percentile(&self) -> bool {
+        matches!(self, ScreenOp::TopPct | ScreenOp::BottomPct)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScreenRule {
+    pub column: String,
+    pub op: ScreenOp,
+    /// Threshold, or a percentage in (0, 100] for the percentile operators.
+    pub value: f64,
+}
+
+impl fmt::Display for ScreenRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.op {
+            ScreenOp::TopPct => write!(f, "{} in top {}%", self.column, self.value),
+            ScreenOp::BottomPct => write!(f, "{} in bottom {}%", self.column, self.value),
+            op => write!(f, "{} {} {}", self.column, op.as_str(), self.value),
+        }
+    }
+}
+
+/// A screen: every rule must hold (AND). Matches are ranked by `rank_by`, highest first unless
+/// `ascending`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Screen {
+    pub name: Option<String>,
+    pub country: String,
+    pub rules: Vec<ScreenRule>,
+    pub rank_by: Option<String>,
+    pub ascending: bool,
+    pub limit: u32,
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Screen {
+            name: None,
+            country: String::new(),
+            rules: Vec::new(),
+            rank_by: None,
+            ascending: false,
+            limit: DEFAULT_LIMIT,
+        }
+    }
+}
+
+impl Screen {
+    /// The rules as one line, e.g. `ret_3_1 > 0.05 AND rvolhl_21d in bottom 30%`.
+    pub fn describe(&self) -> String {
+        if self.rules.is_empty() {
+            return "all securities".to_string();
+        }
+        self.rules
+            .iter()
+            .map(|r| r.to_string())
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.country.trim().is_empty() {
+            return Err("a screen needs a country".to_string());
+        }
+        for r in &self.rules {
+            if !r.value.is_finite() {
+                return Err(format!("{}: the value must be a number", r.column));
+            }
+            if r.op.is_percentile() && !(r.value > 0.0 && r.value <= 100.0) {
+                return Err(format!("{r}: the percentage must be in (0, 100]"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Columns the screen reads, in first-use order.
+    pub fn columns(&self) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        for c in self
+            .rules
+            .iter()
+            .map(|r| &r.column)
+            .chain(self.rank_by.as_ref())
+        {
+            if !out.contains(c) {
+                out.push(c.clone());
+            }
+        }
+        out
+    }
+
+    /// DuckDB query over `table`; the country is bound twice as a parameter. Columns must
+    /// already be checked against the table.
+    #[cfg(feature = "server")]
+    fn to_sql(&self, table: &str) -> Result<String, String> {
+        let t = sql::quote_ident(table)?;
+        let cols = self.columns();
+        let mut select: Vec<String> = vec![
+            "CAST(gvkey AS VARCHAR) AS gvkey".to_string(),
+            "CAST(iid AS VARCHAR) AS iid".to_string(),
+            "CAST(date AS VARCHAR) AS date".to_string(),
+        ];
+        let mut ranked: Vec<String> = vec!["*".to_string()];
+        for c in &cols {
+            let q = sql::quote_ident(c)?;
+            select.push(format!("CAST({q} AS DOUBLE) AS {q}"));
+            // Nulls get their own partition so they do not shift the percentiles.
+            ranked.push(format!(
+                "percent_rank() OVER (PARTITION BY {q} IS NULL ORDER BY {q}) AS {}",
+                sql::quote_ident(format!("{c}__pct").as_str())?
+            ));
+        }
+        let conditions: Vec<String> = self
+            .rules
+            .iter()
+            .map(|r| {
+                let q = sql::quote_ident(&r.column)?;
+                let pct = sql::quote_ident(format!("{}__pct", r.column).as_str())?;
+                let v = r.value;
+                Ok(match r.op {
+                    ScreenOp::Gt => format!("{q} > {v}"),
+                    ScreenOp::Ge => format!("{q} >= {v}"),
+                    ScreenOp::Lt => format!("{q} < {v}"),
+                    ScreenOp::Le => format!("{q} <= {v}"),
+                    ScreenOp::TopPct => format!("{q} IS NOT NULL AND {pct} >= {}", 1.0 - v / 100.0),
+                    ScreenOp::BottomPct => format!("{q} IS NOT NULL AND {pct} <= {}", v / 100.0),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+        let order = match &self.rank_by {
+            Some(c) => format!(
+                "{} {} NULLS LAST, gvkey",
+                sql::quote_ident(c)?,
+                if self.ascending { "ASC" } else { "DESC" }
+            ),
+            None => "gvkey, iid".to_string(),
+        };
+        Ok(format!(
+            "WITH latest AS (SELECT {} FROM {t} WHERE excntry = ? AND date = (SELECT max(date) FROM {t} WHERE excntry = ?)), \
+             ranked AS (SELECT {} FROM latest) \
+             SELECT {} FROM ranked{filter} ORDER BY {order} LIMIT {}",
+            select.join(", "),
+            ranked.join(", "),
+            ["gvkey", "iid", "date"]
+                .into_iter()
+                .map(String::from)
+                .chain(cols.iter().cloned())
+                .map(|c| sql::quote_ident(&c))
+                .collect::<Result<Vec<_>, _>>()?
+                .join(", "),
+            self.limit.clamp(1, MAX_LIMIT)
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScreenResult {
+    /// gvkey, iid, date and the screened columns, in rank order.
+    pub matrix: MyMmMatrix,
+    /// Cross-section date the rules were evaluated on.
+    pub date: Option<String>,
+    /// The screen that produced these matches, which the form may since have changed.
+    pub screen: Screen,
+}
+
+#[cfg(feature = "server")]
+mod saved {
+    use super::Screen;
+    use std::sync::Mutex;
+
+    static SAVED: Mutex<Vec<Screen>> = Mutex::new(Vec::new());
+
+    /// Store a named screen, replacing any screen with the same name.
+    pub fn put(screen: Screen) {
+        let mut s = SAVED.lock().unwrap_or_else(|e| e.into_inner());
+        s.retain(|x| x.name != screen.name);
+        s.push(screen);
+    }
+
+    pub fn list() -> Vec<Screen> {
+        SAVED.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+#[server]
+pub async fn run_screen(path: String, screen: Screen) -> Result<ScreenResult, ServerFnError> {
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (path, screen);
+        return Err(ServerFnError::new(
+            "run_screen requires the `server` feature",
+        ));
+    }
+
+    #[cfg(feature = "server")]
+    {
+        screen.validate().map_err(ServerFnError::new)?;
+        super::with_factors_table(path, move |conn, table| {
+            let known: Vec<String> = sql::table_columns(conn, table)?
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+            if let Some(c) = screen.columns().into_iter().find(|c| !known.contains(c)) {
+                return Err(format!("unknown factor column '{c}'"));
+            }
+            let query = screen.to_sql(table)?;
+            tracing::debug!("screen query: {query}");
+            let mut df = sql::query_polars(
+                conn,
+                query.as_str(),
+                duckdb::params![screen.country, screen.country],
+            )
+            .map_err(|e| e.to_string())?;
+            let date = df
+                .column("date")
+                .ok()
+                .and_then(|c| c.str().ok().and_then(|s| s.get(0).map(String::from)));
+            let matrix = MyMmMatrix::from_polars_dataframe(&mut df).map_err(|e| e.to_string())?;
+            Ok(ScreenResult {
+                matrix,
+                date,
+                screen,
+            })
+        })
+        .await
+    }
+}
+
+#[server]
+pub async fn save_screen(screen: Screen) -> Result<(), ServerFnError> {
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = screen;
+        return Err(ServerFnError::new(
+            "save_screen requires the `server` feature",
+        ));
+    }
+
+    #[cfg(feature = "server")]
+    {
+        if screen.name.as_deref().is_none_or(|n| n.trim().is_empty()) {
+            return Err(ServerFnError::new("a saved screen needs a name"));
+        }
+        screen.validate().map_err(ServerFnError::new)?;
+        saved::put(screen);
+        Ok(())
+    }
+}
+
+/// Saved screens, oldest first.
+#[server]
+pub async fn saved_screens() -> Result<Vec<Screen>, ServerFnError> {
+    #[cfg(not(feature = "server"))]
+    {
+        return Err(ServerFnError::new(
+            "saved_screens requires the `server` feature",
+        ));
+    }
+
+    #[cfg(feature = "server")]
+    {
+        Ok(saved::list())
+    }
+}
+
+/// `columns` lists the factor columns rules can use; `country` is the Multi-Asset selection.
+#[component]
+pub fn ScreenerPanel(path: String, country: String, columns: Vec<String>) -> Element {
+    let mut rules: Signal<Vec<ScreenRule>> = use_signal(Vec::new);
+    let mut rank_by: Signal<Option<String>> = use_signal(|| None);
+    let mut ascending = use_signal(|| false);
+    let mut limit = use_signal(|| DEFAULT_LIMIT);
+    let mut save_name = use_signal(String::new);
+    let mut result: Signal<Option<Result<ScreenResult, String>>> = use_signal(|| None);
+    let mut running = use_signal(|| false);
+    let mut sent = use_signal(|| None::<usize>);
+    let mut saved_rev = use_signal(|| 0);
+    let saved = use_resource(move || async move {
+        let _ = saved_rev();
+        saved_screens().await.unwrap_or_default()
+    });
+    let mut universe = use_context::<UniverseCtx>();
+    // A loaded saved screen brings its own country until cleared.
+    let mut loaded_country: Signal<Option<String>> = use_signal(|| None);
+    let screen_country = loaded_country().unwrap_or_else(|| country.clone());
+
+    let current = {
+        let screen_country = screen_country.clone();
+        move || Screen {
+            name: None,
+            country: screen_country.clone(),
+            rules: rules(),
+            rank_by: rank_by(),
+            ascending: ascending(),
+            limit: limit(),
+        }
+    };
+    let run = {
+        let current = current.clone();
+        let path = path.clone();
+        move |_| {
+            let screen = current();
+            let path = path.clone();
+            running.set(true);
+            spawn(async move {
+                result.set(Some(
+                    run_screen(path, screen).await.map_err(|e| e.to_string()),
+                ));
+                sent.set(None);
+                running.set(false);
+            });
+        }
+    };
+    let save = {
+        let current = current.clone();
+        move |_| {
+            let screen = Screen {
+                name: Some(save_name()),
+                ..current()
+            };
+            spawn(async move {
+                match save_screen(screen).await {
+                    Ok(()) => save_name.set(String::new()),
+                    Err(e) => result.set(Some(Err(e.to_string()))),
+                }
+                *saved_rev.write() += 1;
+            });
+        }
+    };
+    let first_column = columns.first().cloned().unwrap_or_default();
+
+    rsx! {
+        div { class: "card",
+            for (i, rule) in rules().into_iter().enumerate() {
+                div { class: "multi-card-div",
+                    if i > 0 { label { "AND" } }
+                    select {
+                        value: "{rule.column}",
+                        onchange: move |evt| rules.with_mut(|r| r[i].column = evt.value()),
+                        for c in columns.iter() {
+                            option { value: "{c}", "{c}" }
+                        }
+                    }
+                    select {
+                        value: "{rule.op.as_str()}",
+                        onchange: move |evt| rules.with_mut(|r| r[i].op = ScreenOp::from_label(&evt.value())),
+                        for op in ScreenOp::ALL {
+                            option { value: "{op.as_str()}", "{op.as_str()}" }
+                        }
+                    }
+                    input {
+                        r#type: "number",
+                        step: "any",
+                        value: "{rule.value}",
+                        oninput: move |evt| {
+                            if let Ok(v) = evt.value().parse::<f64>() {
+                                rules.with_mut(|r| r[i].value = v);
+                            }
+                        },
+                    }
+                    button {
+                        class: "ma-btn",
+                        onclick: move |_| {
+                            rules.with_mut(|r| {
+                                r.remove(i);
+                            });
+                        },
+                        "Remove"
+                    }
+                }
+            }
+            div { class: "multi-card-div",
+                button {
+                    class: "ma-btn",
+                    onclick: move |_| {
+                        let column = first_column.clone();
+                        rules.with_mut(|r| r.push(ScreenRule { column, op: ScreenOp::Gt, value: 0.0 }));
+                    },
+                    "Add rule"
+                }
+                label { "Rank by" }
+                select {
+                    value: rank_by().unwrap_or_default(),
+                    onchange: move |evt| {
+                        let v = evt.value();
+                        rank_by.set((!v.is_empty()).then_some(v));
+                    },
+                    option { value: "", "(gvkey)" }
+                    for c in columns.iter() {
+                        option { value: "{c}", "{c}" }
+                    }
+                }
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: ascending(),
+                        onchange: move |evt| ascending.set(evt.checked()),
+                    }
+                    " Ascending"
+                }
+                label { "Limit" }
+                input {
+                    r#type: "number",
+                    min: 1,
+                    max: MAX_LIMIT as i64,
+                    value: "{limit}",
+                    oninput: move |evt| {
+                        if let Ok(v) = evt.value().parse::<u32>() {
+                            limit.set(v);
+                        }
+                    },
+                }
+                button { class: "ma-btn", disabled: running(), onclick: run, "Run screen" }
+            }
+            div { class: "multi-card-div",
+                p { "{screen_country}, latest date: {current().describe()}" }
+                if screen_country != country {
+                    button {
+                        class: "ma-btn",
+                        onclick: move |_| loaded_country.set(None),
+                        "Use {country}"
+                    }
+                }
+                input {
+                    r#type: "text",
+                    placeholder: "name",
+                    value: "{save_name()}",
+                    oninput: move |evt| save_name.set(evt.value()),
+                }
+                button { class: "ma-btn", onclick: save, "Save screen" }
+                if let Some(list) = saved() {
+                    if !list.is_empty() {
+                        label { "Saved" }
+                        select {
+                            onchange: move |evt| {
+                                let name = evt.value();
+                                if let Some(s) = saved
+                                    .peek()
+                                    .as_ref()
+                                    .and_then(|l| l.iter().find(|s| s.name.as_deref() == Some(name.as_str())).cloned())
+                                {
+                                    loaded_country.set(Some(s.country));
+                                    rules.set(s.rules);
+                                    rank_by.set(s.rank_by);
+                                    ascending.set(s.ascending);
+                                    limit.set(s.limit);
+                                }
+                            },
+                            option { value: "", "load a saved screen…" }
+                            for s in list.iter() {
+                                option {
+                                    value: s.name.clone().unwrap_or_default(),
+                                    title: "{s.describe()}",
+                                    {s.name.clone().unwrap_or_default()}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        match result() {
+            Some(Ok(res)) => {
+                let mat = res.matrix.clone();
+                let members: Vec<(String, String)> = (0..mat.nrows())
+                    .map(|i| (mat.cell(i, "gvkey"), mat.cell(i, "iid")))
+                    .collect();
+                let n_rows = mat.nrows();
+                let date = res.date.clone();
+                let screen = res.screen.clone();
+                rsx! {
+                    p {
+                        "{n_rows} securities"
+                        if let Some(d) = &res.date { " on {d}" }
+                        " "
+                        button {
+                            class: "ma-btn",
+                            disabled: n_rows == 0,
+                            onclick: move |_| {
+                                let n = members.len();
+                                universe.0.set(Some(Universe {
+                                    name: screen.describe(),
+                                    country: screen.country.clone(),
+                                    date: date.clone(),
+                                    members: members.clone(),
+                                }));
+                                sent.set(Some(n));
+                            },
+                            "Send to Portfolio"
+                        }
+                        if let Some(n) = sent() { " Sent {n} securities to the Portfolio view." }
+                    }
+                    DataGrid {
+                        mat,
+                        pinned: vec!["gvkey".to_string(), "iid".to_string()],
+                        row_numbers: true,
+                    }
+                }
+            }
+            Some(Err(e)) => rsx! { div { class: "error", "Screen failed: {e}" } },
+            None => rsx! {},
+        }
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+
+    fn factors() -> duckdb::Connection {
+        let conn = duckdb::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE factors (gvkey VARCHAR, iid VARCHAR, date DATE, excntry VARCHAR, ret_3_1 DOUBLE, rvol DOUBLE);
+             INSERT INTO factors VALUES
+                ('1', '01', '2024-01-31', 'USA', 0.90, 0.1),
+                ('1', '01', '2024-02-29', 'USA', 0.10, 0.1),
+                ('1', '02', '2024-02-29', 'USA', 0.20, 0.2),
+                ('2', '01', '2024-02-29', 'USA', 0.30, 0.3),
+                ('3', '01', '2024-02-29', 'USA', 0.40, NULL),
+                ('4', '01', '2024-02-29', 'USA', NULL, 0.5),
+                ('5', '01', '2024-03-29', 'CAN', 0.99, 0.1);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn run(conn: &duckdb::Connection, screen: &Screen) -> Vec<(String, String)> {
+        let query = screen.to_sql("factors").unwrap();
+        let mut stmt = conn.prepare(query.as_str()).unwrap();
+        let rows = stmt
+            .query_map(duckdb::params![screen.country, screen.country], |r| {
+                Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?))
+            })
+            .unwrap();
+        rows.collect::<Result<Vec<_>, _>>().unwrap()
+    }
+
+    fn screen(rules: Vec<ScreenRule>, rank_by: Option<&str>) -> Screen {
+        Screen {
+            country: "USA".to_string(),
+            rules,
+            rank_by: rank_by.map(String::from),
+            ..Screen::default()
+        }
+    }
+
+    #[test]
+    fn rules_run_on_the_latest_cross_section_of_the_country() {
+        let conn = factors();
+        let rule = ScreenRule {
+            column: "ret_3_1".to_string(),
+            op: ScreenOp::Gt,
+            value: 0.15,
+        };
+        let got = run(&conn, &screen(vec![rule], Some("ret_3_1")));
+        // January's 0.90 and Canada's 0.99 are not in February's US cross-section.
+        let ids = |v: &[(&str, &str)]| -> Vec<(String, String)> {
+            v.iter()
+                .map(|(g, i)| (g.to_string(), i.to_string()))
+                .collect()
+        };
+        assert_eq!(got, ids(&[("3", "01"), ("2", "01"), ("1", "02")]));
+    }
+
+    #[test]
+    fn percentile_rules_ignore_nulls() {
+        let conn = factors();
+        let rule = ScreenRule {
+            column: "rvol".to_string(),
+            op: ScreenOp::BottomPct,
+            value: 50.0,
+        };
+        let got = run(&conn, &screen(vec![rule], None));
+        let gvkeys: Vec<&str> = got.iter().map(|(g, _)| g.as_str()).collect();
+        // Four non-null values (0.1, 0.2, 0.3, 0.5); the null row is neither top nor bottom.
+        assert_eq!(gvkeys, vec!["1", "1"]);
+    }
+
+    #[test]
+    fn column_names_are_checked() {
+        let bad = ScreenRule {
+            column: "ret; DROP TABLE factors".to_string(),
+            op: ScreenOp::Gt,
+            value: 0.0,
+        };
+        assert!(screen(vec![bad], None).to_sql("factors").is_err());
+    }
+}
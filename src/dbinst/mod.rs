@@ -1,22 +1,193 @@
 #![cfg(feature = "server")] // Arc<Mutex<duckdb::Connection>>
+use crate::datasets::{DatasetInfo, DatasetKind, DatasetStatus};
+use dioxus::prelude::ServerFnError;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use tokio::sync::{OnceCell, RwLock};
+use tokio::sync::{Mutex as AsyncMutex, MutexGuard, OnceCell, RwLock};
+
+pub mod sql;
 
 pub type SharedDuck = Arc<Mutex<duckdb::Connection>>;
 
-static DUCKSTORE: OnceCell<RwLock<Option<SharedDuck>>> = OnceCell::const_new();
-pub mod duckstore {
+/// A registered dataset: the metadata the UI lists plus its DuckDB instance while loaded.
+struct Entry {
+    info: DatasetInfo,
+    conn: Option<SharedDuck>,
+}
+
+static REGISTRY: OnceCell<RwLock<HashMap<DatasetKind, Entry>>> = OnceCell::const_new();
+/// Serializes loads so concurrent requests don't ingest the same file twice.
+static LOAD_LOCK: AsyncMutex<()> = AsyncMutex::const_new(());
+
+pub mod registry {
     use super::*;
-    async fn handle() -> &'static RwLock<Option<SharedDuck>> {
-        DUCKSTORE.get_or_init(|| async { RwLock::new(None) }).await
+    async fn handle() -> &'static RwLock<HashMap<DatasetKind, Entry>> {
+        REGISTRY
+            .get_or_init(|| async { RwLock::new(HashMap::new()) })
+            .await
+    }
+    pub async fn get(kind: DatasetKind) -> Option<SharedDuck> {
+        handle()
+            .await
+            .read()
+            .await
+            .get(&kind)
+            .and_then(|e| e.conn.clone())
+    }
+    pub async fn info(kind: DatasetKind) -> DatasetInfo {
+        handle()
+            .await
+            .read()
+            .await
+            .get(&kind)
+            .map(|e| e.info.clone())
+            .unwrap_or_else(|| DatasetInfo::unloaded(kind))
+    }
+    pub async fn list() -> Vec<DatasetInfo> {
+        let reg = handle().await.read().await;
+        DatasetKind::ALL
+            .iter()
+            .map(|kind| {
+                reg.get(kind)
+                    .map(|e| e.info.clone())
+                    .unwrap_or_else(|| DatasetInfo::unloaded(*kind))
+            })
+            .collect()
+    }
+    /// Held for the whole of a load (check, open, insert) so loads never interleave.
+    pub async fn lock_loads() -> MutexGuard<'static, ()> {
+        LOAD_LOCK.lock().await
+    }
+    /// Mark `kind` as loading. Path and connection stay as they are until `insert`.
+    pub async fn set_loading(kind: DatasetKind) {
+        let mut reg = handle().await.write().await;
+        let entry = reg.entry(kind).or_insert_with(|| Entry {
+            info: DatasetInfo::unloaded(kind),
+            conn: None,
+        });
+        entry.info.status = DatasetStatus::Loading;
+    }
+    /// Record a failed load. A previously loaded instance keeps serving under its old path;
+    /// otherwise the dataset is marked `Failed`.
+    pub async fn set_failed(kind: DatasetKind, error: String) {
+        let mut reg = handle().await.write().await;
+        let entry = reg.entry(kind).or_insert_with(|| Entry {
+            info: DatasetInfo::unloaded(kind),
+            conn: None,
+        });
+        entry.info.status = if entry.conn.is_some() {
+            DatasetStatus::Loaded
+        } else {
+            DatasetStatus::Failed(error)
+        };
     }
+    /// Swap in a loaded instance together with the path it was read from.
+    pub async fn insert(info: DatasetInfo, conn: SharedDuck) {
+        handle().await.write().await.insert(
+            info.kind,
+            Entry {
+                info,
+                conn: Some(conn),
+            },
+        );
+    }
+    /// Drop the connection (freeing its memory) but keep the path so it can be reloaded.
+    pub async fn unload(kind: DatasetKind) -> DatasetInfo {
+        let mut reg = handle().await.write().await;
+        let path = reg.remove(&kind).and_then(|e| e.info.path);
+        let info = DatasetInfo {
+            path,
+            ..DatasetInfo::unloaded(kind)
+        };
+        reg.insert(
+            kind,
+            Entry {
+                info: info.clone(),
+                conn: None,
+            },
+        );
+        info
+    }
+}
+
+/// Single-connection interface from before the registry; maps to the `FactorsMonthly` slot.
+pub mod duckstore {
+    use super::*;
     pub async fn set(conn: SharedDuck) {
-        *handle().await.write().await = Some(conn);
+        let (rows, mem_bytes) = measure(&conn);
+        let info = DatasetInfo {
+            status: DatasetStatus::Loaded,
+            rows,
+            mem_bytes,
+            loaded_at: Some(chrono::Utc::now()),
+            ..registry::info(DatasetKind::FactorsMonthly).await
+        };
+        registry::insert(info, conn).await;
     }
     pub async fn get() -> Option<SharedDuck> {
-        handle().await.read().await.clone()
+        registry::get(DatasetKind::FactorsMonthly).await
     }
     pub async fn clear() {
-        *handle().await.write().await = None;
+        registry::unload(DatasetKind::FactorsMonthly).await;
     }
 }
+
+/// Start a fresh in-memory DuckDB instance and ingest `path` into it as `kind`.
+/// Each dataset gets its own instance so unloading one releases exactly its memory.
+pub async fn open(kind: DatasetKind, path: &str) -> Result<SharedDuck, ServerFnError> {
+    let conn = wrds_io::instantiatedb::duckdbinst::start_duck_db("8GB", 14)
+        .await
+        .map_err(|e| ServerFnError::new(format!("duckdb start error: {:?}", e)))?;
+    let arc_conn = Arc::new(Mutex::new(conn));
+    match kind {
+        // An empty path lets words_db fall back to its configured factors file.
+        DatasetKind::FactorsMonthly => {
+            wrds_io::instantiatedb::duckdbinst::DbType::EquityFactorsMonthly
+                .ingest(arc_conn.clone(), path)
+                .await
+                .map_err(|e| {
+                    ServerFnError::new(format!("ingest equity factors monthly parquet: {:?}", e))
+                })?;
+        }
+        DatasetKind::Fundamentals | DatasetKind::Headers => {
+            if path.trim().is_empty() {
+                return Err(ServerFnError::new(format!(
+                    "{} needs a parquet path",
+                    kind.as_str()
+                )));
+            }
+            let sql = format!(
+                "CREATE OR REPLACE TABLE {} AS SELECT * FROM read_parquet('{}')",
+                kind.table_name(),
+                path.replace('\'', "''")
+            );
+            let conn = arc_conn.clone();
+            tokio::task::spawn_blocking(move || {
+                conn.lock()
+                    .map_err(|e| e.to_string())?
+                    .execute_batch(&sql)
+                    .map_err(|e| e.to_string())
+            })
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?
+            .map_err(|e| ServerFnError::new(format!("ingest {}: {e}", kind.as_str())))?;
+        }
+    }
+    Ok(arc_conn)
+}
+
+/// (rows, bytes) held by a dataset's instance. Rows are DuckDB's table size estimate.
+pub fn measure(conn: &SharedDuck) -> (u64, u64) {
+    let Ok(c) = conn.lock() else {
+        return (0, 0);
+    };
+    let scalar = |sql: &str| {
+        c.query_row(sql, [], |r| r.get::<_, i64>(0))
+            .map(|v| v.max(0) as u64)
+            .unwrap_or(0)
+    };
+    (
+        scalar("SELECT coalesce(sum(estimated_size), 0)::BIGINT FROM duckdb_tables()"),
+        scalar("SELECT coalesce(sum(memory_usage_bytes), 0)::BIGINT FROM duckdb_memory()"),
+    )
+}
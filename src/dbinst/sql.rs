@@ -0,0 +1,141 @@
+// SQL helpers for the DuckDB datasets: identifier checks, table lookup and Arrow -> Polars
+// conversion so filtered query results go straight into a DataFrame.
+use crate::datasets::DatasetKind;
+use duckdb::arrow::array::{Array, ArrayRef, BooleanArray, Date32Array, Float64Array, Int64Array, StringArray};
+use duckdb::arrow::compute::cast;
+use duckdb::arrow::datatypes::{DataType as ArrowType, SchemaRef, TimeUnit as ArrowUnit};
+use duckdb::arrow::record_batch::RecordBatch;
+use duckdb::{Connection, Params};
+use polars::prelude::*;
+
+/// Plain SQL identifier (`[A-Za-z_][A-Za-z0-9_]*`), safe to splice into a query.
+pub fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Validate and double-quote a column or table name.
+pub fn quote_ident(s: &str) -> Result<String, String> {
+    if is_identifier(s) {
+        Ok(format!("\"{s}\""))
+    } else {
+        Err(format!("invalid identifier '{s}'"))
+    }
+}
+
+/// Table holding `kind` in its instance: the conventional name if present, otherwise the
+/// largest table (words_db names the factors table itself).
+pub fn resolve_table(conn: &Connection, kind: DatasetKind) -> Result<String, String> {
+    let mut stmt = conn
+        .prepare("SELECT table_name FROM duckdb_tables() ORDER BY (table_name = ?) DESC, estimated_size DESC LIMIT 1")
+        .map_err(|e| e.to_string())?;
+    stmt.query_row([kind.table_name()], |r| r.get::<_, String>(0))
+        .map_err(|_| format!("no table loaded for {}", kind.as_str()))
+}
+
+/// Column names and DuckDB types of `table`, in table order.
+pub fn table_columns(conn: &Connection, table: &str) -> Result<Vec<(String, String)>, String> {
+    let mut stmt = conn
+        .prepare("SELECT column_name, data_type FROM duckdb_columns() WHERE table_name = ? ORDER BY column_index")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([table], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Run `sql` and stack its Arrow record batches into one DataFrame.
+pub fn query_polars<P: Params>(conn: &Connection, sql: &str, params: P) -> PolarsResult<DataFrame> {
+    let to_polars = |e: duckdb::Error| PolarsError::ComputeError(format!("duckdb: {e}").into());
+    let mut stmt = conn.prepare(sql).map_err(to_polars)?;
+    let batches = stmt.query_arrow(params).map_err(to_polars)?;
+    let schema = batches.get_schema();
+    let mut out: Option<DataFrame> = None;
+    for batch in batches {
+        let df = record_batch_to_df(&batch)?;
+        match out.as_mut() {
+            Some(acc) => {
+                acc.vstack_mut(&df)?;
+            }
+            None => out = Some(df),
+        }
+    }
+    let mut df = match out {
+        Some(df) => df,
+        None => empty_df(&schema)?,
+    };
+    df.as_single_chunk_par();
+    Ok(df)
+}
+
+fn empty_df(schema: &SchemaRef) -> PolarsResult<DataFrame> {
+    let batch = RecordBatch::new_empty(schema.clone());
+    record_batch_to_df(&batch)
+}
+
+pub fn record_batch_to_df(batch: &RecordBatch) -> PolarsResult<DataFrame> {
+    let columns = batch
+        .schema()
+        .fields()
+        .iter()
+        .zip(batch.columns())
+        .map(|(field, array)| arrow_to_series(field.name(), array).map(Column::from))
+        .collect::<PolarsResult<Vec<_>>>()?;
+    DataFrame::new(columns)
+}
+
+fn arrow_cast(array: &ArrayRef, to: &ArrowType) -> PolarsResult<ArrayRef> {
+    cast(array, to).map_err(|e| PolarsError::ComputeError(format!("arrow cast: {e}").into()))
+}
+
+/// Map one Arrow column onto the Polars types `MyMmMatrix` understands
+/// (Float64/Int64, String, Date, Boolean, Datetime).
+fn arrow_to_series(name: &str, array: &ArrayRef) -> PolarsResult<Series> {
+    let name = PlSmallStr::from(name);
+    let series = match array.data_type() {
+        ArrowType::Boolean => {
+            let a = array.as_any().downcast_ref::<BooleanArray>().unwrap();
+            Series::new(name, a.iter().collect::<Vec<Option<bool>>>())
+        }
+        ArrowType::Int8
+        | ArrowType::Int16
+        | ArrowType::Int32
+        | ArrowType::Int64
+        | ArrowType::UInt8
+        | ArrowType::UInt16
+        | ArrowType::UInt32 => {
+            let a = arrow_cast(array, &ArrowType::Int64)?;
+            let a = a.as_any().downcast_ref::<Int64Array>().unwrap();
+            Series::new(name, a.iter().collect::<Vec<Option<i64>>>())
+        }
+        ArrowType::UInt64
+        | ArrowType::Float16
+        | ArrowType::Float32
+        | ArrowType::Float64
+        | ArrowType::Decimal128(_, _)
+        | ArrowType::Decimal256(_, _) => {
+            let a = arrow_cast(array, &ArrowType::Float64)?;
+            let a = a.as_any().downcast_ref::<Float64Array>().unwrap();
+            Series::new(name, a.iter().collect::<Vec<Option<f64>>>())
+        }
+        ArrowType::Date32 | ArrowType::Date64 => {
+            let a = arrow_cast(array, &ArrowType::Date32)?;
+            let a = a.as_any().downcast_ref::<Date32Array>().unwrap();
+            Series::new(name, a.iter().collect::<Vec<Option<i32>>>()).cast(&DataType::Date)?
+        }
+        ArrowType::Timestamp(_, _) => {
+            let a = arrow_cast(array, &ArrowType::Timestamp(ArrowUnit::Microsecond, None))?;
+            let a = arrow_cast(&a, &ArrowType::Int64)?;
+            let a = a.as_any().downcast_ref::<Int64Array>().unwrap();
+            Series::new(name, a.iter().collect::<Vec<Option<i64>>>())
+                .cast(&DataType::Datetime(TimeUnit::Microseconds, None))?
+        }
+        _ => {
+            let a = arrow_cast(array, &ArrowType::Utf8)?;
+            let a = a.as_any().downcast_ref::<StringArray>().unwrap();
+            Series::new(name, a.iter().collect::<Vec<Option<&str>>>())
+        }
+    };
+    Ok(series)
+}
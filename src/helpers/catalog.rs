@@ -0,0 +1,91 @@
+// Factor catalog bundled from `factor_catalog.json`: the Jensen-Kelly-Pedersen characteristics
+// with their full names, theme clusters, expected sign and input frequency.
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FactorDef {
+    pub name: String,
+    pub full_name: String,
+    pub theme: String,
+    /// +1 if high values predict high returns, -1 if they predict low returns.
+    pub sign: i8,
+    /// Frequency of the underlying input data ("daily", "monthly", "quarterly", "annual").
+    pub frequency: String,
+    pub description: String,
+}
+
+impl FactorDef {
+    /// Hover text for pickers and table headers.
+    pub fn tooltip(&self) -> String {
+        let direction = if self.sign >= 0 { "high" } else { "low" };
+        format!(
+            "{} ({}, {} data)\n{}\nExpected: {direction} values earn higher returns.",
+            self.full_name, self.theme, self.frequency, self.description
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FactorCatalog {
+    pub source: String,
+    pub factors: Vec<FactorDef>,
+}
+
+/// Theme for columns the catalog does not know (ids, raw prices, user-built factors).
+pub const OTHER_THEME: &str = "Other";
+
+static CATALOG: OnceLock<FactorCatalog> = OnceLock::new();
+
+/// The bundled catalog, parsed on first use.
+pub fn catalog() -> &'static FactorCatalog {
+    CATALOG.get_or_init(|| {
+        serde_json::from_str(include_str!("factor_catalog.json"))
+            .expect("bundled factor_catalog.json is valid")
+    })
+}
+
+impl FactorCatalog {
+    /// Look up a factor by column name, ignoring the `_mean`/`_std` suffix of grouped columns.
+    pub fn get(&self, column: &str) -> Option<&FactorDef> {
+        let base = column
+            .strip_suffix("_mean")
+            .or_else(|| column.strip_suffix("_std"))
+            .unwrap_or(column);
+        self.factors.iter().find(|f| f.name == base)
+    }
+
+    /// Themes in catalog order.
+    pub fn themes(&self) -> Vec<&str> {
+        let mut out: Vec<&str> = Vec::new();
+        for f in &self.factors {
+            if !out.contains(&f.theme.as_str()) {
+                out.push(f.theme.as_str());
+            }
+        }
+        out
+    }
+
+    /// Split `columns` into (theme, columns) groups in catalog theme order, keeping the input
+    /// order within each group. Unknown columns go to `OTHER_THEME`, last.
+    pub fn group_by_theme(&self, columns: &[String]) -> Vec<(String, Vec<String>)> {
+        let mut groups: Vec<(String, Vec<String>)> = self
+            .themes()
+            .into_iter()
+            .chain(std::iter::once(OTHER_THEME))
+            .map(|t| (t.to_string(), Vec::new()))
+            .collect();
+        for c in columns {
+            let theme = self.get(c).map(|f| f.theme.as_str()).unwrap_or(OTHER_THEME);
+            if let Some((_, cols)) = groups.iter_mut().find(|(t, _)| t == theme) {
+                cols.push(c.clone());
+            }
+        }
+        groups.retain(|(_, cols)| !cols.is_empty());
+        groups
+    }
+
+    pub fn tooltip(&self, column: &str) -> String {
+        self.get(column).map(|f| f.tooltip()).unwrap_or_default()
+    }
+}
@@ -20,6 +20,51 @@ pub fn SelectOptions(props: SelectOptionsProps) -> Element {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynOption {
+    pub value: String,
+    pub label: String,
+    /// Hover text, e.g. a factor definition from the catalog.
+    pub title: Option<String>,
+}
+
+impl DynOption {
+    pub fn new(value: impl Into<String>, label: impl Into<String>) -> Self {
+        DynOption {
+            value: value.into(),
+            label: label.into(),
+            title: None,
+        }
+    }
+}
+
+/// `SelectOptions` for option lists only known at runtime.
+#[derive(Props, Clone, PartialEq)]
+pub struct DynSelectOptionsProps {
+    pub value: String,
+    pub options: Vec<DynOption>,
+    pub onchange: EventHandler<FormEvent>,
+}
+
+#[component]
+pub fn DynSelectOptions(props: DynSelectOptionsProps) -> Element {
+    rsx! {
+        select {
+            value: "{props.value}",
+            onchange: move |evt: FormEvent| props.onchange.call(evt),
+            for opt in props.options.iter() {
+                option {
+                    key: "{opt.value}",
+                    value: "{opt.value}",
+                    title: opt.title.clone(),
+                    selected: opt.value == props.value,
+                    "{opt.label}"
+                }
+            }
+        }
+    }
+}
+
 pub struct SelectOptionProps;
 
 impl SelectOptionProps {
@@ -34,39 +79,7 @@ impl SelectOptionsProps {
     pub fn global_stock_returns() -> &'static [&'static str] {
         Self::GLOBAL_FACS
     }
-    pub fn idiosyncratic_factors() -> &'static [&'static str] {
-        Self::IDIOSYN_FACS
-    }
-    pub fn crossectional_factors() -> &'static [&'static str] {
-        Self::CSECTION_FACS
-    }
-    pub fn idiosyncratic_factors_mu() -> &'static [&'static str] {
-        Self::IDIOSYN_FACS_MU
-    }
-    pub fn crossectional_factors_mu() -> &'static [&'static str] {
-        Self::CSECTION_FACS_MU
-    }
 
-    pub const CSECTION_FACS: &'static [&'static str] = &["dolvol"];
-    pub const CSECTION_FACS_MU: &'static [&'static str] = &["dolvol_mean"];
-    pub const IDIOSYN_FACS: &'static [&'static str] = &[
-        "rvolhl_21d",
-        "ret_60_12",
-        "ret_3_1",
-        "inv_gr1",
-        "debt_gr3",
-        "sale_gr3",
-        "capx_gr3",
-    ];
-    pub const IDIOSYN_FACS_MU: &'static [&'static str] = &[
-        "rvolhl_21d_mean",
-        "ret_60_12_mean",
-        "ret_3_1_mean",
-        "inv_gr1_mean",
-        "debt_gr3_mean",
-        "sale_gr3_mean",
-        "capx_gr3_mean",
-    ];
     pub const GLOBAL_FACS: &'static [&'static str] = &[
         "obs_main",
         "exch_main",
@@ -0,0 +1,628 @@
+// Derived factors: user formulas such as `ret_60_12 - ret_3_1` or `rank(sale_gr3 / capx_gr3)`
+// over the factor columns. Formulas parse on either target (so the UI can validate them) and
+// compile to Polars `Expr`s on the server, where they are added next to the native columns.
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Functions available in formulas, with their argument counts.
+pub const FUNCTIONS: &[(&str, usize, &str)] = &[
+    (
+        "rank",
+        1,
+        "cross-sectional rank per date, scaled to -0.5..0.5",
+    ),
+    ("zscore", 1, "cross-sectional z-score per date"),
+    (
+        "lag",
+        2,
+        "value n rows earlier for the same security (gvkey and iid), e.g. lag(ret_1_0, 3)",
+    ),
+    ("if", 3, "if(condition, then, else)"),
+    ("abs", 1, "absolute value"),
+    ("log", 1, "natural logarithm"),
+    ("sqrt", 1, "square root"),
+    ("min", 2, "smaller of two values"),
+    ("max", 2, "larger of two values"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ast {
+    Num(f64),
+    Col(String),
+    Neg(Box<Ast>),
+    Bin(BinOp, Box<Ast>, Box<Ast>),
+    Call(String, Vec<Ast>),
+}
+
+impl Ast {
+    /// Referenced columns, in first-use order.
+    pub fn columns(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        self.collect_columns(&mut out);
+        out
+    }
+
+    fn collect_columns(&self, out: &mut Vec<String>) {
+        match self {
+            Ast::Num(_) => {}
+            Ast::Col(c) => {
+                if !out.contains(c) {
+                    out.push(c.clone());
+                }
+            }
+            Ast::Neg(a) => a.collect_columns(out),
+            Ast::Bin(_, a, b) => {
+                a.collect_columns(out);
+                b.collect_columns(out);
+            }
+            Ast::Call(_, args) => args.iter().for_each(|a| a.collect_columns(out)),
+        }
+    }
+
+    /// True if any `lag` appears (the security id is then needed).
+    pub fn uses_lag(&self) -> bool {
+        match self {
+            Ast::Num(_) | Ast::Col(_) => false,
+            Ast::Neg(a) => a.uses_lag(),
+            Ast::Bin(_, a, b) => a.uses_lag() || b.uses_lag(),
+            Ast::Call(f, args) => f == "lag" || args.iter().any(|a| a.uses_lag()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+const OPERATORS: [&str; 13] = [
+    ">=", "<=", "==", "!=", "&&", "||", "+", "-", "*", "/", "^", ">", "<",
+];
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // Exponent, e.g. 1e-3.
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let v = text
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number '{text}'"))?;
+            out.push(Token::Num(v));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            out.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '(' {
+            out.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            out.push(Token::RParen);
+            i += 1;
+        } else if c == ',' {
+            out.push(Token::Comma);
+            i += 1;
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let op = OPERATORS
+                .into_iter()
+                .find(|op| rest.starts_with(op))
+                .ok_or_else(|| format!("unexpected character '{c}'"))?;
+            out.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+    Ok(out)
+}
+
+/// Recursive-descent parser. Precedence, loosest first: `or`/`||`, `and`/`&&`, comparisons,
+/// `+ -`, `* /`, unary minus, `^` (right-associative).
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    /// Consume an operator (or its keyword spelling) if it comes next.
+    fn eat_op(&mut self, ops: &[(&str, BinOp)]) -> Option<BinOp> {
+        let found = match self.peek() {
+            Some(Token::Op(o)) => ops.iter().find(|(s, _)| s == o),
+            Some(Token::Ident(w)) => ops.iter().find(|(s, _)| s.eq_ignore_ascii_case(w)),
+            _ => None,
+        };
+        let op = found.map(|(_, op)| *op)?;
+        self.pos += 1;
+        Some(op)
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[(&str, BinOp)],
+        next: fn(&mut Self) -> Result<Ast, String>,
+    ) -> Result<Ast, String> {
+        let mut lhs = next(self)?;
+        while let Some(op) = self.eat_op(ops) {
+            let rhs = next(self)?;
+            lhs = Ast::Bin(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn or(&mut self) -> Result<Ast, String> {
+        self.binary(&[("||", BinOp::Or), ("or", BinOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Ast, String> {
+        self.binary(&[("&&", BinOp::And), ("and", BinOp::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Ast, String> {
+        self.binary(
+            &[
+                (">=", BinOp::Ge),
+                ("<=", BinOp::Le),
+                ("==", BinOp::Eq),
+                ("!=", BinOp::Ne),
+                (">", BinOp::Gt),
+                ("<", BinOp::Lt),
+            ],
+            Self::additive,
+        )
+    }
+
+    fn additive(&mut self) -> Result<Ast, String> {
+        self.binary(
+            &[("+", BinOp::Add), ("-", BinOp::Sub)],
+            Self::multiplicative,
+        )
+    }
+
+    fn multiplicative(&mut self) -> Result<Ast, String> {
+        self.binary(&[("*", BinOp::Mul), ("/", BinOp::Div)], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Ast, String> {
+        if self.eat_op(&[("-", BinOp::Sub)]).is_some() {
+            return Ok(Ast::Neg(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Ast, String> {
+        let base = self.primary()?;
+        if self.eat_op(&[("^", BinOp::Pow)]).is_some() {
+            let exp = self.unary()?;
+            return Ok(Ast::Bin(BinOp::Pow, Box::new(base), Box::new(exp)));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Ast, String> {
+        match self.next() {
+            Some(Token::Num(v)) => Ok(Ast::Num(v)),
+            Some(Token::LParen) => {
+                let inner = self.or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => Err("missing ')'".to_string()),
+                }
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                self.pos += 1;
+                let f = name.to_ascii_lowercase();
+                let Some((_, arity, _)) = FUNCTIONS.iter().find(|(n, _, _)| *n == f) else {
+                    return Err(format!("unknown function '{name}'"));
+                };
+                let mut args = Vec::new();
+                if self.peek() != Some(&Token::RParen) {
+                    loop {
+                        args.push(self.or()?);
+                        match self.next() {
+                            Some(Token::Comma) => continue,
+                            Some(Token::RParen) => break,
+                            _ => return Err(format!("missing ')' after arguments of {f}")),
+                        }
+                    }
+                } else {
+                    self.pos += 1;
+                }
+                if args.len() != *arity {
+                    return Err(format!("{f} takes {arity} argument(s), got {}", args.len()));
+                }
+                if f == "lag" && !matches!(args[1], Ast::Num(n) if n >= 1.0 && n.fract() == 0.0) {
+                    return Err("lag needs a whole number of periods >= 1".to_string());
+                }
+                Ok(Ast::Call(f, args))
+            }
+            Some(Token::Ident(name)) => Ok(Ast::Col(name)),
+            Some(t) => Err(format!("unexpected {t:?}")),
+            None => Err("unexpected end of formula".to_string()),
+        }
+    }
+}
+
+/// Parse a formula into its syntax tree.
+pub fn parse(src: &str) -> Result<Ast, String> {
+    let mut p = Parser {
+        tokens: tokenize(src)?,
+        pos: 0,
+    };
+    if p.tokens.is_empty() {
+        return Err("empty formula".to_string());
+    }
+    let ast = p.or()?;
+    match p.peek() {
+        None => Ok(ast),
+        Some(t) => Err(format!("unexpected {t:?} after the end of the expression")),
+    }
+}
+
+/// A named formula, added as a column wherever `name` is requested.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DerivedFactor {
+    pub name: String,
+    pub formula: String,
+}
+
+impl DerivedFactor {
+    /// Parse the formula and check the name and every referenced column against `known`
+    /// (the native factor columns).
+    pub fn validate(&self, known: &[String]) -> Result<Ast, String> {
+        let name = self.name.trim();
+        let mut chars = name.chars();
+        let valid_name = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name {
+            return Err(format!(
+                "'{name}' is not a valid column name (letters, digits and _)"
+            ));
+        }
+        if known.iter().any(|c| c == name) {
+            return Err(format!("'{name}' is already a factor column"));
+        }
+        let ast = parse(&self.formula).map_err(|e| format!("{name}: {e}"))?;
+        if let Some(c) = ast.columns().into_iter().find(|c| !known.contains(c)) {
+            return Err(format!("{name}: unknown column '{c}'"));
+        }
+        Ok(ast)
+    }
+}
+
+/// Form for defining derived factors. `columns` are the native factor columns formulas may use;
+/// `onadd` receives a validated factor, `onremove` the name of one in `derived`.
+#[component]
+pub fn DerivedFactorEditor(
+    columns: Vec<String>,
+    derived: Vec<DerivedFactor>,
+    onadd: EventHandler<DerivedFactor>,
+    onremove: EventHandler<String>,
+) -> Element {
+    let mut name = use_signal(String::new);
+    let mut text = use_signal(String::new);
+    let mut error: Signal<Option<String>> = use_signal(|| None);
+    let help = FUNCTIONS
+        .iter()
+        .map(|(f, _, doc)| format!("{f}: {doc}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    rsx! {
+        label { "Derived factors" }
+        div {
+            input {
+                r#type: "text",
+                placeholder: "name",
+                style: "width: 8em;",
+                value: "{name}",
+                oninput: move |e| name.set(e.value()),
+            }
+            " = "
+            input {
+                r#type: "text",
+                placeholder: "ret_60_12 - ret_3_1",
+                title: "{help}",
+                value: "{text}",
+                oninput: move |e| text.set(e.value()),
+            }
+            button {
+                class: "ma-btn",
+                onclick: move |_| {
+                    let factor = DerivedFactor {
+                        name: name().trim().to_string(),
+                        formula: text(),
+                    };
+                    match factor.validate(&columns) {
+                        Ok(_) => {
+                            error.set(None);
+                            name.set(String::new());
+                            text.set(String::new());
+                            onadd.call(factor);
+                        }
+                        Err(e) => error.set(Some(e)),
+                    }
+                },
+                "Add"
+            }
+        }
+        if let Some(e) = error() {
+            div { class: "error", "{e}" }
+        }
+        for d in derived {
+            div { key: "{d.name}",
+                "{d.name} = {d.formula} "
+                button {
+                    class: "ma-btn",
+                    onclick: {
+                        let n = d.name.clone();
+                        move |_| onremove.call(n.clone())
+                    },
+                    "Remove"
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+mod compile {
+    use super::{Ast, BinOp};
+    use polars::prelude::*;
+
+    fn as_bool(e: Expr) -> Expr {
+        e.cast(DataType::Float64).neq(lit(0.0))
+    }
+
+    impl Ast {
+        /// Polars expression for this formula. `cross_section` partitions `rank`/`zscore`
+        /// (usually `date`), `security` partitions `lag` (`gvkey` and `iid`); rows must be sorted
+        /// by date within each security.
+        pub fn to_expr(&self, cross_section: &[String], security: &[String]) -> Expr {
+            let cs: Vec<Expr> = cross_section.iter().map(|c| col(c.as_str())).collect();
+            let sec: Vec<Expr> = security.iter().map(|c| col(c.as_str())).collect();
+            self.compile(&cs, &sec)
+        }
+
+        fn compile(&self, cs: &[Expr], sec: &[Expr]) -> Expr {
+            let num = |a: &Ast| a.compile(cs, sec).cast(DataType::Float64);
+            match self {
+                Ast::Num(v) => lit(*v),
+                Ast::Col(c) => col(c.as_str()).cast(DataType::Float64),
+                Ast::Neg(a) => lit(0.0) - num(a),
+                Ast::Bin(op, a, b) => {
+                    let (x, y) = (num(a), num(b));
+                    match op {
+                        BinOp::Add => x + y,
+                        BinOp::Sub => x - y,
+                        BinOp::Mul => x * y,
+                        BinOp::Div => x / y,
+                        BinOp::Pow => x.pow(y),
+                        BinOp::Gt => x.gt(y),
+                        BinOp::Ge => x.gt_eq(y),
+                        BinOp::Lt => x.lt(y),
+                        BinOp::Le => x.lt_eq(y),
+                        BinOp::Eq => x.eq(y),
+                        BinOp::Ne => x.neq(y),
+                        BinOp::And => as_bool(x).and(as_bool(y)),
+                        BinOp::Or => as_bool(x).or(as_bool(y)),
+                    }
+                }
+                Ast::Call(f, args) => {
+                    let x = num(&args[0]);
+                    match f.as_str() {
+                        "rank" => {
+                            let opts = RankOptions {
+                                method: RankMethod::Average,
+                                descending: false,
+                            };
+                            let n = x.clone().count().over(cs).cast(DataType::Float64);
+                            x.rank(opts, None).over(cs).cast(DataType::Float64) / (n + lit(1.0))
+                                - lit(0.5)
+                        }
+                        "zscore" => (x.clone() - x.clone().mean().over(cs)) / x.std(1).over(cs),
+                        "lag" => {
+                            let n = match args[1] {
+                                Ast::Num(n) => n as i64,
+                                _ => 1,
+                            };
+                            x.shift(lit(n)).over(sec)
+                        }
+                        "if" => when(as_bool(args[0].compile(cs, sec)))
+                            .then(num(&args[1]))
+                            .otherwise(num(&args[2])),
+                        "abs" => x.abs(),
+                        "log" => x.log(lit(std::f64::consts::E)),
+                        "sqrt" => x.sqrt(),
+                        "min" => {
+                            let y = num(&args[1]);
+                            when(x.clone().lt_eq(y.clone())).then(x).otherwise(y)
+                        }
+                        "max" => {
+                            let y = num(&args[1]);
+                            when(x.clone().gt_eq(y.clone())).then(x).otherwise(y)
+                        }
+                        _ => lit(NULL).cast(DataType::Float64),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn precedence_and_associativity() {
+        let col = |c: &str| Box::new(Ast::Col(c.to_string()));
+        assert_eq!(
+            parse("a - b * c").unwrap(),
+            Ast::Bin(
+                BinOp::Sub,
+                col("a"),
+                Box::new(Ast::Bin(BinOp::Mul, col("b"), col("c")))
+            )
+        );
+        // `^` binds tighter than unary minus and is right-associative.
+        assert_eq!(
+            parse("-a ^ 2 ^ 3").unwrap(),
+            Ast::Neg(Box::new(Ast::Bin(
+                BinOp::Pow,
+                col("a"),
+                Box::new(Ast::Bin(
+                    BinOp::Pow,
+                    Box::new(Ast::Num(2.0)),
+                    Box::new(Ast::Num(3.0))
+                ))
+            )))
+        );
+        assert_eq!(
+            parse("a > 1 and b < 2 or c").unwrap(),
+            parse("((a > 1) && (b < 2)) || c").unwrap()
+        );
+        assert_eq!(parse("1e-3").unwrap(), Ast::Num(0.001));
+    }
+
+    #[test]
+    fn functions_are_checked() {
+        assert!(parse("rank(ret_3_1)").is_ok());
+        assert!(parse("IF(a > 0, a, 0)").is_ok());
+        assert!(parse("lag(a, 0)").is_err());
+        assert!(parse("lag(a, 1.5)").is_err());
+        assert!(parse("lag(a, b)").is_err());
+        assert!(parse("zscore(a, b)").is_err());
+        assert!(parse("median(a)").is_err());
+        assert!(parse("(a + b").is_err());
+        assert!(parse("a b").is_err());
+        assert!(parse("").is_err());
+        let ast = parse("if(lag(a, 2) > b, c, a)").unwrap();
+        assert_eq!(ast.columns(), vec!["a", "b", "c"]);
+        assert!(ast.uses_lag());
+    }
+
+    #[test]
+    fn derived_names_and_columns_are_validated() {
+        let known = vec!["a".to_string(), "b".to_string()];
+        let f = |name: &str, formula: &str| DerivedFactor {
+            name: name.to_string(),
+            formula: formula.to_string(),
+        };
+        assert!(f("a_minus_b", "a - b").validate(&known).is_ok());
+        assert!(f("a", "a - b").validate(&known).is_err());
+        assert!(f("1bad", "a").validate(&known).is_err());
+        assert!(f("ok", "a - z").validate(&known).is_err());
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn compiled_expressions_partition_by_date_and_security() {
+        use polars::prelude::*;
+        // Two share classes of one gvkey over three dates, sorted by security then date.
+        let df = df!(
+            "gvkey" => ["1", "1", "1", "1", "1", "1"],
+            "iid" => ["01", "01", "01", "02", "02", "02"],
+            "date" => [1, 2, 3, 1, 2, 3],
+            "x" => [1.0, 2.0, 3.0, 10.0, 20.0, 30.0]
+        )
+        .unwrap();
+        let run = |formula: &str| -> Vec<Option<f64>> {
+            let expr = parse(formula).unwrap().to_expr(
+                &["date".to_string()],
+                &["gvkey".to_string(), "iid".to_string()],
+            );
+            df.clone()
+                .lazy()
+                .select([expr.alias("out")])
+                .collect()
+                .unwrap()
+                .column("out")
+                .unwrap()
+                .f64()
+                .unwrap()
+                .into_iter()
+                .collect()
+        };
+        // The first date of the second share class must not see the first one's last value.
+        assert_eq!(
+            run("lag(x, 1)"),
+            vec![None, Some(1.0), Some(2.0), None, Some(10.0), Some(20.0)]
+        );
+        assert_eq!(
+            run("rank(x)"),
+            vec![
+                Some(1.0 / 3.0 - 0.5),
+                Some(1.0 / 3.0 - 0.5),
+                Some(1.0 / 3.0 - 0.5),
+                Some(2.0 / 3.0 - 0.5),
+                Some(2.0 / 3.0 - 0.5),
+                Some(2.0 / 3.0 - 0.5)
+            ]
+        );
+        assert_eq!(
+            run("if(x > 2, max(x, 25), -x)"),
+            vec![
+                Some(-1.0),
+                Some(-2.0),
+                Some(25.0),
+                Some(25.0),
+                Some(25.0),
+                Some(30.0)
+            ]
+        );
+    }
+}
@@ -1,2 +1,7 @@
+pub mod catalog;
 pub mod dropdownlist;
+pub mod formula;
+#[cfg(feature = "server")]
+pub mod paths;
 pub mod plrs;
+pub mod prep;
@@ -0,0 +1,55 @@
+// Server-side file paths sent by the client are only honoured inside a configured directory.
+use std::path::{Path, PathBuf};
+
+/// Directory named by the `env` variable, else `default` (relative to the working directory).
+pub fn root_dir(env: &str, default: &str) -> PathBuf {
+    std::env::var(env)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(default))
+}
+
+/// Resolve `path` (relative to `root`, or absolute) and make sure the file it names lies
+/// inside `root` once symlinks and `..` are resolved.
+pub fn confine(root: &Path, path: &str) -> Result<PathBuf, String> {
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("data directory {}: {e}", root.display()))?;
+    let candidate = Path::new(path.trim());
+    let joined = if candidate.is_absolute() {
+        candidate.to_path_buf()
+    } else {
+        root.join(candidate)
+    };
+    let resolved = joined
+        .canonicalize()
+        .map_err(|e| format!("{path}: {e}"))?;
+    if resolved.starts_with(&root) {
+        Ok(resolved)
+    } else {
+        Err(format!("{path} is outside {}", root.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confine_rejects_paths_outside_the_root() {
+        let root = std::env::temp_dir().join(format!("confine_test_{}", std::process::id()));
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub/trades.csv"), "x").unwrap();
+
+        let ok = confine(&root, "sub/trades.csv").unwrap();
+        assert!(ok.ends_with("sub/trades.csv"));
+        let abs = root.join("sub/trades.csv");
+        assert_eq!(confine(&root, abs.to_str().unwrap()).unwrap(), ok);
+        assert!(confine(&root, "../../etc/passwd").is_err());
+        assert!(confine(&root, "/etc/passwd").is_err());
+        assert!(confine(&root, "sub/missing.csv").is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
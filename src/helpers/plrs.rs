@@ -90,3 +90,102 @@ pub fn group_std_dynamic(
         .agg(exprs_mu);
     Ok(lf)
 }
+
+/// Clip each column to its `lower`/`upper` quantiles within every `group_cols` partition
+/// (e.g. per `date`).
+pub fn winsorize_over(
+    mut lf: LazyFrame,
+    group_cols: Vec<String>,
+    cols: Vec<String>,
+    lower: f64,
+    upper: f64,
+) -> PolarsResult<LazyFrame> {
+    let expr_gps: Vec<Expr> = group_cols.iter().map(|c| col(c.as_str())).collect();
+    let exprs: Vec<Expr> = cols
+        .iter()
+        .map(|c| {
+            let x = col(c.as_str()).cast(DataType::Float64);
+            let lo = x
+                .clone()
+                .quantile(lit(lower), QuantileMethod::Linear)
+                .over(expr_gps.clone());
+            let hi = x
+                .clone()
+                .quantile(lit(upper), QuantileMethod::Linear)
+                .over(expr_gps.clone());
+            when(x.clone().lt(lo.clone()))
+                .then(lo)
+                .when(x.clone().gt(hi.clone()))
+                .then(hi)
+                .otherwise(x)
+                .alias(c.as_str())
+        })
+        .collect();
+    lf = lf.with_columns(exprs);
+    Ok(lf)
+}
+
+/// Map each column to its average rank within the partition, scaled to (-0.5, 0.5).
+pub fn rank_normalize_over(
+    mut lf: LazyFrame,
+    group_cols: Vec<String>,
+    cols: Vec<String>,
+) -> PolarsResult<LazyFrame> {
+    let expr_gps: Vec<Expr> = group_cols.iter().map(|c| col(c.as_str())).collect();
+    let opts = RankOptions {
+        method: RankMethod::Average,
+        descending: false,
+    };
+    let exprs: Vec<Expr> = cols
+        .iter()
+        .map(|c| {
+            let x = col(c.as_str());
+            let n = x.clone().count().over(expr_gps.clone()).cast(DataType::Float64);
+            (x.rank(opts, None).over(expr_gps.clone()).cast(DataType::Float64)
+                / (n + lit(1.0))
+                - lit(0.5))
+            .alias(c.as_str())
+        })
+        .collect();
+    lf = lf.with_columns(exprs);
+    Ok(lf)
+}
+
+/// Standardize each column to mean 0 / standard deviation 1 within the partition.
+pub fn zscore_over(
+    mut lf: LazyFrame,
+    group_cols: Vec<String>,
+    cols: Vec<String>,
+) -> PolarsResult<LazyFrame> {
+    let expr_gps: Vec<Expr> = group_cols.iter().map(|c| col(c.as_str())).collect();
+    let exprs: Vec<Expr> = cols
+        .iter()
+        .map(|c| {
+            let x = col(c.as_str()).cast(DataType::Float64);
+            ((x.clone() - x.clone().mean().over(expr_gps.clone()))
+                / x.std(1).over(expr_gps.clone()))
+            .alias(c.as_str())
+        })
+        .collect();
+    lf = lf.with_columns(exprs);
+    Ok(lf)
+}
+
+/// Subtract the partition mean from each column. Partitioning by (`date`, `excntry`) or
+/// (`date`, industry) removes country or industry effects from every cross-section.
+pub fn demean_over(
+    mut lf: LazyFrame,
+    group_cols: Vec<String>,
+    cols: Vec<String>,
+) -> PolarsResult<LazyFrame> {
+    let expr_gps: Vec<Expr> = group_cols.iter().map(|c| col(c.as_str())).collect();
+    let exprs: Vec<Expr> = cols
+        .iter()
+        .map(|c| {
+            let x = col(c.as_str()).cast(DataType::Float64);
+            (x.clone() - x.mean().over(expr_gps.clone())).alias(c.as_str())
+        })
+        .collect();
+    lf = lf.with_columns(exprs);
+    Ok(lf)
+}
@@ -0,0 +1,123 @@
+// Cross-sectional preprocessing applied to factor columns per `date` before they are turned
+// into a `MyMmMatrix` (and hence before PCA / k-means see them).
+use serde::{Deserialize, Serialize};
+
+/// Columns that are never transformed: the forward return is the regression target and must
+/// stay in return units.
+pub const PREP_SKIP: &[&str] = &["ret_exc_lead1m"];
+
+/// Grouping columns offered for neutralization.
+pub const NEUTRALIZE_BY: &[&str] = &["excntry", "ff49", "gics", "sic"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PrepTransform {
+    #[default]
+    None,
+    ZScore,
+    Rank,
+}
+
+impl PrepTransform {
+    pub const ALL: [PrepTransform; 3] = [
+        PrepTransform::None,
+        PrepTransform::ZScore,
+        PrepTransform::Rank,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrepTransform::None => "none",
+            PrepTransform::ZScore => "zscore",
+            PrepTransform::Rank => "rank",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PrepTransform::None => "Raw values",
+            PrepTransform::ZScore => "Z-score",
+            PrepTransform::Rank => "Rank (-0.5..0.5)",
+        }
+    }
+
+    pub fn from_label(s: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|t| t.as_str() == s)
+            .unwrap_or_default()
+    }
+}
+
+/// Per-date preprocessing. Steps run in order: winsorize, neutralize, transform.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FactorPrep {
+    /// Lower/upper quantiles in [0, 1], e.g. (0.01, 0.99).
+    pub winsorize: Option<(f64, f64)>,
+    /// Demean within (`date`, column) groups, e.g. `excntry` or an industry code.
+    pub neutralize: Option<String>,
+    pub transform: PrepTransform,
+}
+
+impl FactorPrep {
+    pub fn is_noop(&self) -> bool {
+        self.winsorize.is_none()
+            && self.neutralize.is_none()
+            && self.transform == PrepTransform::None
+    }
+
+    /// Validate user input before it reaches the query.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some((lo, hi)) = self.winsorize {
+            if !(0.0..=1.0).contains(&lo) || !(0.0..=1.0).contains(&hi) || lo >= hi {
+                return Err(format!(
+                    "winsorize bounds must satisfy 0 <= lower < upper <= 1, got ({lo}, {hi})"
+                ));
+            }
+        }
+        if let Some(g) = &self.neutralize {
+            if !NEUTRALIZE_BY.contains(&g.as_str()) {
+                return Err(format!("cannot neutralize by '{g}'"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "server")]
+mod apply {
+    use super::*;
+    use crate::helpers::plrs;
+    use polars::prelude::*;
+
+    impl FactorPrep {
+        /// Apply the configured steps to `cols` within each cross-section, i.e. each distinct
+        /// value of the `by` columns (`date`, plus `excntry` when several countries are mixed).
+        pub fn apply(
+            &self,
+            mut lf: LazyFrame,
+            by: &[String],
+            cols: Vec<String>,
+        ) -> PolarsResult<LazyFrame> {
+            if cols.is_empty() {
+                return Ok(lf);
+            }
+            let cross_section = by.to_vec();
+            if let Some((lo, hi)) = self.winsorize {
+                lf = plrs::winsorize_over(lf, cross_section.clone(), cols.clone(), lo, hi)?;
+            }
+            if let Some(g) = &self.neutralize {
+                let mut by_group = cross_section.clone();
+                if !by_group.contains(g) {
+                    by_group.push(g.clone());
+                }
+                lf = plrs::demean_over(lf, by_group, cols.clone())?;
+            }
+            lf = match self.transform {
+                PrepTransform::None => lf,
+                PrepTransform::ZScore => plrs::zscore_over(lf, cross_section, cols)?,
+                PrepTransform::Rank => plrs::rank_normalize_over(lf, cross_section, cols)?,
+            };
+            Ok(lf)
+        }
+    }
+}
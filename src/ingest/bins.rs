@@ -0,0 +1,49 @@
+use crate::data_structures::TradeRecord;
+use chrono::DateTime;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// Roll trades up into `equities_returns` rows of `secs` seconds, one series per instrument.
+/// `ret` is close-to-close against the previous bin (open-to-close for the first one).
+pub fn bins_from_trades(trades: &[TradeRecord], bin_size: &str, secs: i64) -> Vec<Value> {
+    let width = secs * 1_000_000_000;
+    let mut by_instrument: BTreeMap<u32, Vec<&TradeRecord>> = BTreeMap::new();
+    for t in trades {
+        by_instrument.entry(t.hd.instrument_id).or_default().push(t);
+    }
+
+    let mut out = Vec::new();
+    for (instrument_id, mut prints) in by_instrument {
+        prints.sort_by_key(|t| t.data.ts_recv);
+        let mut prev_close: Option<i64> = None;
+        for chunk in prints.chunk_by(|a, b| {
+            a.data.ts_recv.div_euclid(width) == b.data.ts_recv.div_euclid(width)
+        }) {
+            let first = chunk[0];
+            let last = chunk[chunk.len() - 1];
+            let bin = first.data.ts_recv - first.data.ts_recv.rem_euclid(width);
+            let (p0, p1) = (first.data.price, last.data.price);
+            let pmax = chunk.iter().map(|t| t.data.price).max().unwrap_or(p1);
+            let pmin = chunk.iter().map(|t| t.data.price).min().unwrap_or(p1);
+            let mean_price =
+                chunk.iter().map(|t| t.data.price as f64).sum::<f64>() / chunk.len() as f64;
+            let base = prev_close.unwrap_or(p0) as f64;
+            out.push(json!({
+                "instrument_id": instrument_id,
+                "bin_size": bin_size,
+                "bin": DateTime::from_timestamp_nanos(bin).to_rfc3339(),
+                "t0": DateTime::from_timestamp_nanos(first.data.ts_recv).to_rfc3339(),
+                "t1": DateTime::from_timestamp_nanos(last.data.ts_recv).to_rfc3339(),
+                "ret": if base != 0.0 { p1 as f64 / base - 1.0 } else { 0.0 },
+                "mean_price": mean_price.round() as i64,
+                "p0": p0,
+                "p1": p1,
+                "pmax": pmax,
+                "pmin": pmin,
+                "price_diff": p1 - p0,
+            }));
+            prev_close = Some(p1);
+        }
+    }
+    out
+}
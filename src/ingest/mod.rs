@@ -0,0 +1,124 @@
+// Server-side ingestion of Databento-style trade files (DBN or CSV) into the Surreal `trades`
+// table, optionally rolling them up into `equities_returns` bins.
+#[cfg(feature = "server")]
+pub mod bins;
+#[cfg(feature = "server")]
+pub mod readers;
+#[cfg(feature = "server")]
+pub mod store;
+#[cfg(feature = "server")]
+pub mod validate;
+
+#[cfg(feature = "server")]
+use crate::helpers::paths;
+#[cfg(feature = "server")]
+use crate::surr_queries::connect_surr;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum IngestFormat {
+    /// Databento binary encoding, plain or zstd-compressed (`.dbn`, `.dbn.zst`).
+    Dbn,
+    /// Databento CSV export with a header row.
+    Csv,
+}
+
+impl IngestFormat {
+    pub fn from_path(path: &str) -> Self {
+        if path.to_ascii_lowercase().ends_with(".csv") {
+            IngestFormat::Csv
+        } else {
+            IngestFormat::Dbn
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct IngestReport {
+    pub parsed: usize,
+    pub inserted: usize,
+    /// (reason, count) for every validation rule that dropped rows.
+    pub rejected: Vec<(String, usize)>,
+    /// (bin_size, rows) written to `equities_returns`.
+    pub bins_inserted: Vec<(String, usize)>,
+}
+
+/// Environment variable naming the directory `ingest_trades_file` may read from
+/// (default `data/ingest`). Paths from the client are resolved inside it.
+pub const INGEST_DIR_ENV: &str = "INGEST_DIR";
+
+/// Width in seconds of the bin sizes used across the app ("1m", "5m", "30m", "1hour", "1day").
+pub fn bin_size_secs(bin_size: &str) -> Option<i64> {
+    match bin_size {
+        "1m" => Some(60),
+        "5m" => Some(300),
+        "30m" => Some(1_800),
+        "1hour" => Some(3_600),
+        "1day" => Some(86_400),
+        _ => None,
+    }
+}
+
+#[server]
+pub async fn ingest_trades_file(
+    url: String,
+    user: String,
+    pass: String,
+    ns: String,
+    dbname: String,
+    path: String,
+    format: Option<IngestFormat>,
+    bin_sizes: Vec<String>,
+) -> Result<IngestReport, ServerFnError> {
+    let format = format.unwrap_or_else(|| IngestFormat::from_path(path.as_str()));
+    let root = paths::root_dir(INGEST_DIR_ENV, "data/ingest");
+    let read_path = paths::confine(&root, path.as_str()).map_err(ServerFnError::new)?;
+    let bin_widths: Vec<(String, i64)> = bin_sizes
+        .into_iter()
+        .map(|b| match bin_size_secs(b.as_str()) {
+            Some(secs) => Ok((b, secs)),
+            None => Err(ServerFnError::new(format!("unknown bin size '{b}'"))),
+        })
+        .collect::<Result<_, _>>()?;
+
+    let records = tokio::task::spawn_blocking(move || match format {
+        IngestFormat::Dbn => readers::read_dbn(&read_path),
+        IngestFormat::Csv => readers::read_csv(&read_path),
+    })
+    .await
+    .map_err(|e| ServerFnError::new(e.to_string()))?
+    .map_err(ServerFnError::new)?;
+    let (records, skipped) = records;
+    let parsed = records.len() + skipped;
+    tracing::debug!("parsed {parsed} records from {path}, {skipped} of them not trades");
+
+    let (valid, mut rejected) = validate::validate_trades(records);
+    if skipped > 0 {
+        match rejected.iter_mut().find(|(r, _)| r == validate::NOT_A_TRADE) {
+            Some((_, n)) => *n += skipped,
+            None => rejected.insert(0, (validate::NOT_A_TRADE.to_string(), skipped)),
+        }
+    }
+    let db = connect_surr(url.as_str(), user.as_str(), pass.as_str(), ns.as_str(), dbname.as_str())
+        .await?;
+    let inserted = store::insert_trades(&db, &valid)
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+    let mut bins_inserted = Vec::with_capacity(bin_widths.len());
+    for (bin_size, secs) in bin_widths {
+        let rows = bins::bins_from_trades(&valid, bin_size.as_str(), secs);
+        let n = store::insert_bins(&db, rows)
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        bins_inserted.push((bin_size, n));
+    }
+
+    Ok(IngestReport {
+        parsed,
+        inserted,
+        rejected,
+        bins_inserted,
+    })
+}
@@ -0,0 +1,134 @@
+use crate::data_structures::{Hd, SecurityData, TradeRecord};
+use chrono::DateTime;
+use dbn::decode::{DecodeRecordRef, DynDecoder};
+use dbn::{TradeMsg, VersionUpgradePolicy};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+const PRICE_SCALE: f64 = 1_000_000_000.0;
+
+impl From<&TradeMsg> for TradeRecord {
+    fn from(msg: &TradeMsg) -> Self {
+        TradeRecord {
+            hd: Hd {
+                instrument_id: msg.hd.instrument_id,
+                length: (msg.hd.record_size() / 4) as u8,
+                publisher_id: msg.hd.publisher_id as u32,
+                rtype: msg.hd.rtype as u32,
+                ts_event: msg.hd.ts_event,
+            },
+            data: SecurityData {
+                instrument_id: msg.hd.instrument_id,
+                ts_in_delta: msg.ts_in_delta,
+                size: msg.size as i64,
+                ts_recv: msg.ts_recv as i64,
+                price: msg.price,
+            },
+            sequence: msg.sequence,
+        }
+    }
+}
+
+/// Decode every `TradeMsg` in a DBN file (compression is detected from the file itself).
+/// Returns the trades and the number of other records (status, definitions, ...) skipped.
+pub fn read_dbn(path: &Path) -> Result<(Vec<TradeRecord>, usize), String> {
+    let shown = path.display();
+    let mut decoder = DynDecoder::from_file(path, VersionUpgradePolicy::UpgradeToV3)
+        .map_err(|e| format!("open {shown}: {e}"))?;
+    let mut out = Vec::new();
+    let mut skipped = 0;
+    while let Some(rec) = decoder
+        .decode_record_ref()
+        .map_err(|e| format!("decode {shown}: {e}"))?
+    {
+        match rec.get::<TradeMsg>() {
+            Some(msg) => out.push(TradeRecord::from(msg)),
+            None => skipped += 1,
+        }
+    }
+    Ok((out, skipped))
+}
+
+/// Databento timestamps are either raw nanoseconds or, with `pretty_ts`, RFC3339 strings.
+fn parse_ts(s: &str) -> Option<i64> {
+    s.parse::<i64>().ok().or_else(|| {
+        DateTime::parse_from_rfc3339(s)
+            .ok()
+            .and_then(|dt| dt.timestamp_nanos_opt())
+    })
+}
+
+/// Prices are fixed-point 1e-9 integers, or decimals when exported with `pretty_px`.
+fn parse_px(s: &str) -> Option<i64> {
+    if s.contains('.') {
+        s.parse::<f64>().ok().map(|p| (p * PRICE_SCALE).round() as i64)
+    } else {
+        s.parse::<i64>().ok()
+    }
+}
+
+/// Read a Databento trades CSV export. Columns are located by header name; without a
+/// `sequence` column the row number stands in for it. Nothing is skipped, so the count is 0.
+pub fn read_csv(path: &Path) -> Result<(Vec<TradeRecord>, usize), String> {
+    let shown = path.display();
+    let file = File::open(path).map_err(|e| format!("open {shown}: {e}"))?;
+    let mut lines = BufReader::new(file).lines();
+    let header = lines
+        .next()
+        .ok_or_else(|| format!("{shown} is empty"))?
+        .map_err(|e| e.to_string())?;
+    let names: Vec<&str> = header.split(',').map(|h| h.trim()).collect();
+    let idx = |name: &str| {
+        names
+            .iter()
+            .position(|h| *h == name)
+            .ok_or_else(|| format!("{shown} is missing the '{name}' column"))
+    };
+    let (i_recv, i_event, i_rtype, i_pub, i_inst, i_px, i_size, i_delta) = (
+        idx("ts_recv")?,
+        idx("ts_event")?,
+        idx("rtype")?,
+        idx("publisher_id")?,
+        idx("instrument_id")?,
+        idx("price")?,
+        idx("size")?,
+        idx("ts_in_delta")?,
+    );
+    let i_seq = idx("sequence").ok();
+
+    let mut out = Vec::new();
+    for (n, line) in lines.enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        let bad = || format!("{shown}: malformed row {}", n + 2);
+        let get = |i: usize| fields.get(i).copied().ok_or_else(bad);
+        let instrument_id = get(i_inst)?.parse::<u32>().map_err(|_| bad())?;
+        let ts_recv = parse_ts(get(i_recv)?).ok_or_else(bad)?;
+        let sequence = match i_seq {
+            Some(i) => get(i)?.parse::<u32>().map_err(|_| bad())?,
+            None => n as u32,
+        };
+        out.push(TradeRecord {
+            hd: Hd {
+                instrument_id,
+                length: 12,
+                publisher_id: get(i_pub)?.parse::<u32>().map_err(|_| bad())?,
+                rtype: get(i_rtype)?.parse::<u32>().map_err(|_| bad())?,
+                ts_event: parse_ts(get(i_event)?).ok_or_else(bad)? as u64,
+            },
+            data: SecurityData {
+                instrument_id,
+                ts_in_delta: get(i_delta)?.parse::<i32>().map_err(|_| bad())?,
+                size: get(i_size)?.parse::<i64>().map_err(|_| bad())?,
+                ts_recv,
+                price: parse_px(get(i_px)?).ok_or_else(bad)?,
+            },
+            sequence,
+        });
+    }
+    Ok((out, 0))
+}
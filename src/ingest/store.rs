@@ -0,0 +1,64 @@
+use crate::data_structures::TradeRecord;
+use serde_json::Value;
+use surrealdb::engine::any::Any;
+use surrealdb::Surreal;
+
+const INSERT_CHUNK: usize = 2_000;
+
+/// Bulk upsert trades, keeping `hd` nested so `hd.instrument_id` filters keep working. Records
+/// are keyed on `[instrument_id, ts_recv, sequence]`, so loading the same prints twice is a no-op.
+pub async fn insert_trades(
+    db: &Surreal<Any>,
+    trades: &[TradeRecord],
+) -> Result<usize, surrealdb::Error> {
+    for chunk in trades.chunks(INSERT_CHUNK) {
+        let rows: Vec<Value> = chunk.iter().map(|t| t.to_surreal_row()).collect();
+        db.query(
+            "FOR $r IN $rows {
+                UPSERT type::thing('trades', $r.key) CONTENT {
+                    hd: $r.hd,
+                    price: $r.price,
+                    size: $r.size,
+                    ts_in_delta: $r.ts_in_delta,
+                    ts_recv: $r.ts_recv,
+                    bin_1m: <datetime> $r.bin_1m,
+                };
+            };",
+        )
+        .bind(("rows", Value::Array(rows)))
+        .await?
+        .check()?;
+    }
+    Ok(trades.len())
+}
+
+/// Bulk upsert rows produced by `bins::bins_from_trades`, keyed on
+/// `[instrument_id, bin_size, bin]` so a re-ingested bin replaces the stored one.
+pub async fn insert_bins(db: &Surreal<Any>, bins: Vec<Value>) -> Result<usize, surrealdb::Error> {
+    let n = bins.len();
+    for chunk in bins.chunks(INSERT_CHUNK) {
+        db.query(
+            "FOR $r IN $rows {
+                UPSERT type::thing('equities_returns', [$r.instrument_id, $r.bin_size, $r.bin])
+                CONTENT {
+                    instrument_id: $r.instrument_id,
+                    bin_size: $r.bin_size,
+                    bin: <datetime> $r.bin,
+                    t0: <datetime> $r.t0,
+                    t1: <datetime> $r.t1,
+                    ret: $r.ret,
+                    mean_price: $r.mean_price,
+                    p0: $r.p0,
+                    p1: $r.p1,
+                    pmax: $r.pmax,
+                    pmin: $r.pmin,
+                    price_diff: $r.price_diff,
+                };
+            };",
+        )
+        .bind(("rows", Value::Array(chunk.to_vec())))
+        .await?
+        .check()?;
+    }
+    Ok(n)
+}
@@ -0,0 +1,58 @@
+use crate::data_structures::TradeRecord;
+use std::collections::HashSet;
+
+/// Databento's sentinel for an undefined price.
+const UNDEF_PRICE: i64 = i64::MAX;
+/// `rtype` of MBP-0 (trades) records.
+const RTYPE_TRADES: u32 = 0;
+/// Rejection reason for records that are not trades.
+pub const NOT_A_TRADE: &str = "not a trade record";
+
+/// Split records into the ones safe to insert and per-reason rejection counts.
+pub fn validate_trades(records: Vec<TradeRecord>) -> (Vec<TradeRecord>, Vec<(String, usize)>) {
+    let reasons = [
+        NOT_A_TRADE,
+        "missing instrument_id",
+        "non-positive or undefined price",
+        "non-positive size",
+        "missing ts_recv",
+        "ts_event after ts_recv",
+        "duplicate print",
+    ];
+    let mut counts = vec![0usize; reasons.len()];
+    // Same key as the stored record id (`TradeRecord::record_key`).
+    let mut seen: HashSet<(u32, i64, u32)> = HashSet::with_capacity(records.len());
+    let mut valid = Vec::with_capacity(records.len());
+
+    for r in records {
+        let reason = if r.hd.rtype != RTYPE_TRADES {
+            Some(0)
+        } else if r.hd.instrument_id == 0 {
+            Some(1)
+        } else if r.data.price <= 0 || r.data.price == UNDEF_PRICE {
+            Some(2)
+        } else if r.data.size <= 0 {
+            Some(3)
+        } else if r.data.ts_recv <= 0 {
+            Some(4)
+        } else if r.hd.ts_event > r.data.ts_recv as u64 {
+            Some(5)
+        } else if !seen.insert((r.hd.instrument_id, r.data.ts_recv, r.sequence)) {
+            Some(6)
+        } else {
+            None
+        };
+        match reason {
+            Some(i) => counts[i] += 1,
+            None => valid.push(r),
+        }
+    }
+
+    let rejected = reasons
+        .iter()
+        .zip(counts)
+        .filter(|(_, n)| *n > 0)
+        .map(|(r, n)| (r.to_string(), n))
+        .collect();
+    (valid, rejected)
+}
@@ -1,18 +1,25 @@
 pub mod charts;
 pub mod data_structures;
+pub mod datasets;
 #[cfg(feature = "server")]
 pub mod dbinst;
 pub mod helpers;
+pub mod ingest;
 pub mod model_request;
 pub mod news;
 pub mod ops;
 pub mod prompting;
+pub mod research;
 pub mod surr_queries;
 pub mod tables;
 pub mod views;
 use std::sync::{Arc, Mutex};
 
-use views::{multi_assets::MultiAsset, portfolio::Portfolio, single_assets::SingleAsset};
+use views::{
+    multi_assets::MultiAsset,
+    portfolio::{Portfolio, UniverseCtx},
+    single_assets::SingleAsset,
+};
 
 use dioxus::prelude::*;
 
@@ -27,6 +34,8 @@ enum Route {
 #[component]
 fn Home() -> Element {
     let mut selected_tab = use_signal(|| 0);
+    let universe = use_signal(|| None);
+    use_context_provider(|| UniverseCtx(universe));
     rsx! {
         style { { include_str!("./../src/css_files/home_style.css") } }
 
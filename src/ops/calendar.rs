@@ -0,0 +1,429 @@
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// Trading days per year used to annualize daily statistics.
+pub const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
+/// Exchanges with a known session, holiday and time-zone calendar.
+/// `Continuous` trades around the clock in UTC (no holidays), for 24x7 venues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Exchange {
+    #[default]
+    Nyse,
+    Nasdaq,
+    Lse,
+    Xetra,
+    Tse,
+    Continuous,
+}
+
+impl Exchange {
+    pub const ALL: [Exchange; 6] = [
+        Exchange::Nyse,
+        Exchange::Nasdaq,
+        Exchange::Lse,
+        Exchange::Xetra,
+        Exchange::Tse,
+        Exchange::Continuous,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Exchange::Nyse => "NYSE",
+            Exchange::Nasdaq => "NASDAQ",
+            Exchange::Lse => "LSE",
+            Exchange::Xetra => "XETRA",
+            Exchange::Tse => "TSE",
+            Exchange::Continuous => "24x7",
+        }
+    }
+
+    pub fn from_label(s: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|e| e.as_str() == s)
+            .unwrap_or_default()
+    }
+
+    /// IANA time zone the exchange quotes its session in.
+    pub fn tz(&self) -> Tz {
+        match self {
+            Exchange::Nyse | Exchange::Nasdaq => chrono_tz::America::New_York,
+            Exchange::Lse => chrono_tz::Europe::London,
+            Exchange::Xetra => chrono_tz::Europe::Berlin,
+            Exchange::Tse => chrono_tz::Asia::Tokyo,
+            Exchange::Continuous => chrono_tz::UTC,
+        }
+    }
+
+    /// Regular session (open, close) in local time.
+    fn hours(&self) -> (NaiveTime, NaiveTime) {
+        let t = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        match self {
+            Exchange::Nyse | Exchange::Nasdaq => (t(9, 30), t(16, 0)),
+            Exchange::Lse => (t(8, 0), t(16, 30)),
+            Exchange::Xetra => (t(9, 0), t(17, 30)),
+            Exchange::Tse => (t(9, 0), t(15, 30)),
+            Exchange::Continuous => (t(0, 0), NaiveTime::MIN),
+        }
+    }
+
+    /// Local close on half days.
+    fn early_close(&self) -> NaiveTime {
+        match self {
+            Exchange::Lse => NaiveTime::from_hms_opt(12, 30, 0).unwrap(),
+            _ => NaiveTime::from_hms_opt(13, 0, 0).unwrap(),
+        }
+    }
+
+    /// Full-day closures in `year`.
+    pub fn holidays(&self, year: i32) -> Vec<NaiveDate> {
+        let d = |m, day| NaiveDate::from_ymd_opt(year, m, day).unwrap();
+        let easter = easter_sunday(year);
+        let mut out = match self {
+            Exchange::Nyse | Exchange::Nasdaq => {
+                let mut v = vec![
+                    nth_weekday(year, 1, Weekday::Mon, 3),
+                    nth_weekday(year, 2, Weekday::Mon, 3),
+                    easter - Duration::days(2),
+                    last_weekday(year, 5, Weekday::Mon),
+                    observed_us(d(7, 4)),
+                    nth_weekday(year, 9, Weekday::Mon, 1),
+                    nth_weekday(year, 11, Weekday::Thu, 4),
+                    observed_us(d(12, 25)),
+                ];
+                // New Year's Day falling on a Saturday is not moved back into December.
+                if d(1, 1).weekday() != Weekday::Sat {
+                    v.push(observed_us(d(1, 1)));
+                }
+                if year >= 2022 {
+                    v.push(observed_us(d(6, 19)));
+                }
+                v
+            }
+            Exchange::Lse => {
+                let (christmas, boxing) = match d(12, 25).weekday() {
+                    Weekday::Fri => (d(12, 25), d(12, 28)),
+                    Weekday::Sat => (d(12, 27), d(12, 28)),
+                    Weekday::Sun => (d(12, 27), d(12, 26)),
+                    _ => (d(12, 25), d(12, 26)),
+                };
+                vec![
+                    observed_uk(d(1, 1)),
+                    easter - Duration::days(2),
+                    easter + Duration::days(1),
+                    nth_weekday(year, 5, Weekday::Mon, 1),
+                    last_weekday(year, 5, Weekday::Mon),
+                    last_weekday(year, 8, Weekday::Mon),
+                    christmas,
+                    boxing,
+                ]
+            }
+            Exchange::Xetra => vec![
+                d(1, 1),
+                easter - Duration::days(2),
+                easter + Duration::days(1),
+                d(5, 1),
+                d(12, 24),
+                d(12, 25),
+                d(12, 26),
+                d(12, 31),
+            ],
+            // Equinox days are approximated by their usual dates.
+            Exchange::Tse => vec![
+                d(1, 1),
+                d(1, 2),
+                d(1, 3),
+                nth_weekday(year, 1, Weekday::Mon, 2),
+                d(2, 11),
+                d(2, 23),
+                d(3, 20),
+                d(4, 29),
+                d(5, 3),
+                d(5, 4),
+                d(5, 5),
+                nth_weekday(year, 7, Weekday::Mon, 3),
+                d(8, 11),
+                nth_weekday(year, 9, Weekday::Mon, 3),
+                d(9, 23),
+                nth_weekday(year, 10, Weekday::Mon, 2),
+                d(11, 3),
+                d(11, 23),
+                d(12, 31),
+            ],
+            Exchange::Continuous => Vec::new(),
+        };
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    /// Trading days that close early in `year`.
+    pub fn half_days(&self, year: i32) -> Vec<NaiveDate> {
+        let d = |m, day| NaiveDate::from_ymd_opt(year, m, day).unwrap();
+        let candidates = match self {
+            Exchange::Nyse | Exchange::Nasdaq => vec![
+                d(7, 3),
+                nth_weekday(year, 11, Weekday::Thu, 4) + Duration::days(1),
+                d(12, 24),
+            ],
+            Exchange::Lse => vec![d(12, 24), d(12, 31)],
+            _ => Vec::new(),
+        };
+        candidates
+            .into_iter()
+            .filter(|day| self.is_trading_day(*day))
+            .collect()
+    }
+
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        if *self == Exchange::Continuous {
+            return true;
+        }
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+            && !self.holidays(date.year()).contains(&date)
+    }
+
+    /// Session bounds for `date` in UTC, or `None` when the exchange is closed.
+    pub fn session(&self, date: NaiveDate) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        if !self.is_trading_day(date) {
+            return None;
+        }
+        let (open, close) = self.hours();
+        if *self == Exchange::Continuous {
+            let start = Utc.from_utc_datetime(&date.and_time(open));
+            return Some((start, start + Duration::days(1)));
+        }
+        let close = if self.half_days(date.year()).contains(&date) {
+            self.early_close()
+        } else {
+            close
+        };
+        let tz = self.tz();
+        let open = tz.from_local_datetime(&date.and_time(open)).earliest()?;
+        let close = tz.from_local_datetime(&date.and_time(close)).earliest()?;
+        Some((open.with_timezone(&Utc), close.with_timezone(&Utc)))
+    }
+
+    /// Trading days (exchange-local dates) between two instants, inclusive.
+    pub fn trading_days_between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<NaiveDate> {
+        let tz = self.tz();
+        let mut day = start.with_timezone(&tz).date_naive();
+        let last = end.with_timezone(&tz).date_naive();
+        let mut out = Vec::new();
+        while day <= last {
+            if self.is_trading_day(day) {
+                out.push(day);
+            }
+            day += Duration::days(1);
+        }
+        out
+    }
+
+    /// Range covering the last `n` complete or in-progress sessions up to `now`,
+    /// e.g. "last 5 trading days, NYSE".
+    pub fn last_n_trading_days(
+        &self,
+        n: usize,
+        now: DateTime<Utc>,
+    ) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let mut day = now.with_timezone(&self.tz()).date_naive();
+        let mut sessions = Vec::with_capacity(n);
+        // A year of look-back is plenty for any realistic `n`.
+        for _ in 0..(n * 2 + 370) {
+            if sessions.len() == n {
+                break;
+            }
+            if let Some((open, close)) = self.session(day) {
+                if open <= now {
+                    sessions.push((open, close.min(now)));
+                }
+            }
+            day -= Duration::days(1);
+        }
+        let end = sessions.first()?.1;
+        let start = sessions.last()?.0;
+        Some((start, end))
+    }
+
+    /// Start times (ns since epoch) of the bins between `start` and `end` that overlap a session.
+    /// Bins are multiples of `bin_secs` since the epoch, like the stored bins (`ingest::bins`),
+    /// so e.g. the 1hour bin starting 14:00 UTC holds the NYSE open at 14:30.
+    pub fn bin_grid(&self, start: DateTime<Utc>, end: DateTime<Utc>, bin_secs: i64) -> Vec<i64> {
+        let width = bin_secs.max(1) * 1_000_000_000;
+        let (lo, hi) = (
+            start.timestamp_nanos_opt().unwrap_or_default(),
+            end.timestamp_nanos_opt().unwrap_or_default(),
+        );
+        let mut out = Vec::new();
+        // A day-wide bin can start before the first local date in range, or hold a session
+        // whose local date is after `end`.
+        let pad = Duration::seconds(bin_secs.max(1));
+        for day in self.trading_days_between(start - pad, end + pad) {
+            let Some((open, close)) = self.session(day) else {
+                continue;
+            };
+            let (open, close) = (
+                open.timestamp_nanos_opt().unwrap_or_default(),
+                close.timestamp_nanos_opt().unwrap_or_default(),
+            );
+            let mut t = open.div_euclid(width) * width;
+            while t < close {
+                if t >= lo && t <= hi {
+                    out.push(t);
+                }
+                t += width;
+            }
+        }
+        // Consecutive sessions can share a bin wider than the overnight break.
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    /// Number of `bin_secs` bins in a regular trading year.
+    pub fn bins_per_year(&self, bin_secs: i64) -> f64 {
+        let (open, close) = self.hours();
+        let session_secs = if *self == Exchange::Continuous {
+            86_400
+        } else {
+            (close - open).num_seconds()
+        };
+        let days = if *self == Exchange::Continuous {
+            365.0
+        } else {
+            TRADING_DAYS_PER_YEAR
+        };
+        let per_day = (session_secs as f64 / bin_secs.max(1) as f64).max(1.0);
+        days * per_day
+    }
+
+    /// Multiply a per-bin standard deviation by this to annualize it.
+    pub fn annualization_factor(&self, bin_secs: i64) -> f64 {
+        self.bins_per_year(bin_secs).sqrt()
+    }
+
+    /// Parse an `<input type="datetime-local">` value as exchange-local time.
+    pub fn parse_local(&self, s: &str) -> Option<DateTime<Utc>> {
+        let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M")
+            .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S"))
+            .ok()?;
+        self.tz()
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|dt| dt.with_timezone(&Utc))
+    }
+
+    /// Format an instant as a `datetime-local` value in exchange-local time.
+    pub fn format_local(&self, dt: DateTime<Utc>) -> String {
+        dt.with_timezone(&self.tz())
+            .format("%Y-%m-%dT%H:%M")
+            .to_string()
+    }
+
+    /// Axis label for a nanosecond timestamp in exchange-local time.
+    pub fn axis_label(&self, ts_nanos: i64) -> String {
+        Utc.timestamp_nanos(ts_nanos)
+            .with_timezone(&self.tz())
+            .format("%Y-%m-%d %H:%M %Z")
+            .to_string()
+    }
+}
+
+/// Anonymous Gregorian computus.
+fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u8) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n).unwrap()
+}
+
+fn last_weekday(year: i32, month: u32, weekday: Weekday) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, 5)
+        .unwrap_or_else(|| nth_weekday(year, month, weekday, 4))
+}
+
+/// US rule: Saturday holidays are observed on Friday, Sunday holidays on Monday.
+fn observed_us(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date - Duration::days(1),
+        Weekday::Sun => date + Duration::days(1),
+        _ => date,
+    }
+}
+
+/// UK rule: weekend holidays move to the following Monday.
+fn observed_uk(date: NaiveDate) -> NaiveDate {
+    let mut d = date;
+    while matches!(d.weekday(), Weekday::Sat | Weekday::Sun) {
+        d += Duration::days(1);
+    }
+    d
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn ns(dt: DateTime<Utc>) -> i64 {
+        dt.timestamp_nanos_opt().unwrap()
+    }
+
+    #[test]
+    fn grid_is_epoch_aligned_and_covers_the_session() {
+        // 2024-01-10 is a Wednesday; the NYSE session is 14:30-21:00 UTC in winter.
+        let day = |h, m| ns(utc(2024, 1, 10, h, m));
+        let (start, end) = (utc(2024, 1, 10, 0, 0), utc(2024, 1, 10, 23, 59));
+        let hourly = Exchange::Nyse.bin_grid(start, end, 3_600);
+        assert_eq!(hourly, (14..=20).map(|h| day(h, 0)).collect::<Vec<_>>());
+        let half_hourly = Exchange::Nyse.bin_grid(start, end, 1_800);
+        assert_eq!(half_hourly.len(), 13);
+        assert_eq!(half_hourly[0], day(14, 30));
+        assert_eq!(*half_hourly.last().unwrap(), day(20, 30));
+        assert_eq!(Exchange::Nyse.bin_grid(start, end, 60).len(), 390);
+    }
+
+    #[test]
+    fn daily_grid_skips_weekends_and_holidays() {
+        // Fri 2024-01-12, then the weekend and Martin Luther King Day (Mon 01-15).
+        let grid = Exchange::Nyse.bin_grid(utc(2024, 1, 12, 0, 0), utc(2024, 1, 16, 0, 0), 86_400);
+        assert_eq!(grid, vec![ns(utc(2024, 1, 12, 0, 0)), ns(utc(2024, 1, 16, 0, 0))]);
+    }
+
+    #[test]
+    fn sessions_follow_daylight_saving_and_half_days() {
+        let (open, close) = Exchange::Nyse
+            .session(NaiveDate::from_ymd_opt(2024, 3, 15).unwrap())
+            .unwrap();
+        assert_eq!((open, close), (utc(2024, 3, 15, 13, 30), utc(2024, 3, 15, 20, 0)));
+        let (_, close) = Exchange::Nyse
+            .session(NaiveDate::from_ymd_opt(2024, 11, 29).unwrap())
+            .unwrap();
+        assert_eq!(close, utc(2024, 11, 29, 18, 0));
+        assert!(Exchange::Nyse
+            .session(NaiveDate::from_ymd_opt(2024, 3, 29).unwrap())
+            .is_none());
+    }
+}
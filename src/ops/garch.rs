@@ -0,0 +1,392 @@
+// GARCH-family conditional volatility fitted by Gaussian maximum likelihood. The likelihood is
+// maximized with Nelder-Mead over unconstrained transforms of the parameters, so positivity
+// and stationarity hold at every step.
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Fewest returns a model is fitted on.
+pub const MIN_OBS: usize = 50;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VolModel {
+    #[default]
+    Garch,
+    GjrGarch,
+    Egarch,
+}
+
+impl VolModel {
+    pub const ALL: [VolModel; 3] = [VolModel::Garch, VolModel::GjrGarch, VolModel::Egarch];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VolModel::Garch => "garch",
+            VolModel::GjrGarch => "gjr",
+            VolModel::Egarch => "egarch",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            VolModel::Garch => "GARCH(1,1)",
+            VolModel::GjrGarch => "GJR-GARCH(1,1)",
+            VolModel::Egarch => "EGARCH(1,1)",
+        }
+    }
+
+    pub fn from_label(s: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|m| m.as_str() == s)
+            .unwrap_or_default()
+    }
+
+    fn n_params(&self) -> usize {
+        match self {
+            VolModel::Garch => 3,
+            VolModel::GjrGarch | VolModel::Egarch => 4,
+        }
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        match self {
+            VolModel::Garch => &["omega", "alpha", "beta"],
+            VolModel::GjrGarch => &["omega", "alpha", "gamma", "beta"],
+            VolModel::Egarch => &["omega", "alpha", "gamma", "beta"],
+        }
+    }
+
+    /// Model parameters from the optimizer's unconstrained vector. GARCH and GJR weights go
+    /// through a softmax against 1 so that alpha + gamma/2 + beta < 1; EGARCH's beta is a tanh.
+    fn params(&self, u: &[f64]) -> Vec<f64> {
+        match self {
+            VolModel::Garch => {
+                let (ea, eb) = (u[1].exp(), u[2].exp());
+                let d = 1.0 + ea + eb;
+                vec![u[0].exp(), ea / d, eb / d]
+            }
+            VolModel::GjrGarch => {
+                let (ea, eg, eb) = (u[1].exp(), u[2].exp(), u[3].exp());
+                let d = 1.0 + ea + eg + eb;
+                vec![u[0].exp(), ea / d, 2.0 * eg / d, eb / d]
+            }
+            VolModel::Egarch => vec![u[0], u[1], u[2], u[3].tanh()],
+        }
+    }
+
+    /// Starting point in unconstrained space for a unit-variance series.
+    fn start(&self) -> Vec<f64> {
+        match self {
+            // omega 0.05, alpha 0.05, beta 0.90
+            VolModel::Garch => vec![0.05_f64.ln(), 1.0_f64.ln(), 18.0_f64.ln()],
+            // omega 0.05, alpha 0.03, gamma 0.06, beta 0.90
+            VolModel::GjrGarch => vec![0.05_f64.ln(), 0.6_f64.ln(), 0.6_f64.ln(), 18.0_f64.ln()],
+            // omega ~0, alpha 0.1, gamma -0.05, beta 0.95
+            VolModel::Egarch => vec![0.0, 0.1, -0.05, 0.95_f64.atanh()],
+        }
+    }
+
+    /// Variance after a period with shock `e` and variance `h`.
+    fn next_variance(&self, p: &[f64], e: f64, h: f64) -> f64 {
+        match self {
+            VolModel::Garch => p[0] + p[1] * e * e + p[2] * h,
+            VolModel::GjrGarch => {
+                let neg = if e < 0.0 { p[2] } else { 0.0 };
+                p[0] + (p[1] + neg) * e * e + p[3] * h
+            }
+            VolModel::Egarch => {
+                let z = e / h.sqrt();
+                (p[0] + p[1] * (z.abs() - (2.0 / PI).sqrt()) + p[2] * z + p[3] * h.ln())
+                    .clamp(-50.0, 50.0)
+                    .exp()
+            }
+        }
+    }
+
+    /// Conditional variances h_1..h_{T+1}; h_1 is the sample variance.
+    fn variances(&self, p: &[f64], e: &[f64], h0: f64) -> Vec<f64> {
+        let mut h = Vec::with_capacity(e.len() + 1);
+        h.push(h0);
+        for t in 0..e.len() {
+            let next = self.next_variance(p, e[t], h[t]);
+            h.push(next);
+        }
+        h
+    }
+
+    /// Per-period persistence of variance shocks.
+    fn persistence(&self, p: &[f64]) -> f64 {
+        match self {
+            VolModel::Garch => p[1] + p[2],
+            VolModel::GjrGarch => p[1] + p[2] / 2.0 + p[3],
+            VolModel::Egarch => p[3],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GarchFit {
+    pub model: VolModel,
+    /// `(name, value)` in the units of the returns (omega is a variance, or a log variance
+    /// for EGARCH).
+    pub params: Vec<(String, f64)>,
+    pub mean: f64,
+    pub loglik: f64,
+    pub aic: f64,
+    pub bic: f64,
+    pub persistence: f64,
+    /// Unconditional per-period volatility; NaN when the fit is not stationary.
+    pub long_run_vol: f64,
+    /// Conditional per-period volatility for each return.
+    pub cond_vol: Vec<f64>,
+    /// Per-period volatility forecasts for 1..=horizon steps after the last return.
+    pub forecast: Vec<f64>,
+    pub converged: bool,
+}
+
+/// Gaussian log-likelihood of shocks `e` with variances `h` (only the first `e.len()` are used).
+fn loglik(e: &[f64], h: &[f64]) -> f64 {
+    e.iter()
+        .zip(h)
+        .map(|(e, h)| -0.5 * ((2.0 * PI).ln() + h.ln() + e * e / h))
+        .sum()
+}
+
+/// Minimize `f` from `x0` with the Nelder-Mead simplex. Returns the best point, its value and
+/// whether the simplex collapsed below `tol` within `max_iter` iterations.
+fn nelder_mead(
+    f: impl Fn(&[f64]) -> f64,
+    x0: &[f64],
+    step: f64,
+    max_iter: usize,
+    tol: f64,
+) -> (Vec<f64>, f64, bool) {
+    let n = x0.len();
+    let mut simplex: Vec<(Vec<f64>, f64)> = (0..=n)
+        .map(|i| {
+            let mut x = x0.to_vec();
+            if i > 0 {
+                x[i - 1] += step;
+            }
+            let fx = f(&x);
+            (x, fx)
+        })
+        .collect();
+    let point = |a: &[f64], b: &[f64], t: f64| -> Vec<f64> {
+        a.iter().zip(b).map(|(a, b)| a + t * (b - a)).collect()
+    };
+    for _ in 0..max_iter {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        if (simplex[n].1 - simplex[0].1).abs() <= tol * (1.0 + simplex[0].1.abs()) {
+            return (simplex[0].0.clone(), simplex[0].1, true);
+        }
+        let centroid: Vec<f64> = (0..n)
+            .map(|j| simplex[..n].iter().map(|(x, _)| x[j]).sum::<f64>() / n as f64)
+            .collect();
+        let worst = simplex[n].clone();
+        let reflected = point(&centroid, &worst.0, -1.0);
+        let fr = f(&reflected);
+        if fr < simplex[0].1 {
+            let expanded = point(&centroid, &worst.0, -2.0);
+            let fe = f(&expanded);
+            simplex[n] = if fe < fr {
+                (expanded, fe)
+            } else {
+                (reflected, fr)
+            };
+        } else if fr < simplex[n - 1].1 {
+            simplex[n] = (reflected, fr);
+        } else {
+            let contracted = if fr < worst.1 {
+                point(&centroid, &reflected, 0.5)
+            } else {
+                point(&centroid, &worst.0, 0.5)
+            };
+            let fc = f(&contracted);
+            if fc < worst.1.min(fr) {
+                simplex[n] = (contracted, fc);
+            } else {
+                let best = simplex[0].0.clone();
+                for (x, fx) in simplex.iter_mut().skip(1) {
+                    *x = point(&best, x, 0.5);
+                    *fx = f(x);
+                }
+            }
+        }
+    }
+    simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+    (simplex[0].0.clone(), simplex[0].1, false)
+}
+
+/// Fit `model` to `returns` (demeaned first) and forecast `horizon` steps ahead. The series is
+/// scaled to unit variance for the optimizer and the parameters are mapped back afterwards.
+pub fn fit(model: VolModel, returns: &[f64], horizon: usize) -> Result<GarchFit, String> {
+    let r: Vec<f64> = returns.iter().copied().filter(|r| r.is_finite()).collect();
+    if r.len() < MIN_OBS {
+        return Err(format!(
+            "{} needs at least {MIN_OBS} returns, got {}",
+            model.label(),
+            r.len()
+        ));
+    }
+    let t = r.len() as f64;
+    let mean = r.iter().sum::<f64>() / t;
+    let var = r.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / t;
+    if var <= 0.0 {
+        return Err("returns have no variance".to_string());
+    }
+    let scale = var.sqrt();
+    let e: Vec<f64> = r.iter().map(|x| (x - mean) / scale).collect();
+
+    let objective = |u: &[f64]| {
+        let p = model.params(u);
+        let h = model.variances(&p, &e, 1.0);
+        let ll = loglik(&e, &h);
+        if ll.is_finite() {
+            -ll
+        } else {
+            f64::INFINITY
+        }
+    };
+    let (u, _, converged) = nelder_mead(objective, &model.start(), 0.5, 2000, 1e-9);
+    let p = model.params(&u);
+    let h = model.variances(&p, &e, 1.0);
+
+    // Back to the units of the returns: variances scale by var, log variances shift by ln var.
+    let s2 = scale * scale;
+    let mut params = p.clone();
+    match model {
+        VolModel::Garch | VolModel::GjrGarch => params[0] *= s2,
+        VolModel::Egarch => params[0] += (1.0 - p[3]) * s2.ln(),
+    }
+    let persistence = model.persistence(&p);
+    let long_run_vol = match model {
+        VolModel::Garch | VolModel::GjrGarch if persistence < 1.0 => {
+            (params[0] / (1.0 - persistence)).sqrt()
+        }
+        VolModel::Egarch if persistence.abs() < 1.0 => {
+            (params[0] / (1.0 - persistence) / 2.0).exp()
+        }
+        _ => f64::NAN,
+    };
+
+    // Forecasts: the first step is known exactly; later steps replace the squared shock by its
+    // expectation (for EGARCH, the shock terms by their zero means).
+    let mut forecast = Vec::with_capacity(horizon);
+    let mut next = h[e.len()];
+    for step in 0..horizon {
+        if step > 0 {
+            next = match model {
+                VolModel::Garch | VolModel::GjrGarch => p[0] + persistence * next,
+                VolModel::Egarch => (p[0] + p[3] * next.ln()).exp(),
+            };
+        }
+        forecast.push(next.sqrt() * scale);
+    }
+
+    let ll = loglik(&e, &h) - t * scale.ln();
+    let k = model.n_params() as f64 + 1.0;
+    Ok(GarchFit {
+        model,
+        params: model
+            .param_names()
+            .iter()
+            .map(|n| n.to_string())
+            .zip(params)
+            .collect(),
+        mean,
+        loglik: ll,
+        aic: 2.0 * k - 2.0 * ll,
+        bic: k * t.ln() - 2.0 * ll,
+        persistence,
+        long_run_vol,
+        cond_vol: h[..e.len()].iter().map(|h| h.sqrt() * scale).collect(),
+        forecast,
+        converged,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// GARCH(1,1) path with standard normal shocks from a fixed-seed xorshift generator.
+    fn simulate(omega: f64, alpha: f64, beta: f64, n: usize) -> Vec<f64> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut uniform = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            ((state >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+        };
+        let mut h = omega / (1.0 - alpha - beta);
+        let mut out = Vec::with_capacity(n);
+        for _ in 0..n {
+            let z = (-2.0 * uniform().ln()).sqrt() * (2.0 * PI * uniform()).cos();
+            let e = h.sqrt() * z;
+            out.push(e);
+            h = omega + alpha * e * e + beta * h;
+        }
+        out
+    }
+
+    fn param(fit: &GarchFit, name: &str) -> f64 {
+        fit.params.iter().find(|(n, _)| n == name).unwrap().1
+    }
+
+    #[test]
+    fn garch_recovers_simulated_parameters() {
+        let r = simulate(1e-5, 0.10, 0.85, 5_000);
+        let fit = fit(VolModel::Garch, &r, 10).unwrap();
+        assert!(
+            (param(&fit, "alpha") - 0.10).abs() < 0.04,
+            "{:?}",
+            fit.params
+        );
+        assert!(
+            (param(&fit, "beta") - 0.85).abs() < 0.06,
+            "{:?}",
+            fit.params
+        );
+        assert!((fit.persistence - 0.95).abs() < 0.03);
+        // Unconditional vol sqrt(1e-5 / 0.05) ≈ 0.0141.
+        assert!(
+            (fit.long_run_vol - 0.0141).abs() < 0.004,
+            "{}",
+            fit.long_run_vol
+        );
+        assert_eq!(fit.cond_vol.len(), r.len());
+        assert_eq!(fit.forecast.len(), 10);
+        // Forecasts head back towards the long-run level.
+        let gap = |v: f64| (v - fit.long_run_vol).abs();
+        assert!(gap(fit.forecast[9]) <= gap(fit.forecast[0]) + 1e-12);
+    }
+
+    #[test]
+    fn fits_do_not_depend_on_the_units_of_the_returns() {
+        let r = simulate(1e-5, 0.10, 0.85, 2_000);
+        let pct: Vec<f64> = r.iter().map(|x| x * 100.0).collect();
+        for model in VolModel::ALL {
+            let a = fit(model, &r, 1).unwrap();
+            let b = fit(model, &pct, 1).unwrap();
+            assert!(
+                (a.persistence - b.persistence).abs() < 1e-3,
+                "{}",
+                model.label()
+            );
+            if a.long_run_vol.is_finite() {
+                assert!(
+                    (b.long_run_vol / a.long_run_vol / 100.0 - 1.0).abs() < 1e-2,
+                    "{}",
+                    model.label()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn short_or_flat_series_are_rejected() {
+        assert!(fit(VolModel::Garch, &[0.01; 10], 1).is_err());
+        assert!(fit(VolModel::Garch, &vec![0.0; MIN_OBS + 10], 1).is_err());
+    }
+}
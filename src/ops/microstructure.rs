@@ -0,0 +1,414 @@
+// Market microstructure measures from trade prints (price, size, ts_in_delta, ts_recv): trade
+// signing by the tick rule, order flow per bin and the spread, price-impact and activity
+// statistics built on them. Prices are in dollars (the trades query undoes the 1e-9 fixed
+// point) and anything that differences consecutive prints or bins stays within one session.
+use crate::charts::boxplot::BoxStats;
+use crate::ops::calendar::Exchange;
+use crate::ops::MyMatrix;
+use chrono::{NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Exact nanoseconds since the first print, next to the f64-rounded `ts_recv`.
+pub const TS_OFFSET_COL: &str = "ts_offset";
+
+/// Upper edges of the trade-size histogram buckets; the last bucket is open-ended.
+pub const SIZE_BUCKETS: [f64; 5] = [1.0, 10.0, 100.0, 1_000.0, 10_000.0];
+
+/// Signed and unsigned activity in one time bin.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct FlowBin {
+    /// Bin start, nanoseconds since the epoch.
+    pub start: i64,
+    pub trades: usize,
+    pub volume: f64,
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+    /// (buy - sell) / (buy + sell) over the signed volume; NaN when nothing was signed.
+    pub imbalance: f64,
+    pub dollar_volume: f64,
+    pub last_price: f64,
+    /// Log return from the previous bin's last price; NaN for the first bin of a session.
+    pub ret: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct MicrostructureReport {
+    pub n_trades: usize,
+    /// Share of signed volume classified as buyer-initiated.
+    pub buy_share: f64,
+    pub bins: Vec<FlowBin>,
+    /// Roll (1984) spread in price units, 2 sqrt(-cov(dp_t, dp_{t-1})); NaN when the
+    /// autocovariance is not negative.
+    pub roll_spread: f64,
+    /// `roll_spread` relative to the mean price, in basis points.
+    pub roll_spread_bps: f64,
+    /// Amihud illiquidity: mean |bin return| per million of dollar volume.
+    pub amihud: f64,
+    /// Kyle's lambda: slope of the bin price change on signed (buy - sell) volume.
+    pub kyle_lambda: f64,
+    pub kyle_t: f64,
+    pub kyle_r2: f64,
+    pub size_dist: Option<BoxStats>,
+    /// `(bucket label, trade count)` over `SIZE_BUCKETS`.
+    pub size_hist: Vec<(String, usize)>,
+    /// Seconds between consecutive prints of the same session.
+    pub inter_arrival: Option<BoxStats>,
+    /// Standard deviation over mean of the inter-arrival times (1 for a Poisson process).
+    pub inter_arrival_cv: f64,
+    /// Share of within-session gaps of zero nanoseconds.
+    pub simultaneous_share: f64,
+}
+
+fn column(m: &MyMatrix, name: &str) -> Option<Vec<f64>> {
+    m.find_index(name)
+        .filter(|&j| j < m.data.ncols())
+        .map(|j| m.data.column(j).iter().copied().collect())
+}
+
+/// Print times in nanoseconds: `ts_recv` rebased onto the exact `TS_OFFSET_COL` when present.
+fn timestamps(m: &MyMatrix) -> Option<Vec<i64>> {
+    let ts = column(m, "ts_recv")?;
+    Some(match column(m, TS_OFFSET_COL) {
+        Some(offset) => {
+            let base = ts
+                .first()
+                .zip(offset.first())
+                .map_or(0, |(t, o)| *t as i64 - *o as i64);
+            offset.iter().map(|o| base + *o as i64).collect()
+        }
+        None => ts.iter().map(|t| *t as i64).collect(),
+    })
+}
+
+/// Trading date of the session holding each print, `None` outside regular hours.
+fn sessions(ts: &[i64], exchange: Exchange) -> Vec<Option<NaiveDate>> {
+    let tz = exchange.tz();
+    let mut cache: Option<(NaiveDate, Option<(i64, i64)>)> = None;
+    ts.iter()
+        .map(|t| {
+            let date = Utc.timestamp_nanos(*t).with_timezone(&tz).date_naive();
+            let bounds = match cache {
+                Some((d, b)) if d == date => b,
+                _ => {
+                    let b = exchange.session(date).map(|(open, close)| {
+                        (
+                            open.timestamp_nanos_opt().unwrap_or_default(),
+                            close.timestamp_nanos_opt().unwrap_or_default(),
+                        )
+                    });
+                    cache = Some((date, b));
+                    b
+                }
+            };
+            bounds
+                .filter(|(open, close)| open <= t && t <= close)
+                .map(|_| date)
+        })
+        .collect()
+}
+
+/// Nanoseconds between consecutive prints of the same session; overnight breaks and prints
+/// outside regular hours are skipped.
+fn inter_arrival_ns(ts: &[i64], sessions: &[Option<NaiveDate>]) -> Vec<i64> {
+    (1..ts.len())
+        .filter(|&i| sessions[i].is_some() && sessions[i] == sessions[i - 1])
+        .map(|i| ts[i] - ts[i - 1])
+        .collect()
+}
+
+/// Tick rule: +1 after an uptick, -1 after a downtick, and the previous sign after a zero tick.
+/// Prints before the first price change are 0 (unsigned).
+pub fn tick_signs(prices: &[f64]) -> Vec<i8> {
+    let mut out = Vec::with_capacity(prices.len());
+    let mut last = 0_i8;
+    for (i, p) in prices.iter().enumerate() {
+        if i > 0 {
+            if *p > prices[i - 1] {
+                last = 1;
+            } else if *p < prices[i - 1] {
+                last = -1;
+            }
+        }
+        out.push(last);
+    }
+    out
+}
+
+/// Roll's implied spread from the first-order autocovariance of price changes.
+pub fn roll_spread(prices: &[f64]) -> f64 {
+    let dp: Vec<f64> = prices.windows(2).map(|w| w[1] - w[0]).collect();
+    if dp.len() < 3 {
+        return f64::NAN;
+    }
+    let mean = dp.iter().sum::<f64>() / dp.len() as f64;
+    let cov = dp
+        .windows(2)
+        .map(|w| (w[1] - mean) * (w[0] - mean))
+        .sum::<f64>()
+        / (dp.len() - 1) as f64;
+    if cov < 0.0 {
+        2.0 * (-cov).sqrt()
+    } else {
+        f64::NAN
+    }
+}
+
+/// Group prints into `bin_secs` bins on `ts_recv`, signing volume with `signs`. Also returns
+/// the session of each bin's last print; returns only chain bins of the same session.
+fn flow_bins(
+    price: &[f64],
+    size: &[f64],
+    ts: &[i64],
+    sessions: &[Option<NaiveDate>],
+    signs: &[i8],
+    bin_secs: i64,
+) -> (Vec<FlowBin>, Vec<Option<NaiveDate>>) {
+    let bin_ns = bin_secs.max(1) * 1_000_000_000;
+    let mut bins: Vec<FlowBin> = Vec::new();
+    let mut bin_sessions: Vec<Option<NaiveDate>> = Vec::new();
+    for i in 0..price.len() {
+        let start = ts[i].div_euclid(bin_ns) * bin_ns;
+        if bins.last().is_none_or(|b| b.start != start) {
+            bins.push(FlowBin {
+                start,
+                ..FlowBin::default()
+            });
+            bin_sessions.push(None);
+        }
+        *bin_sessions.last_mut().unwrap() = sessions[i];
+        let b = bins.last_mut().unwrap();
+        b.trades += 1;
+        b.volume += size[i];
+        b.dollar_volume += size[i] * price[i];
+        match signs[i] {
+            1 => b.buy_volume += size[i],
+            -1 => b.sell_volume += size[i],
+            _ => {}
+        }
+        b.last_price = price[i];
+    }
+    for k in 0..bins.len() {
+        let signed = bins[k].buy_volume + bins[k].sell_volume;
+        bins[k].imbalance = if signed > 0.0 {
+            (bins[k].buy_volume - bins[k].sell_volume) / signed
+        } else {
+            f64::NAN
+        };
+        bins[k].ret = if k > 0
+            && same_session(&bin_sessions, k)
+            && bins[k - 1].last_price > 0.0
+            && bins[k].last_price > 0.0
+        {
+            (bins[k].last_price / bins[k - 1].last_price).ln()
+        } else {
+            f64::NAN
+        };
+    }
+    (bins, bin_sessions)
+}
+
+fn same_session(sessions: &[Option<NaiveDate>], k: usize) -> bool {
+    sessions[k].is_some() && sessions[k] == sessions[k - 1]
+}
+
+/// OLS slope, its t-stat and R^2 of `y` on `x` with an intercept.
+fn ols(x: &[f64], y: &[f64]) -> (f64, f64, f64) {
+    let n = x.len() as f64;
+    if x.len() < 3 {
+        return (f64::NAN, f64::NAN, f64::NAN);
+    }
+    let (mx, my) = (x.iter().sum::<f64>() / n, y.iter().sum::<f64>() / n);
+    let sxx: f64 = x.iter().map(|v| (v - mx).powi(2)).sum();
+    let syy: f64 = y.iter().map(|v| (v - my).powi(2)).sum();
+    let sxy: f64 = x.iter().zip(y).map(|(a, b)| (a - mx) * (b - my)).sum();
+    if sxx <= 0.0 {
+        return (f64::NAN, f64::NAN, f64::NAN);
+    }
+    let slope = sxy / sxx;
+    let sse = (syy - slope * sxy).max(0.0);
+    let se = (sse / (n - 2.0) / sxx).sqrt();
+    let r2 = if syy > 0.0 {
+        slope * sxy / syy
+    } else {
+        f64::NAN
+    };
+    (slope, slope / se, r2)
+}
+
+/// All measures for the trades matrix, with order flow aggregated into `bin_secs` bins and
+/// sessions taken from `exchange`.
+pub fn analyze(m: &MyMatrix, bin_secs: i64, exchange: Exchange) -> MicrostructureReport {
+    let price = column(m, "price").unwrap_or_default();
+    let n = price.len();
+    let size = column(m, "size").unwrap_or_else(|| vec![1.0; n]);
+    let ts = timestamps(m).unwrap_or_default();
+    let sessions = sessions(&ts, exchange);
+    let signs = tick_signs(&price);
+
+    let (buy, sell) = signs
+        .iter()
+        .zip(&size)
+        .fold((0.0, 0.0), |(b, s), (sign, q)| match sign {
+            1 => (b + q, s),
+            -1 => (b, s + q),
+            _ => (b, s),
+        });
+    let mean_price = price.iter().sum::<f64>() / n.max(1) as f64;
+    let roll = roll_spread(&price);
+
+    let (bins, bin_sessions) = if ts.len() == n {
+        flow_bins(&price, &size, &ts, &sessions, &signs, bin_secs)
+    } else {
+        Default::default()
+    };
+    let amihud_terms: Vec<f64> = bins
+        .iter()
+        .filter(|b| b.ret.is_finite() && b.dollar_volume > 0.0)
+        .map(|b| b.ret.abs() / b.dollar_volume * 1e6)
+        .collect();
+    let (flow, dp): (Vec<f64>, Vec<f64>) = (1..bins.len())
+        .filter(|&k| same_session(&bin_sessions, k))
+        .map(|k| {
+            (
+                bins[k].buy_volume - bins[k].sell_volume,
+                bins[k].last_price - bins[k - 1].last_price,
+            )
+        })
+        .unzip();
+    let (kyle_lambda, kyle_t, kyle_r2) = ols(&flow, &dp);
+
+    let mut size_hist: Vec<(String, usize)> = Vec::new();
+    let mut lower = 0.0;
+    for upper in SIZE_BUCKETS {
+        let count = size.iter().filter(|q| **q > lower && **q <= upper).count();
+        let label = if lower == 0.0 {
+            format!("<= {upper}")
+        } else {
+            format!("{lower}-{upper}")
+        };
+        size_hist.push((label, count));
+        lower = upper;
+    }
+    size_hist.push((
+        format!("> {lower}"),
+        size.iter().filter(|q| **q > lower).count(),
+    ));
+
+    let gaps = inter_arrival_ns(&ts, &sessions);
+    let inter_arrival = BoxStats::from_values(gaps.iter().map(|g| *g as f64 / 1e9));
+
+    MicrostructureReport {
+        n_trades: n,
+        buy_share: if buy + sell > 0.0 {
+            buy / (buy + sell)
+        } else {
+            f64::NAN
+        },
+        bins,
+        roll_spread: roll,
+        roll_spread_bps: roll / mean_price * 1e4,
+        amihud: if amihud_terms.is_empty() {
+            f64::NAN
+        } else {
+            amihud_terms.iter().sum::<f64>() / amihud_terms.len() as f64
+        },
+        kyle_lambda,
+        kyle_t,
+        kyle_r2,
+        size_dist: BoxStats::from_values(size.iter().copied()),
+        size_hist,
+        inter_arrival_cv: inter_arrival.map_or(f64::NAN, |s| s.sd / s.mean),
+        inter_arrival,
+        simultaneous_share: if gaps.is_empty() {
+            f64::NAN
+        } else {
+            gaps.iter().filter(|g| **g == 0).count() as f64 / gaps.len() as f64
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+    use nalgebra::DMatrix;
+
+    fn matrix(cols: &[(&str, Vec<f64>)]) -> MyMatrix {
+        let rows = cols[0].1.len();
+        MyMatrix {
+            data: DMatrix::from_fn(rows, cols.len(), |i, j| cols[j].1[i]),
+            colnames_enum: Some(
+                cols.iter()
+                    .enumerate()
+                    .map(|(j, (name, _))| (j, name.to_string()))
+                    .collect(),
+            ),
+            descrips: (0.0, 0.0, 0.0, 0.0, 0.0, 0.0),
+        }
+    }
+
+    fn ns(s: &str) -> i64 {
+        DateTime::parse_from_rfc3339(s)
+            .unwrap()
+            .timestamp_nanos_opt()
+            .unwrap()
+    }
+
+    #[test]
+    fn tick_signs_carry_through_zero_ticks() {
+        let signs = tick_signs(&[10.0, 10.0, 10.1, 10.1, 10.0, 10.0, 10.2]);
+        assert_eq!(signs, vec![0, 0, 1, 1, -1, -1, 1]);
+        assert!(tick_signs(&[]).is_empty());
+    }
+
+    #[test]
+    fn roll_spread_from_bid_ask_bounce() {
+        // dp = .1, -.1, .1, -.1, .1: mean .02, lag-one autocovariance -.0096.
+        let bounce = roll_spread(&[10.0, 10.1, 10.0, 10.1, 10.0, 10.1]);
+        assert!((bounce - 2.0 * 0.0096_f64.sqrt()).abs() < 1e-9);
+        // A steady trend has no negative autocovariance, and three prints are too few.
+        assert!(roll_spread(&[1.0, 2.0, 3.0, 4.0, 5.0]).is_nan());
+        assert!(roll_spread(&[10.0, 10.1, 10.0]).is_nan());
+    }
+
+    #[test]
+    fn gaps_and_returns_stay_within_a_session() {
+        // Three prints 100 ns apart in each of two NYSE sessions, the second a minute later
+        // and $10 higher. ts_recv alone cannot resolve 100 ns at this magnitude.
+        let mut ts = Vec::new();
+        for day in ["2024-03-14", "2024-03-15"] {
+            for minute in ["15:00", "15:01"] {
+                let t = ns(&format!("{day}T{minute}:00Z"));
+                ts.extend([t, t + 100, t + 200]);
+            }
+        }
+        let price: Vec<f64> = (0..ts.len())
+            .map(|i| if i < 6 { 100.0 } else { 110.0 } + 0.01 * (i % 3) as f64)
+            .collect();
+        let m = matrix(&[
+            ("price", price),
+            ("size", vec![100.0; ts.len()]),
+            ("ts_recv", ts.iter().map(|t| *t as f64).collect()),
+            (
+                TS_OFFSET_COL,
+                ts.iter().map(|t| (t - ts[0]) as f64).collect(),
+            ),
+        ]);
+        let report = analyze(&m, 60, Exchange::Nyse);
+
+        // Two minute breaks per session, no overnight break.
+        let gaps = report.inter_arrival.unwrap();
+        assert_eq!(gaps.n, 2 * 5);
+        assert!(gaps.p95 <= 60.0);
+        assert_eq!(report.simultaneous_share, 0.0);
+
+        // Four one-minute bins; only the second of each session has a return.
+        assert_eq!(report.bins.len(), 4);
+        let finite: Vec<bool> = report.bins.iter().map(|b| b.ret.is_finite()).collect();
+        assert_eq!(finite, vec![false, true, false, true]);
+        assert!(report
+            .bins
+            .iter()
+            .all(|b| b.ret.is_nan() || b.ret.abs() < 1e-3));
+    }
+}
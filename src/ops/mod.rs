@@ -1,5 +1,10 @@
+pub mod calendar;
 pub mod datetimeops;
+pub mod garch;
+pub mod microstructure;
 pub mod multi_type_mat;
+pub mod quality;
+pub mod stats;
 use chrono::{DateTime, Utc};
 use dioxus::prelude::*;
 use nalgebra::DMatrix;
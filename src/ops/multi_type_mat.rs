@@ -1,8 +1,8 @@
 use chrono::{DateTime, Utc};
 use dioxus::prelude::*;
 use linfa::dataset::DatasetBase;
-use linfa::traits::{Fit, Predict};
-use linfa_clustering::{KMeans, KMeansInit};
+use linfa::traits::{Fit, Predict, Transformer};
+use linfa_clustering::{Dbscan, GaussianMixtureModel, KMeans, KMeansInit};
 use linfa_reduction::Pca;
 use nalgebra::DMatrix;
 use ndarray::{Array2, ArrayBase, Data, Ix2};
@@ -30,6 +30,160 @@ pub struct MyMmMatrix {
     pub descrips: (f64, f64, f64, f64, f64, f64),
 }
 
+/// PCA settings chosen in the UI. An empty `columns` list means every factor column
+/// (`MyMmMatrix::pca_candidate_columns`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PcaConfig {
+    pub k: usize,
+    pub columns: Vec<String>,
+    /// Scale every column to unit variance (correlation PCA) instead of only centering it.
+    pub standardize: bool,
+}
+
+impl Default for PcaConfig {
+    fn default() -> Self {
+        PcaConfig {
+            k: 2,
+            columns: Vec::new(),
+            standardize: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PcaFit {
+    /// Columns that entered the fit (near-constant columns are dropped).
+    pub columns: Vec<String>,
+    /// Row scores, n x k.
+    pub scores: Array2<f64>,
+    /// Loadings, k x columns.len().
+    pub components: Array2<f64>,
+    /// Every eigenvalue of the covariance matrix, descending (for the scree plot).
+    pub eigenvalues: Vec<f64>,
+}
+
+impl PcaFit {
+    /// Share of total variance per component, over all components.
+    pub fn explained_ratio(&self) -> Vec<f64> {
+        let total: f64 = self.eigenvalues.iter().sum();
+        if total <= 0.0 {
+            return vec![0.0; self.eigenvalues.len()];
+        }
+        self.eigenvalues.iter().map(|v| v / total).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClusterAlgo {
+    #[default]
+    KMeans,
+    Dbscan,
+    Gmm,
+}
+
+impl ClusterAlgo {
+    pub const ALL: [ClusterAlgo; 3] = [ClusterAlgo::KMeans, ClusterAlgo::Dbscan, ClusterAlgo::Gmm];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClusterAlgo::KMeans => "kmeans",
+            ClusterAlgo::Dbscan => "dbscan",
+            ClusterAlgo::Gmm => "gmm",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ClusterAlgo::KMeans => "k-means",
+            ClusterAlgo::Dbscan => "DBSCAN",
+            ClusterAlgo::Gmm => "Gaussian mixture",
+        }
+    }
+
+    pub fn from_label(s: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|a| a.as_str() == s)
+            .unwrap_or_default()
+    }
+}
+
+/// Clustering settings chosen in the UI. Columns are always standardized; an empty `columns`
+/// list means every factor column. `k: None` picks the k in `2..=k_max` with the best
+/// silhouette. DBSCAN ignores `k` and uses `eps` and `min_points` instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClusterConfig {
+    pub algo: ClusterAlgo,
+    pub k: Option<usize>,
+    pub k_max: usize,
+    /// DBSCAN neighbourhood radius, in standard deviations.
+    pub eps: f64,
+    pub min_points: usize,
+    pub columns: Vec<String>,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        ClusterConfig {
+            algo: ClusterAlgo::KMeans,
+            k: None,
+            k_max: 8,
+            eps: 0.5,
+            min_points: 5,
+            columns: Vec::new(),
+        }
+    }
+}
+
+/// Fit quality of one candidate k, for the elbow and silhouette charts.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClusterDiagnostic {
+    pub k: usize,
+    /// Within-cluster sum of squares on the standardized columns.
+    pub inertia: f64,
+    pub silhouette: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClusterFit {
+    pub algo: ClusterAlgo,
+    /// Columns that entered the fit (near-constant columns are dropped).
+    pub columns: Vec<String>,
+    /// Cluster per row; `None` marks DBSCAN noise.
+    pub labels: Vec<Option<usize>>,
+    pub n_clusters: usize,
+    pub silhouette: f64,
+    /// One entry per k in `2..=k_max` (k-means and Gaussian mixture only).
+    pub diagnostics: Vec<ClusterDiagnostic>,
+}
+
+impl ClusterFit {
+    /// Labels as the n x 1 column `NmsPca::labels` expects, with noise as -1.
+    pub fn label_array(&self) -> Array2<f64> {
+        Array2::from_shape_fn((self.labels.len(), 1), |(i, _)| {
+            self.labels[i].map_or(-1.0, |c| c as f64)
+        })
+    }
+
+    /// Row indices of each cluster, in cluster order.
+    pub fn members(&self) -> Vec<Vec<usize>> {
+        let mut out = vec![Vec::new(); self.n_clusters];
+        for (i, l) in self.labels.iter().enumerate() {
+            if let Some(c) = l {
+                out[*c].push(i);
+            }
+        }
+        out
+    }
+
+    /// Rows DBSCAN left out of every cluster.
+    pub fn noise(&self) -> Vec<usize> {
+        (0..self.labels.len())
+            .filter(|&i| self.labels[i].is_none())
+            .collect()
+    }
+}
+
 impl MyMmMatrix {
     // Constructor to create a new empty matrix
     pub fn new(rows: usize, cols: usize) -> Self {
@@ -77,6 +231,53 @@ impl MyMmMatrix {
             .and_then(|vec| vec.iter().find(|(_, name)| name == target).map(|(i, _)| *i))
     }
 
+    /// Column names, string columns first, then numeric columns.
+    pub fn column_names(&self) -> Vec<String> {
+        self.colnames_enum_str
+            .iter()
+            .chain(self.colnames_enum_f64.iter())
+            .flat_map(|cols| cols.iter().map(|(_, name)| name.clone()))
+            .collect()
+    }
+
+    pub fn nrows(&self) -> usize {
+        self.data_f64.nrows().max(self.data_str.nrows())
+    }
+
+    /// Cell `(row, column)` as text, looking the column up by name in either matrix.
+    pub fn cell(&self, row: usize, column: &str) -> String {
+        if let Some(j) = self.find_index_str(column) {
+            self.data_str.get((row, j)).cloned().unwrap_or_default()
+        } else if let Some(j) = self.find_index_f64(column) {
+            self.data_f64
+                .get((row, j))
+                .map(|v| v.to_string())
+                .unwrap_or_default()
+        } else {
+            String::new()
+        }
+    }
+
+    /// Render as CSV (header row plus one line per row, fields quoted when needed).
+    pub fn to_csv(&self) -> String {
+        let quote = |s: &str| {
+            if s.contains([',', '"', '\n']) {
+                format!("\"{}\"", s.replace('"', "\"\""))
+            } else {
+                s.to_string()
+            }
+        };
+        let cols = self.column_names();
+        let mut out = cols.iter().map(|c| quote(c)).collect::<Vec<_>>().join(",");
+        out.push('\n');
+        for i in 0..self.nrows() {
+            let line: Vec<String> = cols.iter().map(|c| quote(&self.cell(i, c))).collect();
+            out.push_str(&line.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
     pub fn find_col_index_str(&self, col_ind: &str, target: &str) -> Option<usize> {
         todo!()
     }
@@ -96,7 +297,7 @@ impl MyMmMatrix {
                 DataType::String => str_nm.push(val.0.as_str()),
                 DataType::Date => {
                     str_nm.push(val.0.as_str());
-                    df.try_apply("date", |s| s.cast(&DataType::String))?;
+                    df.try_apply(val.0.as_str(), |s| s.cast(&DataType::String))?;
                 }
                 _ => float_nm.push(val.0.as_str()),
             }
@@ -252,6 +453,114 @@ impl MyMmMatrix {
         Array2::from_shape_fn((n, d), |(i, j)| x[(i, j)])
     }
 
+    /// Numeric columns that describe a security rather than identify it.
+    pub fn pca_candidate_columns(&self) -> Vec<String> {
+        self.colnames_enum_f64
+            .iter()
+            .flatten()
+            .map(|(_, name)| name.clone())
+            .filter(|name| {
+                !crate::datasets::ID_COLUMNS.contains(&name.as_str())
+                    && name != "ret_exc_lead1m"
+                    && name != crate::charts::clustering::BUBBLE_SIZE_COL
+            })
+            .collect()
+    }
+
+    /// Centered (and, with `standardize`, unit-variance) copies of the named columns, with
+    /// non-finite cells set to zero. Unknown and near-constant columns are dropped.
+    fn prepared_columns(
+        &self,
+        requested: Vec<String>,
+        standardize: bool,
+    ) -> (Vec<String>, Vec<Vec<f64>>) {
+        let n = self.data_f64.nrows();
+        let mut columns: Vec<String> = Vec::new();
+        let mut centered: Vec<Vec<f64>> = Vec::new();
+        if n < 2 {
+            return (columns, centered);
+        }
+        for name in requested {
+            let Some(j) = self.find_index_f64(name.as_str()) else {
+                continue;
+            };
+            let col: Vec<f64> = (0..n)
+                .map(|i| {
+                    let v = self.data_f64[(i, j)];
+                    if v.is_finite() {
+                        v
+                    } else {
+                        0.0
+                    }
+                })
+                .collect();
+            let mean = col.iter().sum::<f64>() / n as f64;
+            let var = col.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n as f64 - 1.0);
+            // Near-constant columns add zero-variance directions.
+            if !var.is_finite() || var <= 1e-12 {
+                continue;
+            }
+            let scale = if standardize { var.sqrt() } else { 1.0 };
+            centered.push(col.iter().map(|v| (v - mean) / scale).collect());
+            columns.push(name);
+        }
+        (columns, centered)
+    }
+
+    /// PCA on the named columns via the symmetric eigendecomposition of their covariance (or
+    /// correlation, when standardizing) matrix. Deterministic and identical on every target.
+    pub fn pca(&self, cfg: &PcaConfig) -> Result<PcaFit, String> {
+        let requested = if cfg.columns.is_empty() {
+            self.pca_candidate_columns()
+        } else {
+            cfg.columns.clone()
+        };
+        let n = self.data_f64.nrows();
+        if n < 2 {
+            return Err("PCA needs at least two rows".to_string());
+        }
+
+        let (columns, centered) = self.prepared_columns(requested, cfg.standardize);
+        let d = columns.len();
+        if d < 2 {
+            return Err("PCA needs at least two non-constant columns".to_string());
+        }
+
+        let x = DMatrix::from_fn(n, d, |i, j| centered[j][i]);
+        let cov = (x.transpose() * &x) / (n as f64 - 1.0);
+        let eig = nalgebra::linalg::SymmetricEigen::new(cov);
+        if eig.eigenvalues.iter().any(|v| !v.is_finite()) {
+            return Err("PCA failed: non-finite eigenvalues".to_string());
+        }
+        let mut order: Vec<usize> = (0..d).collect();
+        order.sort_by(|&a, &b| eig.eigenvalues[b].total_cmp(&eig.eigenvalues[a]));
+
+        let k = cfg.k.clamp(1, d.min(n - 1));
+        let mut components = Array2::zeros((k, d));
+        for (c, &e) in order.iter().take(k).enumerate() {
+            let v = eig.eigenvectors.column(e);
+            // Fix the arbitrary eigenvector sign: largest loading positive.
+            let pivot = v
+                .iter()
+                .copied()
+                .fold(0.0_f64, |m, x| if x.abs() > m.abs() { x } else { m });
+            let sign = if pivot < 0.0 { -1.0 } else { 1.0 };
+            for f in 0..d {
+                components[(c, f)] = sign * v[f];
+            }
+        }
+        let scores = Array2::from_shape_fn((n, k), |(i, c)| {
+            (0..d).map(|f| x[(i, f)] * components[(c, f)]).sum::<f64>()
+        });
+        let eigenvalues = order.iter().map(|&e| eig.eigenvalues[e].max(0.0)).collect();
+        Ok(PcaFit {
+            columns,
+            scores,
+            components,
+            eigenvalues,
+        })
+    }
+
     /// Convert ndarray Array2 -> nalgebra DMatrix
     fn array2_to_dmatrix(a: &Array2<f64>) -> DMatrix<f64> {
         let (n, d) = a.dim();
@@ -442,72 +751,178 @@ impl MyMmMatrix {
         Ok((scores, components))
     }
 
-    pub fn kmeans_clusters(x: DMatrix<f64>) -> Result<Array2<f64>, String> {
-        let n_samples = x.nrows();
-        let n_features = x.ncols();
-        if n_samples == 0 {
-            return Ok(Array2::zeros((0, 1)));
-        }
-        if n_features == 0 {
-            return Ok(Array2::zeros((n_samples, 1)));
+    /// Cluster rows on the standardized named columns. For k-means and Gaussian mixtures every
+    /// k in `2..=k_max` is fitted for the diagnostics, and the configured k (or the best
+    /// silhouette) is kept.
+    pub fn cluster(&self, cfg: &ClusterConfig) -> Result<ClusterFit, String> {
+        let requested = if cfg.columns.is_empty() {
+            self.pca_candidate_columns()
+        } else {
+            cfg.columns.clone()
+        };
+        let n = self.data_f64.nrows();
+        if n < 3 {
+            return Err("clustering needs at least three rows".to_string());
         }
-        tracing::debug!("Replacing NaNs with 0's");
-        let mut records = Self::dmatrix_to_array2(&x);
-        records.mapv_inplace(|v| if v.is_finite() { v } else { 0.0 });
-        // Standardize each feature so L2 distance doesn't get dominated by scale.
-        let mut means = vec![0.0_f64; n_features];
-        for j in 0..n_features {
-            means[j] = records.column(j).sum() / (n_samples as f64);
+        let (columns, standardized) = self.prepared_columns(requested, true);
+        if columns.is_empty() {
+            return Err("clustering needs at least one non-constant column".to_string());
         }
-
-        let mut stds = vec![1.0_f64; n_features];
-        for j in 0..n_features {
-            let mut ss = 0.0_f64;
-            for i in 0..n_samples {
-                let diff = records[(i, j)] - means[j];
-                ss += diff * diff;
+        let records = Array2::from_shape_fn((n, columns.len()), |(i, j)| standardized[j][i]);
+
+        let mut diagnostics = Vec::new();
+        let labels = match cfg.algo {
+            ClusterAlgo::Dbscan => Dbscan::params(cfg.min_points.max(1))
+                .tolerance(cfg.eps)
+                .transform(&records)
+                .map_err(|e| format!("DBSCAN failed: {e}"))?
+                .to_vec(),
+            ClusterAlgo::KMeans | ClusterAlgo::Gmm => {
+                let k_max = cfg.k_max.clamp(2, n - 1);
+                let mut best: Option<(f64, Vec<Option<usize>>)> = None;
+                let mut chosen = None;
+                for k in 2..=k_max {
+                    let labels = match fit_k(&records, cfg.algo, k) {
+                        Ok(labels) => labels,
+                        Err(e) => {
+                            tracing::debug!("k = {k} skipped: {e}");
+                            continue;
+                        }
+                    };
+                    let silhouette = silhouette(&records, &labels);
+                    diagnostics.push(ClusterDiagnostic {
+                        k,
+                        inertia: within_ss(&records, &labels),
+                        silhouette,
+                    });
+                    if cfg.k == Some(k) {
+                        chosen = Some(labels.clone());
+                    }
+                    if best.as_ref().is_none_or(|(s, _)| silhouette > *s) {
+                        best = Some((silhouette, labels));
+                    }
+                }
+                match (cfg.k, chosen) {
+                    (_, Some(labels)) => labels,
+                    (Some(k), None) => fit_k(&records, cfg.algo, k.clamp(1, n))?,
+                    (None, None) => best
+                        .map(|(_, labels)| labels)
+                        .ok_or_else(|| format!("{} failed for every k", cfg.algo.label()))?,
+                }
             }
-            let var = ss / (n_samples as f64);
-            let std = var.sqrt();
-            stds[j] = if std.is_finite() && std > 1e-12 {
-                std
-            } else {
-                1.0
-            };
-        }
-        tracing::debug!("Standardize Columns");
-        for i in 0..n_samples {
-            for j in 0..n_features {
-                records[(i, j)] = (records[(i, j)] - means[j]) / stds[j];
+        };
+        let n_clusters = labels.iter().flatten().max().map_or(0, |c| c + 1);
+        Ok(ClusterFit {
+            algo: cfg.algo,
+            columns,
+            silhouette: silhouette(&records, &labels),
+            labels,
+            n_clusters,
+            diagnostics,
+        })
+    }
+}
+
+fn fit_k(records: &Array2<f64>, algo: ClusterAlgo, k: usize) -> Result<Vec<Option<usize>>, String> {
+    let ds = DatasetBase::new(records.clone(), ());
+    let labels = match algo {
+        ClusterAlgo::Gmm => GaussianMixtureModel::params(k)
+            .n_runs(3)
+            .fit(&ds)
+            .map_err(|e| format!("Gaussian mixture fit failed: {e}"))?
+            .predict(records),
+        _ => KMeans::params(k)
+            .init_method(KMeansInit::KMeansPlusPlus)
+            .tolerance(1e-4)
+            .fit(&ds)
+            .map_err(|e| format!("KMeans fit failed: {e:?}"))?
+            .predict(records),
+    };
+    Ok(labels.iter().map(|&c| Some(c)).collect())
+}
+
+fn sq_dist(records: &Array2<f64>, a: usize, b: usize) -> f64 {
+    records
+        .row(a)
+        .iter()
+        .zip(records.row(b).iter())
+        .map(|(x, y)| (x - y).powi(2))
+        .sum()
+}
+
+/// Sum of squared distances from each clustered row to its cluster mean.
+fn within_ss(records: &Array2<f64>, labels: &[Option<usize>]) -> f64 {
+    let k = labels.iter().flatten().max().map_or(0, |c| c + 1);
+    let d = records.ncols();
+    let mut sums = vec![vec![0.0; d]; k];
+    let mut counts = vec![0usize; k];
+    for (i, l) in labels.iter().enumerate() {
+        if let Some(c) = l {
+            counts[*c] += 1;
+            for j in 0..d {
+                sums[*c][j] += records[(i, j)];
             }
         }
+    }
+    labels
+        .iter()
+        .enumerate()
+        .filter_map(|(i, l)| l.map(|c| (i, c)))
+        .map(|(i, c)| {
+            (0..d)
+                .map(|j| (records[(i, j)] - sums[c][j] / counts[c] as f64).powi(2))
+                .sum::<f64>()
+        })
+        .sum()
+}
 
-        // Choose k heuristically since the signature doesn't accept it.
-        let k = if n_samples <= 1 {
-            1
-        } else {
-            let k_max = n_samples.min(8);
-            ((n_samples as f64).sqrt().round() as usize).clamp(2, k_max)
-        };
-        tracing::debug!("Creating Linfa Database");
-        let ds = DatasetBase::new(records, ());
-        tracing::debug!("Instantiate Kmeans");
-        // When targeting wasm (Dioxus web), `getrandom` is already wired up (via `getrandom/js`),
-        // so we can use it to pick randomized initial centroids without pulling in `rand` here.
-        let init = KMeansInit::KMeansPlusPlus;
-        tracing::debug!("Estimate groups");
-        let model = KMeans::params(k)
-            .init_method(init)
-            .tolerance(1e-2)
-            .fit(&ds)
-            .map_err(|e| format!("KMeans fit failed: {e:?}"))?;
-        let clustered = model.predict(ds);
-        let memberships = clustered.targets();
-        tracing::debug!("Output Labels");
-        Ok(Array2::from_shape_fn((n_samples, 1), |(i, _)| {
-            memberships[i] as f64
-        }))
+/// Rows scored by `silhouette`; larger inputs are sampled at an even stride to keep the cost
+/// near-linear in the number of rows.
+const SILHOUETTE_SAMPLE: usize = 1000;
+
+/// Mean silhouette over clustered rows (noise excluded); NaN with fewer than two clusters.
+fn silhouette(records: &Array2<f64>, labels: &[Option<usize>]) -> f64 {
+    let k = labels.iter().flatten().max().map_or(0, |c| c + 1);
+    let clustered: Vec<(usize, usize)> = labels
+        .iter()
+        .enumerate()
+        .filter_map(|(i, l)| l.map(|c| (i, c)))
+        .collect();
+    let mut sizes = vec![0usize; k];
+    for (_, c) in &clustered {
+        sizes[*c] += 1;
+    }
+    if sizes.iter().filter(|s| **s > 0).count() < 2 {
+        return f64::NAN;
     }
+    let stride = clustered.len().div_ceil(SILHOUETTE_SAMPLE).max(1);
+    let scores: Vec<f64> = clustered
+        .iter()
+        .step_by(stride)
+        .map(|&(i, own)| {
+            if sizes[own] < 2 {
+                return 0.0;
+            }
+            let mut dist = vec![0.0; k];
+            for &(j, c) in &clustered {
+                if j != i {
+                    dist[c] += sq_dist(records, i, j).sqrt();
+                }
+            }
+            let a = dist[own] / (sizes[own] - 1) as f64;
+            let b = (0..k)
+                .filter(|&c| c != own && sizes[c] > 0)
+                .map(|c| dist[c] / sizes[c] as f64)
+                .fold(f64::INFINITY, f64::min);
+            let m = a.max(b);
+            if m > 0.0 {
+                (b - a) / m
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    scores.iter().sum::<f64>() / scores.len() as f64
 }
 
 mod matrix_as_vecvec {
@@ -0,0 +1,394 @@
+use crate::ops::{calendar::Exchange, MyMatrix};
+use chrono::{TimeZone, Utc};
+use nalgebra::DMatrix;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IssueKind {
+    Gap,
+    Duplicate,
+    NonPositivePrice,
+    Outlier,
+    Stale,
+    LatencyAnomaly,
+}
+
+impl IssueKind {
+    pub const ALL: [IssueKind; 6] = [
+        IssueKind::Gap,
+        IssueKind::Duplicate,
+        IssueKind::NonPositivePrice,
+        IssueKind::Outlier,
+        IssueKind::Stale,
+        IssueKind::LatencyAnomaly,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IssueKind::Gap => "Missing bins",
+            IssueKind::Duplicate => "Duplicate timestamp",
+            IssueKind::NonPositivePrice => "Zero/negative price",
+            IssueKind::Outlier => "Outlier print",
+            IssueKind::Stale => "Stale period",
+            IssueKind::LatencyAnomaly => "ts_in_delta anomaly",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityIssue {
+    pub kind: IssueKind,
+    pub row: usize,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QualityParams {
+    /// Flag prints whose return is more than this many scaled MADs from the median.
+    pub mad_threshold: f64,
+    /// Minimum run of unchanged prices reported as a stale period.
+    pub stale_run: usize,
+}
+
+impl Default for QualityParams {
+    fn default() -> Self {
+        QualityParams {
+            mad_threshold: 5.0,
+            stale_run: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct QualityReport {
+    pub source: String,
+    pub rows: usize,
+    pub issues: Vec<QualityIssue>,
+}
+
+impl QualityReport {
+    pub fn count(&self, kind: IssueKind) -> usize {
+        self.issues.iter().filter(|i| i.kind == kind).count()
+    }
+
+    /// Rows that should be dropped before analysis. Gaps point at the row after the hole,
+    /// which is itself fine, so they are not included.
+    pub fn flagged_rows(&self) -> Vec<usize> {
+        let mut rows: Vec<usize> = self
+            .issues
+            .iter()
+            .filter(|i| i.kind != IssueKind::Gap)
+            .map(|i| i.row)
+            .collect();
+        rows.sort_unstable();
+        rows.dedup();
+        rows
+    }
+}
+
+fn median(v: &[f64]) -> f64 {
+    if v.is_empty() {
+        return 0.0;
+    }
+    let mut s = v.to_vec();
+    s.sort_by(|a, b| a.total_cmp(b));
+    let n = s.len();
+    if n.is_multiple_of(2) {
+        0.5 * (s[n / 2 - 1] + s[n / 2])
+    } else {
+        s[n / 2]
+    }
+}
+
+/// Median and MAD scaled to be consistent with the standard deviation under normality.
+fn median_mad(v: &[f64]) -> (f64, f64) {
+    let med = median(v);
+    let dev: Vec<f64> = v.iter().map(|x| (x - med).abs()).collect();
+    (med, 1.4826 * median(&dev))
+}
+
+fn column(m: &MyMatrix, name: &str) -> Option<Vec<f64>> {
+    m.find_index(name)
+        .filter(|&j| j < m.data.ncols())
+        .map(|j| m.data.column(j).iter().copied().collect())
+}
+
+fn push_duplicates(ts: &[f64], label: &str, issues: &mut Vec<QualityIssue>) {
+    for i in 1..ts.len() {
+        if ts[i] == ts[i - 1] {
+            issues.push(QualityIssue {
+                kind: IssueKind::Duplicate,
+                row: i,
+                detail: format!("{label} repeats row {}", i - 1),
+            });
+        }
+    }
+}
+
+fn push_non_positive(px: &[f64], label: &str, issues: &mut Vec<QualityIssue>) {
+    for (i, p) in px.iter().enumerate() {
+        if *p <= 0.0 {
+            issues.push(QualityIssue {
+                kind: IssueKind::NonPositivePrice,
+                row: i,
+                detail: format!("{label} = {p}"),
+            });
+        }
+    }
+}
+
+/// Flag rows whose value sits more than `threshold` scaled MADs away from the median.
+fn push_mad_outliers(
+    values: &[(usize, f64)],
+    threshold: f64,
+    kind: IssueKind,
+    label: &str,
+    issues: &mut Vec<QualityIssue>,
+) {
+    let xs: Vec<f64> = values.iter().map(|(_, x)| *x).collect();
+    let (med, mad) = median_mad(&xs);
+    if mad <= 0.0 {
+        return;
+    }
+    for (row, x) in values {
+        let z = (x - med) / mad;
+        if z.abs() > threshold {
+            issues.push(QualityIssue {
+                kind,
+                row: *row,
+                detail: format!("{label} {x:.6} ({z:.1} MADs)"),
+            });
+        }
+    }
+}
+
+fn push_stale(px: &[f64], min_run: usize, issues: &mut Vec<QualityIssue>) {
+    let mut start = 0;
+    for i in 1..=px.len() {
+        if i == px.len() || px[i] != px[start] {
+            if i - start >= min_run {
+                for row in start..i {
+                    issues.push(QualityIssue {
+                        kind: IssueKind::Stale,
+                        row,
+                        detail: format!("price unchanged for {} rows from row {start}", i - start),
+                    });
+                }
+            }
+            start = i;
+        }
+    }
+}
+
+/// Checks for the trades matrix from `query_surr_trademsg_db` (price, size, ts_in_delta, ts_recv).
+pub fn check_trades(m: &MyMatrix, params: QualityParams) -> QualityReport {
+    let mut issues = Vec::new();
+    let price = column(m, "price").unwrap_or_default();
+    if let Some(ts) = column(m, "ts_recv") {
+        push_duplicates(&ts, "ts_recv", &mut issues);
+    }
+    push_non_positive(&price, "price", &mut issues);
+
+    let log_rets: Vec<(usize, f64)> = (1..price.len())
+        .filter(|&i| price[i] > 0.0 && price[i - 1] > 0.0)
+        .map(|i| (i, (price[i] / price[i - 1]).ln()))
+        .collect();
+    push_mad_outliers(
+        &log_rets,
+        params.mad_threshold,
+        IssueKind::Outlier,
+        "log return",
+        &mut issues,
+    );
+    push_stale(&price, params.stale_run, &mut issues);
+
+    if let Some(delta) = column(m, "ts_in_delta") {
+        for (i, d) in delta.iter().enumerate() {
+            if *d < 0.0 {
+                issues.push(QualityIssue {
+                    kind: IssueKind::LatencyAnomaly,
+                    row: i,
+                    detail: format!("negative ts_in_delta {d}"),
+                });
+            }
+        }
+        let positive: Vec<(usize, f64)> = delta
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| **d >= 0.0)
+            .map(|(i, d)| (i, *d))
+            .collect();
+        push_mad_outliers(
+            &positive,
+            params.mad_threshold,
+            IssueKind::LatencyAnomaly,
+            "ts_in_delta",
+            &mut issues,
+        );
+    }
+
+    issues.sort_by_key(|i| i.row);
+    QualityReport {
+        source: String::from("trades"),
+        rows: m.data.nrows(),
+        issues,
+    }
+}
+
+/// Checks for the bins matrix from `query_surr_trade_bin_db`. Gaps are measured against the
+/// `exchange` session grid of `bin_secs` bins, so nights, weekends and holidays are not flagged.
+pub fn check_bins(
+    m: &MyMatrix,
+    bin_secs: i64,
+    exchange: Exchange,
+    params: QualityParams,
+) -> QualityReport {
+    let mut issues = Vec::new();
+    if let Some(bins) = column(m, "bin") {
+        push_duplicates(&bins, "bin", &mut issues);
+        let lo = bins.iter().copied().fold(f64::INFINITY, f64::min);
+        let hi = bins.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let grid = if lo.is_finite() && hi.is_finite() {
+            exchange.bin_grid(
+                Utc.timestamp_nanos(lo as i64),
+                Utc.timestamp_nanos(hi as i64),
+                bin_secs,
+            )
+        } else {
+            Vec::new()
+        };
+        for i in 1..bins.len() {
+            let (a, b) = (bins[i - 1] as i64, bins[i] as i64);
+            let missing = grid
+                .partition_point(|&t| t < b)
+                .saturating_sub(grid.partition_point(|&t| t <= a));
+            if missing > 0 {
+                issues.push(QualityIssue {
+                    kind: IssueKind::Gap,
+                    row: i,
+                    detail: format!("{missing} bin(s) missing before this row"),
+                });
+            }
+        }
+    }
+    for name in ["mean_price", "p0", "p1", "pmax", "pmin"] {
+        if let Some(px) = column(m, name) {
+            push_non_positive(&px, name, &mut issues);
+        }
+    }
+    if let Some(ret) = column(m, "ret") {
+        let rets: Vec<(usize, f64)> = ret.iter().copied().enumerate().collect();
+        push_mad_outliers(
+            &rets,
+            params.mad_threshold,
+            IssueKind::Outlier,
+            "ret",
+            &mut issues,
+        );
+    }
+    if let Some(close) = column(m, "p1") {
+        push_stale(&close, params.stale_run, &mut issues);
+    }
+
+    issues.sort_by_key(|i| i.row);
+    QualityReport {
+        source: String::from("bins"),
+        rows: m.data.nrows(),
+        issues,
+    }
+}
+
+impl MyMatrix {
+    /// Copy of the matrix without the given rows (e.g. `QualityReport::flagged_rows`).
+    pub fn drop_rows(&self, rows: &[usize]) -> MyMatrix {
+        let keep: Vec<usize> = (0..self.data.nrows())
+            .filter(|i| rows.binary_search(i).is_err())
+            .collect();
+        let data = DMatrix::from_fn(keep.len(), self.data.ncols(), |i, j| {
+            self.data[(keep[i], j)]
+        });
+        MyMatrix {
+            data,
+            colnames_enum: self.colnames_enum.clone(),
+            descrips: self.descrips,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn matrix(cols: &[(&str, Vec<f64>)]) -> MyMatrix {
+        let rows = cols[0].1.len();
+        MyMatrix {
+            data: DMatrix::from_fn(rows, cols.len(), |i, j| cols[j].1[i]),
+            colnames_enum: Some(
+                cols.iter()
+                    .enumerate()
+                    .map(|(j, (name, _))| (j, name.to_string()))
+                    .collect(),
+            ),
+            descrips: (0.0, 0.0, 0.0, 0.0, 0.0, 0.0),
+        }
+    }
+
+    fn ns(s: &str) -> f64 {
+        DateTime::parse_from_rfc3339(s)
+            .unwrap()
+            .timestamp_nanos_opt()
+            .unwrap() as f64
+    }
+
+    fn bins(times: &[f64]) -> MyMatrix {
+        let p1 = (0..times.len()).map(|i| 100.0 + (i % 3) as f64).collect();
+        matrix(&[("bin", times.to_vec()), ("p1", p1)])
+    }
+
+    #[test]
+    fn hourly_bins_across_sessions_have_no_gaps() {
+        // Two NYSE winter sessions of epoch-aligned hourly bins, 14:00 to 20:00 UTC.
+        let mut times = Vec::new();
+        for day in ["2024-01-10", "2024-01-11"] {
+            for h in 14..=20 {
+                times.push(ns(&format!("{day}T{h:02}:00:00Z")));
+            }
+        }
+        let report = check_bins(&bins(&times), 3_600, Exchange::Nyse, QualityParams::default());
+        assert_eq!(report.count(IssueKind::Gap), 0);
+
+        times.remove(3);
+        let report = check_bins(&bins(&times), 3_600, Exchange::Nyse, QualityParams::default());
+        assert_eq!(report.count(IssueKind::Gap), 1);
+        assert_eq!(report.issues[0].row, 3);
+    }
+
+    #[test]
+    fn daily_bins_over_a_holiday_weekend_have_no_gaps() {
+        let times: Vec<f64> = ["2024-01-11", "2024-01-12", "2024-01-16", "2024-01-17"]
+            .iter()
+            .map(|d| ns(&format!("{d}T00:00:00Z")))
+            .collect();
+        let report = check_bins(&bins(&times), 86_400, Exchange::Nyse, QualityParams::default());
+        assert_eq!(report.count(IssueKind::Gap), 0);
+        assert!(report.flagged_rows().is_empty());
+    }
+
+    #[test]
+    fn trade_checks_flag_bad_prints() {
+        let mut price: Vec<f64> = (0..60).map(|i| 100.0 + 0.01 * (i % 7) as f64).collect();
+        price[10] = 0.0;
+        price[30] = 150.0;
+        let ts: Vec<f64> = (0..60).map(|i| (i / 2 * 2) as f64 * 1e9).collect();
+        let m = matrix(&[("price", price), ("ts_recv", ts)]);
+        let report = check_trades(&m, QualityParams::default());
+        assert_eq!(report.count(IssueKind::NonPositivePrice), 1);
+        assert_eq!(report.count(IssueKind::Duplicate), 30);
+        assert!(report
+            .issues
+            .iter()
+            .any(|i| i.kind == IssueKind::Outlier && i.row == 30));
+        assert!(report.flagged_rows().contains(&10));
+    }
+}
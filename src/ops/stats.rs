@@ -0,0 +1,253 @@
+use crate::ops::{calendar::Exchange, MyMatrix};
+use chrono::{TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Lags reported by `DescriptiveStats::autocorr`.
+pub const ACF_LAGS: [usize; 4] = [1, 2, 5, 10];
+
+/// Which single-asset matrix the statistics describe.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StatsSource {
+    #[default]
+    Trades,
+    Bins,
+}
+
+impl StatsSource {
+    pub const ALL: [StatsSource; 2] = [StatsSource::Trades, StatsSource::Bins];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatsSource::Trades => "trades",
+            StatsSource::Bins => "bins",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatsSource::Trades => "Trade prints",
+            StatsSource::Bins => "Price bins",
+        }
+    }
+
+    pub fn from_label(s: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|x| x.as_str() == s)
+            .unwrap_or_default()
+    }
+}
+
+/// Return and activity statistics for one price series. Returns are log returns between
+/// consecutive rows; NaN marks a statistic the sample is too short for.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct DescriptiveStats {
+    pub source: StatsSource,
+    /// Number of returns.
+    pub n: usize,
+    /// Returns per trading year used to annualize.
+    pub periods_per_year: f64,
+    pub mean: f64,
+    pub sd: f64,
+    pub ann_return: f64,
+    pub ann_vol: f64,
+    pub skewness: f64,
+    pub excess_kurtosis: f64,
+    pub jarque_bera: f64,
+    /// Chi-square (2 df) p-value of `jarque_bera`.
+    pub jb_pvalue: f64,
+    /// `(lag, autocorrelation)` for each of `ACF_LAGS`.
+    pub autocorr: Vec<(usize, f64)>,
+    /// Rescaled-range Hurst exponent (0.5 for a random walk).
+    pub hurst: f64,
+    /// `None` when the matrix carries no size/volume column.
+    pub total_volume: Option<f64>,
+    pub trade_count: Option<f64>,
+    pub avg_trade_size: Option<f64>,
+}
+
+fn column(m: &MyMatrix, names: &[&str]) -> Option<Vec<f64>> {
+    names.iter().find_map(|name| {
+        m.find_index(name)
+            .filter(|&j| j < m.data.ncols())
+            .map(|j| m.data.column(j).iter().copied().collect())
+    })
+}
+
+/// Log returns between consecutive positive prices.
+pub fn log_returns(prices: &[f64]) -> Vec<f64> {
+    prices
+        .windows(2)
+        .filter(|w| w[0] > 0.0 && w[1] > 0.0)
+        .map(|w| (w[1] / w[0]).ln())
+        .collect()
+}
+
+/// Log returns of the bin close (`p1`, else `mean_price`) as `(row, return)`, where `row` is
+/// the bin the return ends in.
+pub fn bin_returns(m: &MyMatrix) -> Vec<(usize, f64)> {
+    let px = column(m, &["p1", "mean_price"]).unwrap_or_default();
+    (1..px.len())
+        .filter(|&i| px[i] > 0.0 && px[i - 1] > 0.0)
+        .map(|i| (i, (px[i] / px[i - 1]).ln()))
+        .collect()
+}
+
+/// Sample autocorrelation of `x` at `lag`.
+pub fn autocorrelation(x: &[f64], lag: usize) -> f64 {
+    let n = x.len();
+    if lag == 0 || n <= lag + 1 {
+        return f64::NAN;
+    }
+    let mean = x.iter().sum::<f64>() / n as f64;
+    let var: f64 = x.iter().map(|v| (v - mean).powi(2)).sum();
+    if var <= 0.0 {
+        return f64::NAN;
+    }
+    let cov: f64 = (lag..n).map(|t| (x[t] - mean) * (x[t - lag] - mean)).sum();
+    cov / var
+}
+
+/// Hurst exponent from the slope of log(R/S) on log(window) over dyadic windows of 8 or more
+/// observations, each averaged over the non-overlapping blocks that fit.
+pub fn hurst_exponent(x: &[f64]) -> f64 {
+    let mut points: Vec<(f64, f64)> = Vec::new();
+    let mut size = 8;
+    while size <= x.len() / 2 {
+        let rs: Vec<f64> = x
+            .chunks_exact(size)
+            .filter_map(|block| {
+                let mean = block.iter().sum::<f64>() / size as f64;
+                let (mut cum, mut lo, mut hi) = (0.0_f64, 0.0_f64, 0.0_f64);
+                for v in block {
+                    cum += v - mean;
+                    lo = lo.min(cum);
+                    hi = hi.max(cum);
+                }
+                let sd =
+                    (block.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / size as f64).sqrt();
+                (sd > 0.0).then(|| (hi - lo) / sd)
+            })
+            .collect();
+        if !rs.is_empty() {
+            let avg = rs.iter().sum::<f64>() / rs.len() as f64;
+            points.push(((size as f64).ln(), avg.ln()));
+        }
+        size *= 2;
+    }
+    if points.len() < 2 {
+        return f64::NAN;
+    }
+    let k = points.len() as f64;
+    let mx = points.iter().map(|p| p.0).sum::<f64>() / k;
+    let my = points.iter().map(|p| p.1).sum::<f64>() / k;
+    let sxy: f64 = points.iter().map(|p| (p.0 - mx) * (p.1 - my)).sum();
+    let sxx: f64 = points.iter().map(|p| (p.0 - mx).powi(2)).sum();
+    sxy / sxx
+}
+
+impl DescriptiveStats {
+    /// Statistics of `returns`, annualized with `periods_per_year`.
+    pub fn from_returns(source: StatsSource, returns: &[f64], periods_per_year: f64) -> Self {
+        let n = returns.len();
+        let nan = f64::NAN;
+        let mut out = DescriptiveStats {
+            source,
+            n,
+            periods_per_year,
+            mean: nan,
+            sd: nan,
+            ann_return: nan,
+            ann_vol: nan,
+            skewness: nan,
+            excess_kurtosis: nan,
+            jarque_bera: nan,
+            jb_pvalue: nan,
+            autocorr: ACF_LAGS
+                .iter()
+                .map(|&l| (l, autocorrelation(returns, l)))
+                .collect(),
+            hurst: hurst_exponent(returns),
+            total_volume: None,
+            trade_count: None,
+            avg_trade_size: None,
+        };
+        if n < 2 {
+            return out;
+        }
+        let nf = n as f64;
+        let mean = returns.iter().sum::<f64>() / nf;
+        let moment = |p: i32| returns.iter().map(|r| (r - mean).powi(p)).sum::<f64>() / nf;
+        let (m2, m3, m4) = (moment(2), moment(3), moment(4));
+        let sd = (m2 * nf / (nf - 1.0)).sqrt();
+        out.mean = mean;
+        out.sd = sd;
+        out.ann_return = mean * periods_per_year;
+        out.ann_vol = sd * periods_per_year.sqrt();
+        if m2 > 0.0 {
+            let skew = m3 / m2.powf(1.5);
+            let kurt = m4 / (m2 * m2) - 3.0;
+            let jb = nf / 6.0 * (skew * skew + kurt * kurt / 4.0);
+            out.skewness = skew;
+            out.excess_kurtosis = kurt;
+            out.jarque_bera = jb;
+            out.jb_pvalue = (-jb / 2.0).exp();
+        }
+        out
+    }
+}
+
+/// Statistics of the trades matrix (price, size, ts_in_delta, ts_recv). Trades arrive at
+/// irregular times, so returns are annualized by the number of trading seconds on the
+/// `exchange` calendar between the first and last print.
+pub fn trade_stats(m: &MyMatrix, exchange: Exchange) -> DescriptiveStats {
+    let returns = log_returns(&column(m, &["price"]).unwrap_or_default());
+    let periods_per_year = column(m, &["ts_recv"])
+        .and_then(|ts| {
+            let lo = ts.iter().copied().fold(f64::INFINITY, f64::min);
+            let hi = ts.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            if !(lo.is_finite() && hi > lo) {
+                return None;
+            }
+            let minutes = exchange
+                .bin_grid(
+                    Utc.timestamp_nanos(lo as i64),
+                    Utc.timestamp_nanos(hi as i64),
+                    60,
+                )
+                .len()
+                .max(1);
+            Some(returns.len() as f64 * exchange.bins_per_year(60) / minutes as f64)
+        })
+        .unwrap_or(f64::NAN);
+    let mut out = DescriptiveStats::from_returns(StatsSource::Trades, &returns, periods_per_year);
+    if let Some(size) = column(m, &["size"]) {
+        let total: f64 = size.iter().sum();
+        let count = size.len() as f64;
+        out.total_volume = Some(total);
+        out.trade_count = Some(count);
+        out.avg_trade_size = (count > 0.0).then(|| total / count);
+    }
+    out
+}
+
+/// Statistics of the bins matrix: log returns of the bin close (`p1`, else `mean_price`),
+/// annualized with the `exchange` session's bins per year. Volume figures are filled when the
+/// bins carry `volume` and `n_trades` columns.
+pub fn bin_stats(m: &MyMatrix, bin_secs: i64, exchange: Exchange) -> DescriptiveStats {
+    let returns: Vec<f64> = bin_returns(m).into_iter().map(|(_, r)| r).collect();
+    let mut out = DescriptiveStats::from_returns(
+        StatsSource::Bins,
+        &returns,
+        exchange.bins_per_year(bin_secs),
+    );
+    let volume = column(m, &["volume", "size"]).map(|v| v.iter().sum::<f64>());
+    let count = column(m, &["n_trades", "count"]).map(|v| v.iter().sum::<f64>());
+    out.total_volume = volume;
+    out.trade_count = count;
+    out.avg_trade_size = volume
+        .zip(count)
+        .filter(|(_, c)| *c > 0.0)
+        .map(|(v, c)| v / c);
+    out
+}
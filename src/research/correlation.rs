@@ -0,0 +1,287 @@
+// Redundancy between factors: correlation matrices (pooled or averaged over monthly cross
+// sections) ordered by average-linkage hierarchical clustering.
+use super::{pearson, rows_by_date, FactorQuery};
+use crate::charts::heatmap::CorrelationHeatmap;
+use chrono::NaiveDate;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Cross-sections smaller than this are left out of the time average.
+pub const MIN_CROSS_SECTION: usize = 10;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CorrMode {
+    /// One correlation over every firm-month row.
+    #[default]
+    Pooled,
+    /// Correlation per `date`, averaged over dates; removes common time-series movement.
+    CrossSectional,
+}
+
+impl CorrMode {
+    pub const ALL: [CorrMode; 2] = [CorrMode::Pooled, CorrMode::CrossSectional];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CorrMode::Pooled => "pooled",
+            CorrMode::CrossSectional => "cross_sectional",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CorrMode::Pooled => "Pooled over all rows",
+            CorrMode::CrossSectional => "Average of monthly cross-sections",
+        }
+    }
+
+    pub fn from_label(s: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|m| m.as_str() == s)
+            .unwrap_or_default()
+    }
+}
+
+/// One agglomeration step: clusters `left` and `right` joined at `height`. Leaves are
+/// `0..n`; the cluster formed by merge `k` has id `n + k`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Merge {
+    pub left: usize,
+    pub right: usize,
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Dendrogram {
+    pub merges: Vec<Merge>,
+    /// Leaves in drawing order (children of each merge are adjacent).
+    pub order: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct FactorCorrelation {
+    pub names: Vec<String>,
+    pub corr: Vec<Vec<f64>>,
+}
+
+/// Pearson correlation over the rows where both series are finite.
+fn pairwise_complete(a: &[f64], b: &[f64], rows: &[usize]) -> f64 {
+    let (x, y): (Vec<f64>, Vec<f64>) = rows
+        .iter()
+        .filter(|&&i| a[i].is_finite() && b[i].is_finite())
+        .map(|&i| (a[i], b[i]))
+        .unzip();
+    if x.len() < 3 {
+        return f64::NAN;
+    }
+    pearson(&x, &y)
+}
+
+fn corr_over(x: &[Vec<f64>], rows: &[usize]) -> Vec<Vec<f64>> {
+    let k = x.len();
+    let mut out = vec![vec![f64::NAN; k]; k];
+    for a in 0..k {
+        out[a][a] = 1.0;
+        for b in a + 1..k {
+            let r = pairwise_complete(&x[a], &x[b], rows);
+            out[a][b] = r;
+            out[b][a] = r;
+        }
+    }
+    out
+}
+
+/// Correlation matrix of the factor columns `x` (one vector per factor, NaN where missing).
+/// Each pair uses the rows where both factors are present, so a sparse factor doesn't shrink
+/// every other pair's sample and missing values are never treated as zeros.
+pub fn factor_correlation(
+    dates: &[Option<NaiveDate>],
+    x: &[Vec<f64>],
+    mode: CorrMode,
+) -> Vec<Vec<f64>> {
+    match mode {
+        CorrMode::Pooled => corr_over(x, &(0..dates.len()).collect::<Vec<_>>()),
+        CorrMode::CrossSectional => {
+            let k = x.len();
+            let mut sum = vec![vec![0.0; k]; k];
+            let mut count = vec![vec![0usize; k]; k];
+            for rows in rows_by_date(dates, |_| true)
+                .values()
+                .filter(|r| r.len() >= MIN_CROSS_SECTION)
+            {
+                let c = corr_over(x, rows);
+                for a in 0..k {
+                    for b in 0..k {
+                        if c[a][b].is_finite() {
+                            sum[a][b] += c[a][b];
+                            count[a][b] += 1;
+                        }
+                    }
+                }
+            }
+            (0..k)
+                .map(|a| {
+                    (0..k)
+                        .map(|b| {
+                            if count[a][b] > 0 {
+                                sum[a][b] / count[a][b] as f64
+                            } else {
+                                f64::NAN
+                            }
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+    }
+}
+
+/// Correlations of the query's factors, computed on the server from the research panel (raw
+/// values with nulls kept as NaN) rather than from the display matrix.
+#[server]
+pub async fn run_factor_correlation(
+    query: FactorQuery,
+    mode: CorrMode,
+) -> Result<FactorCorrelation, ServerFnError> {
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (query, mode);
+        return Err(ServerFnError::new(
+            "run_factor_correlation requires the `server` feature",
+        ));
+    }
+
+    #[cfg(feature = "server")]
+    {
+        let panel = super::panel::load(&query, None).await?;
+        if panel.signals.len() < 2 {
+            return Err(ServerFnError::new("select at least two factor columns"));
+        }
+        tokio::task::spawn_blocking(move || FactorCorrelation {
+            corr: factor_correlation(&panel.dates, &panel.x, mode),
+            names: panel.signals,
+        })
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))
+    }
+}
+
+/// Average-linkage (UPGMA) clustering on the distance `1 - corr`. Undefined correlations are
+/// treated as zero (distance 1).
+pub fn average_linkage(corr: &[Vec<f64>]) -> Dendrogram {
+    let n = corr.len();
+    let dist = |a: usize, b: usize| {
+        let r = corr[a][b];
+        1.0 - if r.is_finite() { r } else { 0.0 }
+    };
+    // Active clusters: (id, members).
+    let mut active: Vec<(usize, Vec<usize>)> = (0..n).map(|i| (i, vec![i])).collect();
+    let mut merges: Vec<Merge> = Vec::with_capacity(n.saturating_sub(1));
+    let mut children: Vec<(usize, usize)> = Vec::new();
+    while active.len() > 1 {
+        let mut best = (0, 1, f64::INFINITY);
+        for a in 0..active.len() {
+            for b in a + 1..active.len() {
+                let (ma, mb) = (&active[a].1, &active[b].1);
+                let d = ma
+                    .iter()
+                    .flat_map(|&i| mb.iter().map(move |&j| (i, j)))
+                    .map(|(i, j)| dist(i, j))
+                    .sum::<f64>()
+                    / (ma.len() * mb.len()) as f64;
+                if d < best.2 {
+                    best = (a, b, d);
+                }
+            }
+        }
+        let (a, b, height) = best;
+        let (id_b, members_b) = active.remove(b);
+        let (id_a, members_a) = active.remove(a);
+        merges.push(Merge {
+            left: id_a,
+            right: id_b,
+            height,
+        });
+        children.push((id_a, id_b));
+        active.push((n + merges.len() - 1, [members_a, members_b].concat()));
+    }
+
+    let mut order = Vec::with_capacity(n);
+    if let Some((root, _)) = active.first() {
+        let mut stack = vec![*root];
+        while let Some(node) = stack.pop() {
+            if node < n {
+                order.push(node);
+            } else {
+                let (l, r) = children[node - n];
+                stack.push(r);
+                stack.push(l);
+            }
+        }
+    }
+    Dendrogram { merges, order }
+}
+
+#[component]
+pub fn FactorCorrelationPanel(query: FactorQuery) -> Element {
+    let mut mode = use_signal(CorrMode::default);
+    let mut running = use_signal(|| false);
+    let mut result: Signal<Option<Result<FactorCorrelation, String>>> = use_signal(|| None);
+
+    let run = move |_| {
+        let query = query.clone();
+        let mode = *mode.peek();
+        running.set(true);
+        spawn(async move {
+            result.set(Some(
+                run_factor_correlation(query, mode)
+                    .await
+                    .map_err(|e| e.to_string()),
+            ));
+            running.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "multi-card-div",
+                label { "Correlation" }
+                select {
+                    value: "{mode().as_str()}",
+                    onchange: move |evt| mode.set(CorrMode::from_label(&evt.value())),
+                    for m in CorrMode::ALL {
+                        option { value: "{m.as_str()}", "{m.label()}" }
+                    }
+                }
+                button { class: "ma-btn", disabled: running(), onclick: run, "Run correlations" }
+            }
+        }
+        match result() {
+            Some(Ok(res)) => {
+                let dendrogram = average_linkage(&res.corr);
+                rsx! { CorrelationHeatmap { names: res.names, corr: res.corr, dendrogram } }
+            }
+            Some(Err(e)) => rsx! { div { class: "error", "Correlation failed: {e}" } },
+            None => rsx! {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_values_are_dropped_pairwise_not_zero_filled() {
+        let dates = vec![NaiveDate::from_ymd_opt(2024, 1, 31); 6];
+        let a = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let b = vec![2.0, 4.0, 6.0, 8.0, f64::NAN, f64::NAN];
+        let c = vec![f64::NAN, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let corr = factor_correlation(&dates, &[a, b, c], CorrMode::Pooled);
+        // With zeros in place of the gaps none of these would be a perfect correlation.
+        assert!((corr[0][1] - 1.0).abs() < 1e-12);
+        assert!((corr[0][2] - 1.0).abs() < 1e-12);
+        assert!((corr[1][2] - 1.0).abs() < 1e-12);
+    }
+}
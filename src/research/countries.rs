@@ -0,0 +1,271 @@
+// Cross-country comparison: the same factors fetched for several markets in one query, with
+// per-country distributions and quantile-spread premia.
+use super::quantiles::quantile_sort;
+use super::FactorQuery;
+use crate::charts::boxplot::{BoxStats, GroupedBoxPlot};
+use crate::charts::heatmap::ValueHeatmap;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Upper bound on countries per run; each adds a full panel slice.
+pub const MAX_COUNTRIES: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct CountryFactorStats {
+    /// Pooled distribution over the country's firm-months.
+    pub dist: Option<BoxStats>,
+    /// Months with a quantile portfolio.
+    pub months: usize,
+    /// Mean top-minus-bottom quantile return.
+    pub spread_mean: f64,
+    /// Newey-West t-stat of the mean spread.
+    pub spread_t: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct CountryComparison {
+    pub countries: Vec<String>,
+    pub factors: Vec<String>,
+    /// `stats[f][c]` for `factors[f]` in `countries[c]`.
+    pub stats: Vec<Vec<CountryFactorStats>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CountryMetric {
+    #[default]
+    Median,
+    Mean,
+    SpreadMean,
+    SpreadT,
+}
+
+impl CountryMetric {
+    pub const ALL: [CountryMetric; 4] = [
+        CountryMetric::Median,
+        CountryMetric::Mean,
+        CountryMetric::SpreadMean,
+        CountryMetric::SpreadT,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CountryMetric::Median => "median",
+            CountryMetric::Mean => "mean",
+            CountryMetric::SpreadMean => "spread_mean",
+            CountryMetric::SpreadT => "spread_t",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CountryMetric::Median => "Median factor value",
+            CountryMetric::Mean => "Mean factor value",
+            CountryMetric::SpreadMean => "Mean top - bottom return",
+            CountryMetric::SpreadT => "Top - bottom t-stat (NW)",
+        }
+    }
+
+    pub fn from_label(s: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|m| m.as_str() == s)
+            .unwrap_or_default()
+    }
+
+    pub fn value(&self, s: &CountryFactorStats) -> f64 {
+        match self {
+            CountryMetric::Median => s.dist.map_or(f64::NAN, |d| d.median),
+            CountryMetric::Mean => s.dist.map_or(f64::NAN, |d| d.mean),
+            CountryMetric::SpreadMean => s.spread_mean,
+            CountryMetric::SpreadT => s.spread_t,
+        }
+    }
+}
+
+#[server]
+pub async fn run_country_comparison(
+    query: FactorQuery,
+    countries: Vec<String>,
+    n_quantiles: usize,
+) -> Result<CountryComparison, ServerFnError> {
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (query, countries, n_quantiles);
+        return Err(ServerFnError::new(
+            "run_country_comparison requires the `server` feature",
+        ));
+    }
+
+    #[cfg(feature = "server")]
+    {
+        if countries.is_empty() || countries.len() > MAX_COUNTRIES {
+            return Err(ServerFnError::new(format!(
+                "select between 1 and {MAX_COUNTRIES} countries"
+            )));
+        }
+        if !(2..=20).contains(&n_quantiles) {
+            return Err(ServerFnError::new(
+                "number of quantiles must be between 2 and 20",
+            ));
+        }
+        let panel = super::panel::load_countries(&query, &countries, None).await?;
+        tokio::task::spawn_blocking(move || {
+            let rows: Vec<Vec<usize>> = countries
+                .iter()
+                .map(|c| {
+                    (0..panel.countries.len())
+                        .filter(|&i| panel.countries[i] == *c)
+                        .collect()
+                })
+                .collect();
+            let pick = |v: &[f64], rows: &[usize]| rows.iter().map(|&i| v[i]).collect::<Vec<_>>();
+            let stats = panel
+                .signals
+                .iter()
+                .zip(&panel.x)
+                .map(|(name, signal)| {
+                    rows.iter()
+                        .map(|rows| {
+                            let dates: Vec<_> = rows.iter().map(|&i| panel.dates[i]).collect();
+                            let ids: Vec<super::SecurityId> =
+                                rows.iter().map(|&i| panel.security(i)).collect();
+                            let x = pick(signal, rows);
+                            let spread = quantile_sort(
+                                name,
+                                &dates,
+                                &ids,
+                                &pick(&panel.ret, rows),
+                                &x,
+                                None,
+                                n_quantiles,
+                            );
+                            CountryFactorStats {
+                                dist: BoxStats::from_values(x),
+                                months: spread.as_ref().map_or(0, |q| q.dates.len()),
+                                spread_mean: spread.as_ref().map_or(f64::NAN, |q| q.spread_mean),
+                                spread_t: spread.as_ref().map_or(f64::NAN, |q| q.spread_t),
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            CountryComparison {
+                countries,
+                factors: panel.signals,
+                stats,
+            }
+        })
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))
+    }
+}
+
+/// `available` lists the country codes offered for selection.
+#[component]
+pub fn CountryComparisonPanel(query: FactorQuery, available: Vec<String>) -> Element {
+    let mut chosen: Signal<Vec<String>> = use_signal(Vec::new);
+    let mut n_quantiles = use_signal(|| 5_usize);
+    let mut metric = use_signal(CountryMetric::default);
+    let mut running = use_signal(|| false);
+    let mut result: Signal<Option<Result<CountryComparison, String>>> = use_signal(|| None);
+
+    let run = move |_| {
+        let query = query.clone();
+        let countries = chosen();
+        let n_q = n_quantiles();
+        running.set(true);
+        spawn(async move {
+            result.set(Some(
+                run_country_comparison(query, countries, n_q)
+                    .await
+                    .map_err(|e| e.to_string()),
+            ));
+            running.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "multi-card-div",
+                label { "Countries" }
+                for code in available.iter().cloned() {
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: chosen().contains(&code),
+                            onchange: {
+                                let code = code.clone();
+                                move |evt: FormEvent| {
+                                    chosen.with_mut(|c| {
+                                        c.retain(|x| *x != code);
+                                        if evt.checked() {
+                                            c.push(code.clone());
+                                        }
+                                    });
+                                }
+                            },
+                        }
+                        " {code}"
+                    }
+                }
+            }
+            div { class: "multi-card-div",
+                label { "Quantiles" }
+                input {
+                    r#type: "number",
+                    min: 2,
+                    max: 20,
+                    value: "{n_quantiles}",
+                    oninput: move |evt| {
+                        if let Ok(v) = evt.value().parse::<usize>() {
+                            n_quantiles.set(v);
+                        }
+                    },
+                }
+                label { "Heatmap" }
+                select {
+                    value: "{metric().as_str()}",
+                    onchange: move |evt| metric.set(CountryMetric::from_label(&evt.value())),
+                    for m in CountryMetric::ALL {
+                        option { value: "{m.as_str()}", "{m.label()}" }
+                    }
+                }
+                button {
+                    class: "ma-btn",
+                    disabled: running() || chosen().is_empty() || chosen().len() > MAX_COUNTRIES,
+                    onclick: run,
+                    "Compare countries"
+                }
+            }
+        }
+        match result() {
+            Some(Ok(cmp)) => {
+                let boxes: Vec<Vec<Option<BoxStats>>> = cmp
+                    .stats
+                    .iter()
+                    .map(|row| row.iter().map(|s| s.dist).collect())
+                    .collect();
+                let m = metric();
+                let heat: Vec<Vec<f64>> = (0..cmp.countries.len())
+                    .map(|c| cmp.stats.iter().map(|row| m.value(&row[c])).collect())
+                    .collect();
+                rsx! {
+                    GroupedBoxPlot {
+                        title: "Factor distributions by country (whiskers: 5th-95th percentile)".to_string(),
+                        groups: cmp.factors.clone(),
+                        members: cmp.countries.clone(),
+                        boxes,
+                    }
+                    h3 { "{m.label()} by country" }
+                    ValueHeatmap {
+                        row_labels: cmp.countries.clone(),
+                        col_labels: cmp.factors.clone(),
+                        values: heat,
+                    }
+                }
+            }
+            Some(Err(e)) => rsx! { div { class: "error", "Country comparison failed: {e}" } },
+            None => rsx! {},
+        }
+    }
+}
@@ -0,0 +1,298 @@
+// Fama-MacBeth (1973): one OLS of next-month returns on the factors per date, then inference
+// on the time series of slopes.
+use super::{default_nw_lags, newey_west_se, rows_by_date, FactorQuery};
+use crate::charts::series::SeriesChart;
+use chrono::NaiveDate;
+use dioxus::prelude::*;
+use nalgebra::{DMatrix, DVector};
+use serde::{Deserialize, Serialize};
+
+pub const INTERCEPT: &str = "intercept";
+/// Fewer monthly slopes than this give no t-statistic; the Newey-West variance is too noisy.
+pub const MIN_T_STAT_MONTHS: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PremiumStat {
+    pub name: String,
+    pub mean: f64,
+    pub nw_se: f64,
+    /// NaN when `note` says why it could not be computed.
+    pub t_stat: f64,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct FamaMacBethResult {
+    /// `INTERCEPT` followed by the factor names.
+    pub regressors: Vec<String>,
+    pub dates: Vec<NaiveDate>,
+    /// Slopes per date, in `regressors` order.
+    pub premia: Vec<Vec<f64>>,
+    pub r2: Vec<f64>,
+    pub n_obs: Vec<usize>,
+    pub stats: Vec<PremiumStat>,
+    pub avg_r2: f64,
+    pub nw_lags: usize,
+}
+
+impl FamaMacBethResult {
+    /// Running sum of each factor premium (intercept excluded).
+    pub fn cumulative_premia(&self) -> Vec<(String, Vec<f64>)> {
+        (1..self.regressors.len())
+            .map(|j| {
+                let mut acc = 0.0;
+                let path = self
+                    .premia
+                    .iter()
+                    .map(|p| {
+                        acc += p[j];
+                        acc
+                    })
+                    .collect();
+                (self.regressors[j].clone(), path)
+            })
+            .collect()
+    }
+
+    pub fn avg_obs(&self) -> f64 {
+        if self.n_obs.is_empty() {
+            return 0.0;
+        }
+        self.n_obs.iter().sum::<usize>() as f64 / self.n_obs.len() as f64
+    }
+}
+
+/// OLS of `y` on `x` (which already holds the intercept column). Returns the slopes and R².
+pub fn ols(y: &DVector<f64>, x: &DMatrix<f64>) -> Option<(DVector<f64>, f64)> {
+    let beta = x.clone().svd(true, true).solve(y, 1e-12).ok()?;
+    let resid = y - x * &beta;
+    let mean = y.mean();
+    let sst: f64 = y.iter().map(|v| (v - mean).powi(2)).sum();
+    let r2 = if sst > 0.0 {
+        1.0 - resid.norm_squared() / sst
+    } else {
+        f64::NAN
+    };
+    Some((beta, r2))
+}
+
+/// Run the per-date regressions. Rows with any non-finite value are dropped, and dates with
+/// fewer complete rows than regressors + 1 are skipped.
+pub fn fama_macbeth(
+    dates: &[Option<NaiveDate>],
+    ret: &[f64],
+    x: &[Vec<f64>],
+    names: &[String],
+    nw_lags: Option<usize>,
+) -> Result<FamaMacBethResult, String> {
+    let k = x.len() + 1;
+    let by_date = rows_by_date(dates, |i| {
+        ret[i].is_finite() && x.iter().all(|c| c[i].is_finite())
+    });
+
+    let mut out = FamaMacBethResult {
+        regressors: std::iter::once(INTERCEPT.to_string())
+            .chain(names.iter().cloned())
+            .collect(),
+        ..Default::default()
+    };
+    for (date, rows) in by_date {
+        if rows.len() <= k {
+            continue;
+        }
+        let y = DVector::from_iterator(rows.len(), rows.iter().map(|&i| ret[i]));
+        let xm = DMatrix::from_fn(
+            rows.len(),
+            k,
+            |r, c| if c == 0 { 1.0 } else { x[c - 1][rows[r]] },
+        );
+        if let Some((beta, r2)) = ols(&y, &xm) {
+            out.dates.push(date);
+            out.premia.push(beta.iter().copied().collect());
+            out.r2.push(r2);
+            out.n_obs.push(rows.len());
+        }
+    }
+    if out.dates.len() < 2 {
+        return Err(format!(
+            "only {} usable cross-sections; widen the date range or select fewer factors",
+            out.dates.len()
+        ));
+    }
+
+    let t = out.dates.len();
+    out.nw_lags = nw_lags.unwrap_or_else(|| default_nw_lags(t));
+    out.stats = (0..k)
+        .map(|j| {
+            let series: Vec<f64> = out.premia.iter().map(|p| p[j]).collect();
+            let mean = series.iter().sum::<f64>() / t as f64;
+            let nw_se = newey_west_se(&series, out.nw_lags);
+            let note = if t < MIN_T_STAT_MONTHS {
+                Some(format!("only {t} months, need {MIN_T_STAT_MONTHS}"))
+            } else if !(nw_se.is_finite() && nw_se > 1e-12) {
+                Some("Newey-West s.e. is zero (constant premium)".to_string())
+            } else {
+                None
+            };
+            PremiumStat {
+                name: out.regressors[j].clone(),
+                mean,
+                nw_se,
+                t_stat: if note.is_none() {
+                    mean / nw_se
+                } else {
+                    f64::NAN
+                },
+                note,
+            }
+        })
+        .collect();
+    let r2: Vec<f64> = out.r2.iter().copied().filter(|v| v.is_finite()).collect();
+    out.avg_r2 = r2.iter().sum::<f64>() / r2.len().max(1) as f64;
+    Ok(out)
+}
+
+#[server]
+pub async fn run_fama_macbeth(
+    query: FactorQuery,
+    nw_lags: Option<usize>,
+) -> Result<FamaMacBethResult, ServerFnError> {
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (query, nw_lags);
+        return Err(ServerFnError::new(
+            "run_fama_macbeth requires the `server` feature",
+        ));
+    }
+
+    #[cfg(feature = "server")]
+    {
+        let panel = super::panel::load(&query, None).await?;
+        tokio::task::spawn_blocking(move || {
+            fama_macbeth(&panel.dates, &panel.ret, &panel.x, &panel.signals, nw_lags)
+        })
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?
+        .map_err(ServerFnError::new)
+    }
+}
+
+#[component]
+pub fn FamaMacBethPanel(query: FactorQuery) -> Element {
+    let mut nw_lags = use_signal(String::new);
+    let mut running = use_signal(|| false);
+    let mut result: Signal<Option<Result<FamaMacBethResult, String>>> = use_signal(|| None);
+
+    let run = move |_| {
+        let query = query.clone();
+        let lags = nw_lags.peek().trim().parse::<usize>().ok();
+        running.set(true);
+        spawn(async move {
+            result.set(Some(
+                run_fama_macbeth(query, lags)
+                    .await
+                    .map_err(|e| e.to_string()),
+            ));
+            running.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "multi-card-div",
+                label { "Newey-West lags (blank = auto)" }
+                input {
+                    r#type: "number",
+                    min: 0,
+                    value: "{nw_lags}",
+                    oninput: move |evt| nw_lags.set(evt.value()),
+                }
+                button { class: "ma-btn", disabled: running(), onclick: run, "Run Fama-MacBeth" }
+            }
+        }
+        match result() {
+            Some(Ok(res)) => rsx! {
+                p {
+                    "{res.dates.len()} months, {res.avg_obs():.0} firms per month on average, "
+                    "average R² {res.avg_r2:.4}, Newey-West lags {res.nw_lags}"
+                }
+                table { class: "trade-table",
+                    thead {
+                        tr {
+                            th { "Regressor" }
+                            th { "Mean premium" }
+                            th { "NW s.e." }
+                            th { "t-stat" }
+                            th { "" }
+                        }
+                    }
+                    tbody {
+                        for stat in res.stats.iter() {
+                            tr { class: "ind-trade",
+                                td { "{stat.name}" }
+                                td { "{stat.mean:.5}" }
+                                td { "{stat.nw_se:.5}" }
+                                td { "{stat.t_stat:.2}" }
+                                td { "{stat.note.clone().unwrap_or_default()}" }
+                            }
+                        }
+                    }
+                }
+                SeriesChart {
+                    title: "Cumulative factor premia".to_string(),
+                    x_labels: res.dates.iter().map(|d| d.format("%Y-%m").to_string()).collect::<Vec<_>>(),
+                    series: res.cumulative_premia(),
+                }
+            },
+            Some(Err(e)) => rsx! { div { class: "error", "Fama-MacBeth failed: {e}" } },
+            None => rsx! {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn panel(
+        months: usize,
+        slope: impl Fn(usize) -> f64,
+    ) -> (Vec<Option<NaiveDate>>, Vec<f64>, Vec<Vec<f64>>) {
+        let (mut dates, mut ret, mut x) = (Vec::new(), Vec::new(), Vec::new());
+        for m in 0..months {
+            let date = NaiveDate::from_ymd_opt(2000 + (m / 12) as i32, 1 + (m % 12) as u32, 28);
+            for i in 0..20 {
+                let xi = i as f64 / 10.0 - 1.0;
+                dates.push(date);
+                x.push(xi);
+                ret.push(0.01 + slope(m) * xi);
+            }
+        }
+        (dates, ret, vec![x])
+    }
+
+    #[test]
+    fn constant_premium_has_no_t_stat() {
+        let (dates, ret, x) = panel(24, |_| 0.5);
+        let res = fama_macbeth(&dates, &ret, &x, &["f".to_string()], None).unwrap();
+        let stat = &res.stats[1];
+        assert!((stat.mean - 0.5).abs() < 1e-9);
+        assert!(stat.t_stat.is_nan());
+        assert!(stat.note.is_some());
+    }
+
+    #[test]
+    fn short_samples_have_no_t_stat() {
+        let (dates, ret, x) = panel(6, |m| 0.5 + 0.1 * (m % 2) as f64);
+        let res = fama_macbeth(&dates, &ret, &x, &["f".to_string()], None).unwrap();
+        assert!(res
+            .stats
+            .iter()
+            .all(|s| s.t_stat.is_nan() && s.note.is_some()));
+
+        let (dates, ret, x) = panel(36, |m| 0.5 + 0.1 * (m % 2) as f64);
+        let res = fama_macbeth(&dates, &ret, &x, &["f".to_string()], None).unwrap();
+        assert!(res.stats[1].t_stat.is_finite() && res.stats[1].t_stat > 0.0);
+        assert!(res.stats[1].note.is_none());
+    }
+}
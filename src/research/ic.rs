@@ -0,0 +1,306 @@
+// Information coefficients: per-date correlation between a factor and forward returns, at
+// several holding horizons built by compounding `ret_exc_lead1m`.
+use super::{
+    default_nw_lags, newey_west_se, pearson, rows_by_date, spearman, FactorQuery, SecurityId,
+};
+use crate::charts::series::SeriesChart;
+use chrono::{Datelike, NaiveDate};
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Forward horizons in months.
+pub const HORIZONS: [usize; 4] = [1, 3, 6, 12];
+
+/// Cross-sections smaller than this are skipped; correlations on a handful of firms are noise.
+pub const MIN_CROSS_SECTION: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct IcSeries {
+    pub horizon: usize,
+    pub dates: Vec<NaiveDate>,
+    pub pearson: Vec<f64>,
+    pub spearman: Vec<f64>,
+    pub mean_pearson: f64,
+    pub mean_spearman: f64,
+    /// Mean over standard deviation of the Spearman IC.
+    pub icir: f64,
+    /// Share of dates with a positive Spearman IC.
+    pub hit_rate: f64,
+    /// Newey-West t-stat of the mean Spearman IC (overlapping horizons are autocorrelated).
+    pub t_stat: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct FactorIc {
+    pub factor: String,
+    /// One entry per horizon in `HORIZONS` that had usable dates.
+    pub horizons: Vec<IcSeries>,
+}
+
+impl FactorIc {
+    pub fn at(&self, horizon: usize) -> Option<&IcSeries> {
+        self.horizons.iter().find(|s| s.horizon == horizon)
+    }
+}
+
+fn month_index(d: NaiveDate) -> i32 {
+    d.year() * 12 + d.month0() as i32
+}
+
+/// `h`-month forward return per row: the product of `1 + ret_exc_lead1m` over this month and
+/// the next `h - 1` months of the same security (gvkey and iid), NaN unless all of those
+/// months are present.
+pub fn forward_returns(
+    dates: &[Option<NaiveDate>],
+    ids: &[SecurityId],
+    ret: &[f64],
+    h: usize,
+) -> Vec<f64> {
+    if h <= 1 {
+        return ret.to_vec();
+    }
+    let mut by_id: HashMap<&SecurityId, HashMap<i32, usize>> = HashMap::new();
+    for (i, d) in dates.iter().enumerate() {
+        if let Some(d) = d {
+            by_id.entry(&ids[i]).or_default().insert(month_index(*d), i);
+        }
+    }
+    dates
+        .iter()
+        .enumerate()
+        .map(|(i, d)| {
+            let Some(d) = d else { return f64::NAN };
+            let months = &by_id[&ids[i]];
+            let m0 = month_index(*d);
+            let mut acc = 1.0;
+            for k in 0..h as i32 {
+                match months.get(&(m0 + k)).map(|&j| ret[j]) {
+                    Some(r) if r.is_finite() => acc *= 1.0 + r,
+                    _ => return f64::NAN,
+                }
+            }
+            acc - 1.0
+        })
+        .collect()
+}
+
+/// Per-date Pearson and Spearman IC of `signal` against `fwd`, with summary statistics.
+pub fn ic_series(
+    dates: &[Option<NaiveDate>],
+    signal: &[f64],
+    fwd: &[f64],
+    horizon: usize,
+) -> Option<IcSeries> {
+    let by_date = rows_by_date(dates, |i| signal[i].is_finite() && fwd[i].is_finite());
+    let mut out = IcSeries {
+        horizon,
+        ..Default::default()
+    };
+    for (date, rows) in by_date {
+        if rows.len() < MIN_CROSS_SECTION {
+            continue;
+        }
+        let s: Vec<f64> = rows.iter().map(|&i| signal[i]).collect();
+        let r: Vec<f64> = rows.iter().map(|&i| fwd[i]).collect();
+        let (p, sp) = (pearson(&s, &r), spearman(&s, &r));
+        if p.is_finite() && sp.is_finite() {
+            out.dates.push(date);
+            out.pearson.push(p);
+            out.spearman.push(sp);
+        }
+    }
+    let t = out.dates.len();
+    if t < 2 {
+        return None;
+    }
+    out.mean_pearson = out.pearson.iter().sum::<f64>() / t as f64;
+    out.mean_spearman = out.spearman.iter().sum::<f64>() / t as f64;
+    let var = out
+        .spearman
+        .iter()
+        .map(|v| (v - out.mean_spearman).powi(2))
+        .sum::<f64>()
+        / (t as f64 - 1.0);
+    out.icir = out.mean_spearman / var.sqrt();
+    out.hit_rate = out.spearman.iter().filter(|v| **v > 0.0).count() as f64 / t as f64;
+    let lags = default_nw_lags(t).max(horizon - 1);
+    out.t_stat = out.mean_spearman / newey_west_se(&out.spearman, lags);
+    Some(out)
+}
+
+#[server]
+pub async fn run_ic_analysis(query: FactorQuery) -> Result<Vec<FactorIc>, ServerFnError> {
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = query;
+        return Err(ServerFnError::new(
+            "run_ic_analysis requires the `server` feature",
+        ));
+    }
+
+    #[cfg(feature = "server")]
+    {
+        let panel = super::panel::load(&query, None).await?;
+        tokio::task::spawn_blocking(move || {
+            let ids = panel.securities();
+            let fwd: Vec<(usize, Vec<f64>)> = HORIZONS
+                .iter()
+                .map(|&h| (h, forward_returns(&panel.dates, &ids, &panel.ret, h)))
+                .collect();
+            panel
+                .signals
+                .iter()
+                .zip(&panel.x)
+                .map(|(name, signal)| FactorIc {
+                    factor: name.clone(),
+                    horizons: fwd
+                        .iter()
+                        .filter_map(|(h, r)| ic_series(&panel.dates, signal, r, *h))
+                        .collect(),
+                })
+                .collect()
+        })
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))
+    }
+}
+
+#[component]
+pub fn IcPanel(query: FactorQuery) -> Element {
+    let mut running = use_signal(|| false);
+    let mut result: Signal<Option<Result<Vec<FactorIc>, String>>> = use_signal(|| None);
+    let mut horizon = use_signal(|| 1_usize);
+
+    let run = move |_| {
+        let query = query.clone();
+        running.set(true);
+        spawn(async move {
+            result.set(Some(
+                run_ic_analysis(query).await.map_err(|e| e.to_string()),
+            ));
+            running.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "multi-card-div",
+                label { "Horizon for the table and time series" }
+                select {
+                    value: "{horizon}",
+                    onchange: move |evt| {
+                        if let Ok(h) = evt.value().parse::<usize>() {
+                            horizon.set(h);
+                        }
+                    },
+                    for h in HORIZONS {
+                        option { value: "{h}", "{h} month(s)" }
+                    }
+                }
+                button { class: "ma-btn", disabled: running(), onclick: run, "Run IC analysis" }
+            }
+        }
+        match result() {
+            Some(Ok(results)) => {
+                let h = horizon();
+                let decay: Vec<(String, Vec<f64>)> = results
+                    .iter()
+                    .map(|f| {
+                        let curve = HORIZONS
+                            .iter()
+                            .map(|&h| f.at(h).map_or(f64::NAN, |s| s.mean_spearman))
+                            .collect();
+                        (f.factor.clone(), curve)
+                    })
+                    .collect();
+                let mut ic_dates: Vec<NaiveDate> = results
+                    .iter()
+                    .filter_map(|f| f.at(h))
+                    .flat_map(|s| s.dates.iter().copied())
+                    .collect();
+                ic_dates.sort();
+                ic_dates.dedup();
+                let ic_paths: Vec<(String, Vec<f64>)> = results
+                    .iter()
+                    .filter_map(|f| f.at(h).map(|s| (f.factor.clone(), s)))
+                    .map(|(name, s)| {
+                        let by_date: HashMap<NaiveDate, f64> =
+                            s.dates.iter().copied().zip(s.spearman.iter().copied()).collect();
+                        let path = ic_dates
+                            .iter()
+                            .map(|d| by_date.get(d).copied().unwrap_or(f64::NAN))
+                            .collect();
+                        (name, path)
+                    })
+                    .collect();
+                rsx! {
+                    table { class: "trade-table",
+                        thead {
+                            tr {
+                                th { "Factor" }
+                                th { "Months" }
+                                th { "Mean IC (Pearson)" }
+                                th { "Mean IC (Spearman)" }
+                                th { "ICIR" }
+                                th { "Hit rate" }
+                                th { "t-stat (NW)" }
+                            }
+                        }
+                        tbody {
+                            for f in results.iter() {
+                                if let Some(s) = f.at(h) {
+                                    tr { class: "ind-trade",
+                                        td { "{f.factor}" }
+                                        td { "{s.dates.len()}" }
+                                        td { "{s.mean_pearson:.4}" }
+                                        td { "{s.mean_spearman:.4}" }
+                                        td { "{s.icir:.3}" }
+                                        td { {format!("{:.1}%", s.hit_rate * 100.0)} }
+                                        td { "{s.t_stat:.2}" }
+                                    }
+                                } else {
+                                    tr { class: "ind-trade",
+                                        td { "{f.factor}" }
+                                        td { colspan: 6, "not enough data at this horizon" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    SeriesChart {
+                        title: "IC decay: mean Spearman IC by horizon".to_string(),
+                        x_labels: HORIZONS.iter().map(|h| format!("{h}m")).collect::<Vec<_>>(),
+                        series: decay,
+                    }
+                    SeriesChart {
+                        title: format!("Monthly Spearman IC, {h}-month horizon"),
+                        x_labels: ic_dates.iter().map(|d| d.format("%Y-%m").to_string()).collect::<Vec<_>>(),
+                        series: ic_paths,
+                    }
+                }
+            }
+            Some(Err(e)) => rsx! { div { class: "error", "IC analysis failed: {e}" } },
+            None => rsx! {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forward_returns_compound_within_one_share_class() {
+        let m = |month| NaiveDate::from_ymd_opt(2024, month, 28);
+        let a = ("001004".to_string(), "01".to_string());
+        let b = ("001004".to_string(), "02".to_string());
+        let dates = [m(1), m(2), m(1), m(3)];
+        let ids = [a.clone(), a, b.clone(), b];
+        let ret = [0.1, 0.1, 0.5, 0.5];
+        let fwd = forward_returns(&dates, &ids, &ret, 2);
+        assert!((fwd[0] - 0.21).abs() < 1e-12);
+        // The other share class has no February, so its two-month return is unknown.
+        assert!(fwd[1].is_nan() && fwd[2].is_nan() && fwd[3].is_nan());
+    }
+}
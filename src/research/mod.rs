@@ -0,0 +1,274 @@
+// Cross-sectional factor research on the monthly JKP panel: Fama-MacBeth regressions, quantile
+// sorts, information coefficients, factor correlations, cross-country comparisons and the
+// statistics they share.
+pub mod correlation;
+pub mod countries;
+pub mod fama_macbeth;
+pub mod ic;
+pub mod quantiles;
+
+use crate::helpers::formula::DerivedFactor;
+use crate::helpers::prep::FactorPrep;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Next-month excess return, the left-hand side of every cross-sectional test.
+pub const RETURN_COL: &str = "ret_exc_lead1m";
+
+/// A security in the JKP panel: (gvkey, iid). A gvkey alone can cover several share classes.
+pub type SecurityId = (String, String);
+
+/// Which slice of the factors dataset a research run uses; mirrors the Multi-Asset filters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FactorQuery {
+    pub country: String,
+    /// Native and derived factor names; derived ones are defined in `derived`.
+    pub factors: Vec<String>,
+    pub derived: Vec<DerivedFactor>,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub path: String,
+    pub prep: FactorPrep,
+}
+
+impl FactorQuery {
+    /// Selected factor columns without identifiers, the return column or duplicates.
+    pub fn signal_columns(&self) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        for f in &self.factors {
+            if f != RETURN_COL
+                && !crate::datasets::ID_COLUMNS.contains(&f.as_str())
+                && !out.contains(f)
+            {
+                out.push(f.clone());
+            }
+        }
+        out
+    }
+}
+
+/// Newey-West lag rule of thumb, floor(4 (T/100)^(2/9)).
+pub fn default_nw_lags(periods: usize) -> usize {
+    (4.0 * (periods as f64 / 100.0).powf(2.0 / 9.0)).floor() as usize
+}
+
+/// Newey-West (Bartlett kernel) standard error of the mean of `x`.
+pub fn newey_west_se(x: &[f64], lags: usize) -> f64 {
+    let t = x.len();
+    if t < 2 {
+        return f64::NAN;
+    }
+    let mean = x.iter().sum::<f64>() / t as f64;
+    let dev: Vec<f64> = x.iter().map(|v| v - mean).collect();
+    let autocov = |l: usize| {
+        dev[l..]
+            .iter()
+            .zip(&dev[..t - l])
+            .map(|(a, b)| a * b)
+            .sum::<f64>()
+            / t as f64
+    };
+    let mut var = autocov(0);
+    for l in 1..=lags.min(t - 1) {
+        var += 2.0 * (1.0 - l as f64 / (lags as f64 + 1.0)) * autocov(l);
+    }
+    (var.max(0.0) / t as f64).sqrt()
+}
+
+/// Row indices per date, keeping only rows for which `keep` holds.
+pub fn rows_by_date(
+    dates: &[Option<NaiveDate>],
+    keep: impl Fn(usize) -> bool,
+) -> BTreeMap<NaiveDate, Vec<usize>> {
+    let mut out: BTreeMap<NaiveDate, Vec<usize>> = BTreeMap::new();
+    for (i, d) in dates.iter().enumerate() {
+        if let Some(d) = d {
+            if keep(i) {
+                out.entry(*d).or_default().push(i);
+            }
+        }
+    }
+    out
+}
+
+/// Pearson correlation; NaN when either side has no variance.
+pub fn pearson(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len().min(b.len());
+    if n < 2 {
+        return f64::NAN;
+    }
+    let ma = a[..n].iter().sum::<f64>() / n as f64;
+    let mb = b[..n].iter().sum::<f64>() / n as f64;
+    let (mut sab, mut saa, mut sbb) = (0.0, 0.0, 0.0);
+    for i in 0..n {
+        let (da, db) = (a[i] - ma, b[i] - mb);
+        sab += da * db;
+        saa += da * da;
+        sbb += db * db;
+    }
+    if saa <= 0.0 || sbb <= 0.0 {
+        return f64::NAN;
+    }
+    sab / (saa * sbb).sqrt()
+}
+
+/// 1-based ranks with ties sharing their average rank.
+pub fn average_ranks(v: &[f64]) -> Vec<f64> {
+    let mut idx: Vec<usize> = (0..v.len()).collect();
+    idx.sort_by(|&a, &b| v[a].total_cmp(&v[b]));
+    let mut ranks = vec![0.0; v.len()];
+    let mut i = 0;
+    while i < idx.len() {
+        let mut j = i;
+        while j + 1 < idx.len() && v[idx[j + 1]] == v[idx[i]] {
+            j += 1;
+        }
+        let avg = (i + j) as f64 / 2.0 + 1.0;
+        for &k in &idx[i..=j] {
+            ranks[k] = avg;
+        }
+        i = j + 1;
+    }
+    ranks
+}
+
+/// Spearman rank correlation.
+pub fn spearman(a: &[f64], b: &[f64]) -> f64 {
+    pearson(&average_ranks(a), &average_ranks(b))
+}
+
+#[cfg(feature = "server")]
+pub(crate) mod panel {
+    use super::*;
+    use crate::datasets::panel::load_factor_panel;
+    use dioxus::prelude::ServerFnError;
+    use polars::prelude::*;
+
+    /// The query's panel as plain vectors: dates, security ids, countries, returns, optional
+    /// weights and one vector per signal. Nulls become NaN; callers drop incomplete rows.
+    pub struct Panel {
+        pub dates: Vec<Option<NaiveDate>>,
+        /// gvkey and iid per row; see `security`.
+        pub ids: Vec<String>,
+        pub iids: Vec<String>,
+        pub countries: Vec<String>,
+        pub ret: Vec<f64>,
+        pub weights: Option<Vec<f64>>,
+        pub signals: Vec<String>,
+        pub x: Vec<Vec<f64>>,
+    }
+
+    impl Panel {
+        /// (gvkey, iid) of row `i`.
+        pub fn security(&self, i: usize) -> SecurityId {
+            (self.ids[i].clone(), self.iids[i].clone())
+        }
+
+        pub fn securities(&self) -> Vec<SecurityId> {
+            (0..self.ids.len()).map(|i| self.security(i)).collect()
+        }
+    }
+
+    /// Load the query's panel; `weight_col` (e.g. `dolvol`) is fetched alongside the signals and
+    /// read before preprocessing.
+    pub async fn load(
+        query: &FactorQuery,
+        weight_col: Option<&str>,
+    ) -> Result<Panel, ServerFnError> {
+        load_countries(query, std::slice::from_ref(&query.country), weight_col).await
+    }
+
+    /// Like `load`, but for several countries in one query (`query.country` is ignored).
+    /// Preprocessing then runs per date and country.
+    pub async fn load_countries(
+        query: &FactorQuery,
+        countries: &[String],
+        weight_col: Option<&str>,
+    ) -> Result<Panel, ServerFnError> {
+        let signals = query.signal_columns();
+        if signals.is_empty() {
+            return Err(ServerFnError::new("select at least one factor column"));
+        }
+        let mut cols = vec![
+            "date".to_string(),
+            "gvkey".to_string(),
+            "iid".to_string(),
+            "excntry".to_string(),
+            RETURN_COL.to_string(),
+        ];
+        cols.extend(signals.iter().cloned());
+        if let Some(w) = weight_col {
+            if !cols.iter().any(|c| c == w) {
+                cols.push(w.to_string());
+            }
+        }
+        let df = load_factor_panel(
+            countries,
+            cols,
+            &query.derived,
+            query.start,
+            query.end,
+            query.path.as_str(),
+            None,
+            query.prep.clone(),
+            weight_col.as_slice(),
+        )
+        .await?;
+        from_dataframe(&df, signals, weight_col).map_err(|e| ServerFnError::new(e.to_string()))
+    }
+
+    fn from_dataframe(
+        df: &DataFrame,
+        signals: Vec<String>,
+        weight_col: Option<&str>,
+    ) -> PolarsResult<Panel> {
+        let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+        let dates = df
+            .column("date")?
+            .cast(&DataType::Int32)?
+            .i32()?
+            .into_iter()
+            .map(|d| d.map(|d| epoch + chrono::Duration::days(d as i64)))
+            .collect();
+        let str_col = |name: &str| -> PolarsResult<Vec<String>> {
+            Ok(df
+                .column(name)?
+                .cast(&DataType::String)?
+                .str()?
+                .into_iter()
+                .map(|v| v.unwrap_or_default().to_string())
+                .collect())
+        };
+        let ids = str_col("gvkey")?;
+        let iids = str_col("iid")?;
+        let countries = str_col("excntry")?;
+        let f64_col = |name: &str| -> PolarsResult<Vec<f64>> {
+            Ok(df
+                .column(name)?
+                .cast(&DataType::Float64)?
+                .f64()?
+                .into_iter()
+                .map(|v| v.unwrap_or(f64::NAN))
+                .collect())
+        };
+        let ret = f64_col(RETURN_COL)?;
+        let weights = weight_col
+            .map(|w| f64_col(format!("{w}_raw").as_str()))
+            .transpose()?;
+        let x = signals
+            .iter()
+            .map(|s| f64_col(s.as_str()))
+            .collect::<PolarsResult<Vec<_>>>()?;
+        Ok(Panel {
+            dates,
+            ids,
+            iids,
+            countries,
+            ret,
+            weights,
+            signals,
+            x,
+        })
+    }
+}
@@ -0,0 +1,380 @@
+// Sort-based factor backtest: per date, bucket securities into quantiles of each factor and
+// track next-month portfolio returns, the top-minus-bottom spread and its turnover.
+use super::{default_nw_lags, newey_west_se, rows_by_date, spearman, FactorQuery, SecurityId};
+use crate::charts::series::{BarChart, SeriesChart};
+use chrono::NaiveDate;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Size proxy used for value weighting.
+pub const WEIGHT_COL: &str = "dolvol";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weighting {
+    #[default]
+    Equal,
+    Value,
+}
+
+impl Weighting {
+    pub const ALL: [Weighting; 2] = [Weighting::Equal, Weighting::Value];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Weighting::Equal => "equal",
+            Weighting::Value => "value",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Weighting::Equal => "Equal-weighted",
+            Weighting::Value => "Value-weighted (dolvol)",
+        }
+    }
+
+    pub fn from_label(s: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|w| w.as_str() == s)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct QuantileResult {
+    pub factor: String,
+    pub dates: Vec<NaiveDate>,
+    /// Portfolio return per date and quantile; quantile 0 holds the lowest factor values.
+    pub returns: Vec<Vec<f64>>,
+    pub mean_returns: Vec<f64>,
+    /// Top minus bottom quantile per date.
+    pub spread: Vec<f64>,
+    pub spread_mean: f64,
+    /// Newey-West t-stat of the mean spread.
+    pub spread_t: f64,
+    /// Average one-way turnover of the two legs, as a fraction of each leg per rebalance.
+    pub turnover: f64,
+    /// Spearman correlation between quantile rank and mean return, in [-1, 1].
+    pub monotonicity: f64,
+}
+
+impl QuantileResult {
+    /// Compounded long-short return.
+    pub fn cumulative_spread(&self) -> Vec<f64> {
+        let mut acc = 1.0;
+        self.spread
+            .iter()
+            .map(|r| {
+                acc *= 1.0 + r;
+                acc - 1.0
+            })
+            .collect()
+    }
+}
+
+/// Cumulative spreads of several factors on the union of their dates (NaN where a factor has
+/// no portfolio that month), with the date labels.
+pub fn cumulative_spreads(results: &[QuantileResult]) -> (Vec<String>, Vec<(String, Vec<f64>)>) {
+    let mut dates: Vec<NaiveDate> = results
+        .iter()
+        .flat_map(|r| r.dates.iter().copied())
+        .collect();
+    dates.sort();
+    dates.dedup();
+    let series = results
+        .iter()
+        .map(|r| {
+            let path: HashMap<NaiveDate, f64> =
+                r.dates.iter().copied().zip(r.cumulative_spread()).collect();
+            let aligned = dates
+                .iter()
+                .map(|d| path.get(d).copied().unwrap_or(f64::NAN))
+                .collect();
+            (r.factor.clone(), aligned)
+        })
+        .collect();
+    let labels = dates
+        .iter()
+        .map(|d| d.format("%Y-%m").to_string())
+        .collect();
+    (labels, series)
+}
+
+/// One-way turnover between two sets of portfolio weights.
+fn turnover(prev: &HashMap<SecurityId, f64>, next: &HashMap<SecurityId, f64>) -> f64 {
+    let mut sum: f64 = next
+        .iter()
+        .map(|(id, w)| (w - prev.get(id).copied().unwrap_or(0.0)).abs())
+        .sum();
+    sum += prev
+        .iter()
+        .filter(|(id, _)| !next.contains_key(*id))
+        .map(|(_, w)| w.abs())
+        .sum::<f64>();
+    sum / 2.0
+}
+
+/// Sort one factor into `n_q` quantiles per date. `weights` switches to value weighting; rows
+/// without a positive weight are then dropped. Dates with fewer than `n_q` usable rows are
+/// skipped.
+pub fn quantile_sort(
+    factor: &str,
+    dates: &[Option<NaiveDate>],
+    ids: &[SecurityId],
+    ret: &[f64],
+    signal: &[f64],
+    weights: Option<&[f64]>,
+    n_q: usize,
+) -> Option<QuantileResult> {
+    let n_q = n_q.max(2);
+    let by_date = rows_by_date(dates, |i| {
+        signal[i].is_finite()
+            && ret[i].is_finite()
+            && weights.is_none_or(|w| w[i].is_finite() && w[i] > 0.0)
+    });
+
+    let mut out = QuantileResult {
+        factor: factor.to_string(),
+        ..Default::default()
+    };
+    let mut prev_legs: Option<(HashMap<SecurityId, f64>, HashMap<SecurityId, f64>)> = None;
+    let mut turnovers: Vec<f64> = Vec::new();
+    for (date, mut rows) in by_date {
+        if rows.len() < n_q {
+            continue;
+        }
+        rows.sort_by(|&a, &b| signal[a].total_cmp(&signal[b]));
+        let n = rows.len();
+        let mut buckets: Vec<Vec<usize>> = vec![Vec::new(); n_q];
+        for (pos, &i) in rows.iter().enumerate() {
+            buckets[pos * n_q / n].push(i);
+        }
+        let bucket_weights: Vec<Vec<f64>> = buckets
+            .iter()
+            .map(|b| {
+                let raw: Vec<f64> = b.iter().map(|&i| weights.map_or(1.0, |w| w[i])).collect();
+                let total: f64 = raw.iter().sum();
+                raw.into_iter().map(|w| w / total).collect()
+            })
+            .collect();
+        let rets: Vec<f64> = buckets
+            .iter()
+            .zip(&bucket_weights)
+            .map(|(b, w)| b.iter().zip(w).map(|(&i, w)| ret[i] * w).sum())
+            .collect();
+        let leg = |q: usize| -> HashMap<SecurityId, f64> {
+            buckets[q]
+                .iter()
+                .zip(&bucket_weights[q])
+                .map(|(&i, &w)| (ids[i].clone(), w))
+                .collect()
+        };
+        let legs = (leg(n_q - 1), leg(0));
+        if let Some((top, bottom)) = &prev_legs {
+            turnovers.push((turnover(top, &legs.0) + turnover(bottom, &legs.1)) / 2.0);
+        }
+        prev_legs = Some(legs);
+        out.spread.push(rets[n_q - 1] - rets[0]);
+        out.returns.push(rets);
+        out.dates.push(date);
+    }
+    let t = out.dates.len();
+    if t == 0 {
+        return None;
+    }
+
+    out.mean_returns = (0..n_q)
+        .map(|q| out.returns.iter().map(|r| r[q]).sum::<f64>() / t as f64)
+        .collect();
+    out.spread_mean = out.spread.iter().sum::<f64>() / t as f64;
+    out.spread_t = out.spread_mean / newey_west_se(&out.spread, default_nw_lags(t));
+    out.turnover = if turnovers.is_empty() {
+        f64::NAN
+    } else {
+        turnovers.iter().sum::<f64>() / turnovers.len() as f64
+    };
+    let ranks: Vec<f64> = (1..=n_q).map(|q| q as f64).collect();
+    out.monotonicity = spearman(&ranks, &out.mean_returns);
+    Some(out)
+}
+
+#[server]
+pub async fn run_quantile_backtest(
+    query: FactorQuery,
+    n_quantiles: usize,
+    weighting: Weighting,
+) -> Result<Vec<QuantileResult>, ServerFnError> {
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (query, n_quantiles, weighting);
+        return Err(ServerFnError::new(
+            "run_quantile_backtest requires the `server` feature",
+        ));
+    }
+
+    #[cfg(feature = "server")]
+    {
+        if !(2..=20).contains(&n_quantiles) {
+            return Err(ServerFnError::new(
+                "number of quantiles must be between 2 and 20",
+            ));
+        }
+        let weight_col = (weighting == Weighting::Value).then_some(WEIGHT_COL);
+        let panel = super::panel::load(&query, weight_col).await?;
+        tokio::task::spawn_blocking(move || {
+            let ids = panel.securities();
+            panel
+                .signals
+                .iter()
+                .zip(&panel.x)
+                .filter_map(|(name, signal)| {
+                    quantile_sort(
+                        name,
+                        &panel.dates,
+                        &ids,
+                        &panel.ret,
+                        signal,
+                        panel.weights.as_deref(),
+                        n_quantiles,
+                    )
+                })
+                .collect()
+        })
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))
+    }
+}
+
+#[component]
+pub fn QuantilePanel(query: FactorQuery) -> Element {
+    let mut n_quantiles = use_signal(|| 5_usize);
+    let mut weighting = use_signal(Weighting::default);
+    let mut running = use_signal(|| false);
+    let mut result: Signal<Option<Result<Vec<QuantileResult>, String>>> = use_signal(|| None);
+    let mut shown = use_signal(|| 0_usize);
+
+    let run = move |_| {
+        let query = query.clone();
+        let n_q = n_quantiles();
+        let w = weighting();
+        running.set(true);
+        spawn(async move {
+            result.set(Some(
+                run_quantile_backtest(query, n_q, w)
+                    .await
+                    .map_err(|e| e.to_string()),
+            ));
+            shown.set(0);
+            running.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "card",
+            div { class: "multi-card-div",
+                label { "Quantiles" }
+                input {
+                    r#type: "number",
+                    min: 2,
+                    max: 20,
+                    value: "{n_quantiles}",
+                    oninput: move |evt| {
+                        if let Ok(v) = evt.value().parse::<usize>() {
+                            n_quantiles.set(v);
+                        }
+                    },
+                }
+                label { "Weighting" }
+                select {
+                    value: "{weighting().as_str()}",
+                    onchange: move |evt| weighting.set(Weighting::from_label(&evt.value())),
+                    for w in Weighting::ALL {
+                        option { value: "{w.as_str()}", "{w.label()}" }
+                    }
+                }
+                button { class: "ma-btn", disabled: running(), onclick: run, "Run quantile sorts" }
+            }
+        }
+        match result() {
+            Some(Ok(results)) if results.is_empty() => rsx! {
+                div { "No factor had enough securities per date for the chosen quantiles." }
+            },
+            Some(Ok(results)) => {
+                let (x_labels, series) = cumulative_spreads(&results);
+                rsx! {
+                table { class: "trade-table",
+                    thead {
+                        tr {
+                            th { "Factor" }
+                            th { "Months" }
+                            th { "Top - bottom (mean)" }
+                            th { "t-stat (NW)" }
+                            th { "Turnover" }
+                            th { "Monotonicity" }
+                        }
+                    }
+                    tbody {
+                        for (i, r) in results.iter().enumerate() {
+                            tr { class: "ind-trade",
+                                td {
+                                    a {
+                                        href: "#",
+                                        onclick: move |evt: MouseEvent| {
+                                            evt.prevent_default();
+                                            shown.set(i);
+                                        },
+                                        "{r.factor}"
+                                    }
+                                }
+                                td { "{r.dates.len()}" }
+                                td { "{r.spread_mean:.5}" }
+                                td { "{r.spread_t:.2}" }
+                                td { "{r.turnover:.3}" }
+                                td { "{r.monotonicity:.2}" }
+                            }
+                        }
+                    }
+                }
+                if let Some(r) = results.get(shown()) {
+                    BarChart {
+                        title: format!("{}: mean next-month return by quantile", r.factor),
+                        labels: (1..=r.mean_returns.len()).map(|q| format!("Q{q}")).collect::<Vec<_>>(),
+                        values: r.mean_returns.clone(),
+                    }
+                }
+                SeriesChart {
+                    title: "Cumulative top-minus-bottom return".to_string(),
+                    x_labels,
+                    series,
+                }
+                }
+            }
+            Some(Err(e)) => rsx! { div { class: "error", "Quantile backtest failed: {e}" } },
+            None => rsx! {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn share_classes_are_separate_legs() {
+        // Two months, four securities; gvkey 1 has two share classes that swap legs.
+        let d1 = NaiveDate::from_ymd_opt(2024, 1, 31);
+        let d2 = NaiveDate::from_ymd_opt(2024, 2, 29);
+        let id = |g: &str, i: &str| (g.to_string(), i.to_string());
+        let ids = [id("1", "01"), id("1", "02"), id("2", "01"), id("3", "01")];
+        let ids: Vec<SecurityId> = ids.iter().chain(ids.iter()).cloned().collect();
+        let dates = [vec![d1; 4], vec![d2; 4]].concat();
+        let signal = [1.0, 4.0, 2.0, 3.0, 4.0, 1.0, 2.0, 3.0];
+        let ret = [0.01; 8];
+        let res = quantile_sort("f", &dates, &ids, &ret, &signal, None, 2).unwrap();
+        assert_eq!(res.dates.len(), 2);
+        // Keyed by gvkey alone both legs would look unchanged; by security half of each turns over.
+        assert!((res.turnover - 0.5).abs() < 1e-12);
+    }
+}
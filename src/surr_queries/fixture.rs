@@ -0,0 +1,126 @@
+#![cfg(all(feature = "server", feature = "mem-db"))]
+// Embedded in-memory Surreal engine seeded with synthetic `trades` and `equities_returns`
+// rows, so the views and `query_surr_*` functions can run without the hosted instance.
+use crate::data_structures::{Hd, SecurityData, TradeRecord};
+use crate::ingest::{bin_size_secs, bins, store};
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use surrealdb::engine::any::{self, Any};
+use surrealdb::Surreal;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use tokio::sync::OnceCell;
+
+/// URL the views use to ask for the fixture instead of a remote database.
+pub const MEM_URL: &str = "mem://";
+/// Instruments seeded into the fixture (the defaults used by the Single Asset view).
+pub const FIXTURE_INSTRUMENTS: &[u32] = &[8147, 11667];
+/// Bin sizes seeded into `equities_returns`, matching the Bin Size dropdown.
+pub const FIXTURE_BIN_SIZES: &[&str] = &["1m", "5m", "30m", "1hour", "1day"];
+const FIXTURE_DAYS: i64 = 5;
+const TRADES_PER_MINUTE: i64 = 2;
+const PRICE_SCALE: f64 = 1_000_000_000.0;
+
+/// Last instant of fixture data (the close of Friday 2024-03-15), so every run sees the same rows.
+pub fn fixture_anchor() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, 15, 21, 0, 0).unwrap()
+}
+
+type FixtureCell = Arc<OnceCell<Surreal<Any>>>;
+
+/// One embedded engine per (namespace, database). Each connection is pointed at its ns/db once,
+/// when it is seeded, and never switched afterwards, so concurrent requests can share it.
+static FIXTURES: LazyLock<Mutex<HashMap<(String, String), FixtureCell>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Return the fixture connection for `ns`/`dbname`, creating and seeding it on first use.
+pub async fn fixture_db(ns: &str, dbname: &str) -> Result<Surreal<Any>, surrealdb::Error> {
+    let cell = FIXTURES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry((ns.to_string(), dbname.to_string()))
+        .or_default()
+        .clone();
+    let db = cell
+        .get_or_try_init(|| async {
+            let db = any::connect(MEM_URL).await?;
+            db.use_ns(ns).use_db(dbname).await?;
+            seed(&db, fixture_anchor()).await?;
+            Ok::<_, surrealdb::Error>(db)
+        })
+        .await?;
+    Ok(db.clone())
+}
+
+/// Seed `FIXTURE_DAYS` of synthetic trades (and the bins derived from them) ending at `until`.
+pub async fn seed(db: &Surreal<Any>, until: DateTime<Utc>) -> Result<(), surrealdb::Error> {
+    for &instrument_id in FIXTURE_INSTRUMENTS {
+        let trades = synthetic_trades(instrument_id, until);
+        tracing::debug!("seeding {} trades for {instrument_id}", trades.len());
+        store::insert_trades(db, &trades).await?;
+        for &bin_size in FIXTURE_BIN_SIZES {
+            let secs = bin_size_secs(bin_size).unwrap_or(60);
+            store::insert_bins(db, bins::bins_from_trades(&trades, bin_size, secs)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Small deterministic generator so fixture runs are reproducible.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next_f64(&mut self) -> f64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Random-walk trades during 14:30-21:00 UTC on weekdays, with prices scaled by 1e9 like Databento.
+pub fn synthetic_trades(instrument_id: u32, until: DateTime<Utc>) -> Vec<TradeRecord> {
+    let mut rng = Lcg(instrument_id as u64);
+    let mut price = 50.0 + (instrument_id % 100) as f64;
+    let mut rows = Vec::new();
+    let last_day = until.date_naive();
+    let mut day = last_day - Duration::days(FIXTURE_DAYS * 7 / 5 + 2);
+    while day <= last_day {
+        if day.weekday().number_from_monday() <= 5 {
+            let open = Utc.from_utc_datetime(&day.and_hms_opt(14, 30, 0).unwrap());
+            for minute in 0..390 {
+                let bin_1m = open + Duration::minutes(minute);
+                if bin_1m > until {
+                    break;
+                }
+                for k in 0..TRADES_PER_MINUTE {
+                    price *= 1.0 + (rng.next_f64() - 0.5) * 0.002;
+                    let ts_recv = bin_1m
+                        + Duration::seconds(k * 60 / TRADES_PER_MINUTE)
+                        + Duration::milliseconds((rng.next_f64() * 1_000.0) as i64);
+                    let ts_recv_ns = ts_recv.timestamp_nanos_opt().unwrap_or_default();
+                    let ts_in_delta = 5_000 + (rng.next_f64() * 20_000.0) as i32;
+                    rows.push(TradeRecord {
+                        hd: Hd {
+                            instrument_id,
+                            length: 12,
+                            publisher_id: 1,
+                            rtype: 0,
+                            ts_event: (ts_recv_ns - ts_in_delta as i64) as u64,
+                        },
+                        data: SecurityData {
+                            instrument_id,
+                            ts_in_delta,
+                            size: 1 + (rng.next_f64() * 500.0) as i64,
+                            ts_recv: ts_recv_ns,
+                            price: (price * PRICE_SCALE).round() as i64,
+                        },
+                        sequence: rows.len() as u32,
+                    });
+                }
+            }
+        }
+        day += Duration::days(1);
+    }
+    rows
+}
@@ -1,6 +1,9 @@
+pub mod fixture;
+
 use core::time;
 
 use crate::data_structures::*;
+use crate::ops::multi_type_mat::MyMmMatrix;
 use crate::ops::*;
 use nalgebra::DMatrix;
 use crate::ops::datetimeops::*;
@@ -12,6 +15,7 @@ use surrealdb::opt::auth::Root;
 use surrealdb::{Response, Surreal};
 use dioxus::prelude::*;
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 #[cfg(feature="server")]
 use ml_backend::{
     surreal_queries::{make_db,DbParams},
@@ -23,6 +27,37 @@ use ml_backend::{
 };
 #[cfg(feature = "server")]
 use polars::prelude::*;
+#[cfg(feature = "server")]
+use crate::ops::microstructure::TS_OFFSET_COL;
+
+/// Default Surreal endpoint for the views: the embedded fixture under `mem-db`, else the hosted instance.
+pub fn default_surreal_url() -> String {
+    if cfg!(feature = "mem-db") {
+        String::from("mem://")
+    } else {
+        String::from("https://quant-platform-06cb0tpcrpsspao10de28go15s.aws-use1.surreal.cloud/rpc")
+    }
+}
+
+/// Connect to `url`, routing `mem://` to the seeded in-memory fixture when `mem-db` is enabled.
+#[cfg(feature = "server")]
+pub async fn connect_surr(
+    url: &str,
+    user: &str,
+    pass: &str,
+    ns: &str,
+    dbname: &str,
+) -> Result<Surreal<any::Any>, ServerFnError> {
+    #[cfg(feature = "mem-db")]
+    if url.starts_with(fixture::MEM_URL) {
+        return fixture::fixture_db(ns, dbname)
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()));
+    }
+    make_db(url, user, pass, ns, dbname)
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))
+}
 
 
 
@@ -39,9 +74,8 @@ pub async fn query_surr_trademsg_db(
     instrument_id: i64,   // <--- pass the instrument id directly
 ) -> Result<MyMatrix, ServerFnError> {
     //let db = any::connect("wss://quant-platform-06cb0tpcrpsspao10de28go15s.aws-use1.surreal.cloud").await?;
-    let db = make_db(url.as_str(), user.as_str(), pass.as_str(), ns.as_str(), dbname.as_str())
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let db = connect_surr(url.as_str(), user.as_str(), pass.as_str(), ns.as_str(), dbname.as_str())
+        .await?;
     let column_vec = vec!["price", "size", "ts_in_delta", "ts_recv", "bin_1m"];
     println!("{:?}", column_vec.clone());
     let part_eq_surr = PartEqSurr {
@@ -57,9 +91,13 @@ pub async fn query_surr_trademsg_db(
         .await
         .map_err(|e| ServerFnError::new(e.to_string()))?;
     let exprs: Vec<Expr> = vec![col("ts_recv")];
+    // ts_recv is ~1.7e18 ns and only holds 256 ns steps once the matrix turns it into f64, so
+    // the exact offset from the first print travels alongside it for inter-arrival times.
+    let ts_ns = col("ts_recv").cast(DataType::Int64);
     df = df
         .lazy()
         .sort_by_exprs(exprs, SortMultipleOptions::default())
+        .with_column((ts_ns.clone() - ts_ns.min()).alias(TS_OFFSET_COL))
         .collect()
         .map_err(|e| ServerFnError::new(e.to_string()))?;
     df = df.drop(time_col.as_str())
@@ -69,7 +107,10 @@ pub async fn query_surr_trademsg_db(
     let mut my_matrix = MyMatrix::from_polars_dataframe(&df)
         .map_err(|e| ServerFnError::new(e.to_string()))?;
     //println!("dataframe: {:?}", &my_matrix.data.clone());
-    my_matrix = my_matrix.scale_column(0.000000001, 0).unwrap();
+    // Prices are stored as 1e-9 fixed point.
+    if let Some(j) = my_matrix.find_index("price") {
+        my_matrix = my_matrix.scale_column(0.000000001, j).unwrap();
+    }
     //my_matrix.data = my_matrix.estimate_retuns().unwrap();
     my_matrix.descrips = my_matrix.snapshot(0).unwrap();
     Ok(my_matrix)
@@ -90,9 +131,8 @@ pub async fn query_surr_trade_bin_db(
     bin_size: String,
 ) -> Result<MyMatrix, ServerFnError> {
     //let db = any::connect("wss://quant-platform-06cb0tpcrpsspao10de28go15s.aws-use1.surreal.cloud").await?;
-    let db = make_db(url.as_str(), user.as_str(), pass.as_str(), ns.as_str(), dbname.as_str())
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let db = connect_surr(url.as_str(), user.as_str(), pass.as_str(), ns.as_str(), dbname.as_str())
+        .await?;
     let column_vec = vec![ "ret","t0", "t1", "bin",  "mean_price", "p0", "p1","pmax", "pmin", "price_diff"];
     println!("Column Names {:?}", column_vec.clone());
     let part_eq_surr = PartEqSurr {
@@ -139,3 +179,319 @@ pub async fn query_surr_trade_bin_db(
     Ok(my_matrix)
 }
 
+/// Tables (and the columns on them) that `query_surr_filtered` is allowed to touch.
+/// Nested record fields use Surreal dot syntax, e.g. `hd.instrument_id`.
+pub const SURR_ALLOW_LIST: &[(&str, &[&str])] = &[
+    (
+        "trades",
+        &[
+            "price",
+            "size",
+            "ts_in_delta",
+            "ts_recv",
+            "bin_1m",
+            "hd.instrument_id",
+            "hd.publisher_id",
+            "hd.rtype",
+            "hd.ts_event",
+        ],
+    ),
+    (
+        "equities_returns",
+        &[
+            "instrument_id",
+            "bin_size",
+            "ret",
+            "t0",
+            "t1",
+            "bin",
+            "mean_price",
+            "p0",
+            "p1",
+            "pmax",
+            "pmin",
+            "price_diff",
+        ],
+    ),
+];
+
+/// Client-side mirror of `ml_backend::polars_ops::Logic`, which only exists on the server.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FilterLogic {
+    And,
+    Or,
+}
+
+/// Serializable version of `PartEqSurr`: typed equality filters plus an optional time range.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SurrFilter {
+    pub int: Vec<(String, i64, FilterLogic)>,
+    pub string: Vec<(String, String, FilterLogic)>,
+    pub float: Vec<(String, f64, FilterLogic)>,
+    pub time_range: Option<(String, DateTime<Utc>, DateTime<Utc>, FilterLogic)>,
+}
+
+impl SurrFilter {
+    /// Every column referenced by the filter, used for the allow-list check.
+    pub fn columns(&self) -> Vec<&str> {
+        let mut out: Vec<&str> = Vec::new();
+        out.extend(self.int.iter().map(|(c, _, _)| c.as_str()));
+        out.extend(self.string.iter().map(|(c, _, _)| c.as_str()));
+        out.extend(self.float.iter().map(|(c, _, _)| c.as_str()));
+        if let Some((c, _, _, _)) = &self.time_range {
+            out.push(c.as_str());
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SortKey {
+    pub column: String,
+    pub descending: bool,
+}
+
+/// Check a table/column request against `SURR_ALLOW_LIST`.
+pub fn check_allow_list(table: &str, columns: &[&str]) -> Result<(), String> {
+    let allowed = SURR_ALLOW_LIST
+        .iter()
+        .find(|(t, _)| *t == table)
+        .map(|(_, cols)| *cols)
+        .ok_or_else(|| format!("table '{table}' is not queryable"))?;
+    match columns.iter().find(|c| !allowed.contains(c)) {
+        Some(c) => Err(format!("column '{c}' is not queryable on '{table}'")),
+        None => Ok(()),
+    }
+}
+
+/// SurrealQL for a filtered select, with the filter values returned as `$p<i>` bindings.
+/// Sorting and the row limit run in the database; Surreal can only order by selected fields,
+/// so every sort key must also be one of `columns`. Identifiers are interpolated as-is, so
+/// run `check_allow_list` first.
+pub fn filtered_select_surql(
+    table: &str,
+    columns: &[String],
+    filter: &SurrFilter,
+    sort: &[SortKey],
+    limit: Option<u32>,
+) -> Result<(String, Vec<(String, serde_json::Value)>), String> {
+    if columns.is_empty() {
+        return Err("no columns requested".to_string());
+    }
+    if let Some(k) = sort.iter().find(|k| !columns.contains(&k.column)) {
+        return Err(format!("sort column '{}' is not selected", k.column));
+    }
+    let mut conds: Vec<(String, FilterLogic)> = Vec::new();
+    let mut binds: Vec<(String, serde_json::Value)> = Vec::new();
+    let mut bind = |v: serde_json::Value| {
+        let name = format!("p{}", binds.len());
+        binds.push((name.clone(), v));
+        name
+    };
+    for (c, v, l) in &filter.int {
+        conds.push((format!("{c} = ${}", bind((*v).into())), *l));
+    }
+    for (c, v, l) in &filter.string {
+        conds.push((format!("{c} = ${}", bind(v.as_str().into())), *l));
+    }
+    for (c, v, l) in &filter.float {
+        conds.push((format!("{c} = ${}", bind((*v).into())), *l));
+    }
+    if let Some((c, t0, t1, l)) = &filter.time_range {
+        let (p0, p1) = (bind(t0.to_rfc3339().into()), bind(t1.to_rfc3339().into()));
+        conds.push((
+            format!("({c} >= <datetime> ${p0} AND {c} <= <datetime> ${p1})"),
+            *l,
+        ));
+    }
+
+    let mut sql = format!("SELECT {} FROM {table}", columns.join(", "));
+    for (i, (cond, logic)) in conds.iter().enumerate() {
+        sql.push_str(match (i, logic) {
+            (0, _) => " WHERE ",
+            (_, FilterLogic::And) => " AND ",
+            (_, FilterLogic::Or) => " OR ",
+        });
+        sql.push_str(cond);
+    }
+    if !sort.is_empty() {
+        let keys: Vec<String> = sort
+            .iter()
+            .map(|k| format!("{} {}", k.column, if k.descending { "DESC" } else { "ASC" }))
+            .collect();
+        sql.push_str(&format!(" ORDER BY {}", keys.join(", ")));
+    }
+    if let Some(n) = limit {
+        sql.push_str(&format!(" LIMIT {n}"));
+    }
+    Ok((sql, binds))
+}
+
+/// Build a frame from Surreal result rows, following dot paths into nested records. Numeric
+/// columns become f64, RFC3339 strings become nanoseconds since the epoch (datetimes don't
+/// survive the trip into `MyMmMatrix`), and anything else stays a string.
+#[cfg(feature = "server")]
+fn rows_to_df(rows: &[serde_json::Value], columns: &[String]) -> PolarsResult<DataFrame> {
+    let frame: Vec<Column> = columns
+        .iter()
+        .map(|name| {
+            let cells: Vec<&serde_json::Value> = rows
+                .iter()
+                .map(|r| {
+                    name.split('.')
+                        .try_fold(r, |v, key| v.get(key))
+                        .unwrap_or(&serde_json::Value::Null)
+                })
+                .collect();
+            let present = || cells.iter().filter(|v| !v.is_null());
+            let series = if present().all(|v| v.is_number()) {
+                Series::new(
+                    name.as_str().into(),
+                    cells.iter().map(|v| v.as_f64()).collect::<Vec<Option<f64>>>(),
+                )
+            } else if present().all(|v| {
+                v.as_str()
+                    .is_some_and(|s| DateTime::parse_from_rfc3339(s).is_ok())
+            }) {
+                Series::new(
+                    name.as_str().into(),
+                    cells
+                        .iter()
+                        .map(|v| {
+                            v.as_str()
+                                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                                .and_then(|dt| dt.timestamp_nanos_opt())
+                                .map(|ns| ns as f64)
+                        })
+                        .collect::<Vec<Option<f64>>>(),
+                )
+            } else {
+                Series::new(
+                    name.as_str().into(),
+                    cells
+                        .iter()
+                        .map(|v| match v {
+                            serde_json::Value::Null => None,
+                            serde_json::Value::String(s) => Some(s.clone()),
+                            other => Some(other.to_string()),
+                        })
+                        .collect::<Vec<Option<String>>>(),
+                )
+            };
+            series.into_column()
+        })
+        .collect();
+    DataFrame::new(frame)
+}
+
+#[server]
+pub async fn query_surr_filtered(
+    url: String,
+    user: String,
+    pass: String,
+    ns: String,
+    dbname: String,
+    table: String,
+    columns: Vec<String>,
+    filter: SurrFilter,
+    sort: Vec<SortKey>,
+    limit: Option<u32>,
+) -> Result<MyMmMatrix, ServerFnError> {
+    let mut requested: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+    requested.extend(filter.columns());
+    requested.extend(sort.iter().map(|k| k.column.as_str()));
+    check_allow_list(table.as_str(), &requested).map_err(ServerFnError::new)?;
+    let (sql, binds) = filtered_select_surql(table.as_str(), &columns, &filter, &sort, limit)
+        .map_err(ServerFnError::new)?;
+
+    let db = connect_surr(url.as_str(), user.as_str(), pass.as_str(), ns.as_str(), dbname.as_str())
+        .await?;
+    let mut query = db.query(sql.as_str());
+    for bound in binds {
+        query = query.bind(bound);
+    }
+    let rows: surrealdb::Value = query
+        .await
+        .and_then(|mut res| res.take(0))
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let rows = match rows.into_inner().into_json() {
+        serde_json::Value::Array(rows) => rows,
+        other => vec![other],
+    };
+    let mut df = rows_to_df(&rows, &columns).map_err(|e| ServerFnError::new(e.to_string()))?;
+    tracing::debug!("filtered {table} shape: {:?}", df.shape());
+
+    MyMmMatrix::from_polars_dataframe(&mut df).map_err(|e| ServerFnError::new(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn allow_list_rejects_unknown_tables_and_columns() {
+        assert!(check_allow_list("trades", &["price", "hd.instrument_id"]).is_ok());
+        assert!(check_allow_list("equities_returns", &[]).is_ok());
+        assert_eq!(
+            check_allow_list("users", &["price"]),
+            Err("table 'users' is not queryable".to_string())
+        );
+        assert_eq!(
+            check_allow_list("trades", &["price", "password"]),
+            Err("column 'password' is not queryable on 'trades'".to_string())
+        );
+        // Columns are allowed per table, not globally.
+        assert!(check_allow_list("trades", &["mean_price"]).is_err());
+    }
+
+    #[test]
+    fn filtered_select_pushes_sort_and_limit_into_the_query() {
+        let filter = SurrFilter {
+            int: vec![("instrument_id".to_string(), 8147, FilterLogic::And)],
+            string: vec![("bin_size".to_string(), "5m".to_string(), FilterLogic::And)],
+            time_range: Some((
+                "bin".to_string(),
+                Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 1, 3, 0, 0, 0).unwrap(),
+                FilterLogic::Or,
+            )),
+            ..SurrFilter::default()
+        };
+        let sort = vec![SortKey {
+            column: "bin".to_string(),
+            descending: true,
+        }];
+        let columns = vec!["bin".to_string(), "p1".to_string()];
+        let (sql, binds) =
+            filtered_select_surql("equities_returns", &columns, &filter, &sort, Some(10)).unwrap();
+        assert_eq!(
+            sql,
+            "SELECT bin, p1 FROM equities_returns WHERE instrument_id = $p0 AND bin_size = $p1 \
+             OR (bin >= <datetime> $p2 AND bin <= <datetime> $p3) ORDER BY bin DESC LIMIT 10"
+        );
+        assert_eq!(binds.len(), 4);
+        assert_eq!(binds[0], ("p0".to_string(), serde_json::json!(8147)));
+        assert_eq!(binds[2].1, serde_json::json!("2024-01-02T00:00:00+00:00"));
+    }
+
+    #[test]
+    fn filtered_select_requires_selected_sort_keys() {
+        let sort = vec![SortKey {
+            column: "ts_recv".to_string(),
+            descending: false,
+        }];
+        let err = filtered_select_surql(
+            "trades",
+            &["price".to_string()],
+            &SurrFilter::default(),
+            &sort,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err, "sort column 'ts_recv' is not selected");
+        assert!(
+            filtered_select_surql("trades", &[], &SurrFilter::default(), &[], None).is_err()
+        );
+    }
+}
@@ -0,0 +1,485 @@
+// Reusable grid for `MyMmMatrix`: click-to-sort headers, per-column filters, pagination,
+// per-column number formats, pinned id columns and a column chooser.
+use crate::ops::multi_type_mat::MyMmMatrix;
+use chrono::DateTime;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// Rows-per-page choices offered under the grid.
+pub const PAGE_SIZES: [usize; 5] = [25, 50, 100, 250, 1000];
+
+/// How a numeric cell is printed. NaN prints as an empty cell.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum CellFormat {
+    /// Shortest round-trip representation.
+    #[default]
+    Auto,
+    Fixed(usize),
+    /// Multiplied by 100, with a `%` sign.
+    Percent(usize),
+    Scientific(usize),
+    /// Rounded, with thousands separators.
+    Integer,
+    /// Nanoseconds since the epoch, printed as a UTC timestamp.
+    NanosUtc,
+}
+
+impl CellFormat {
+    pub fn render(&self, v: f64) -> String {
+        if v.is_nan() {
+            return String::new();
+        }
+        match self {
+            CellFormat::Auto => v.to_string(),
+            CellFormat::Fixed(d) => format!("{v:.d$}"),
+            CellFormat::Percent(d) => format!("{:.d$}%", v * 100.0),
+            CellFormat::Scientific(d) => format!("{v:.d$e}"),
+            CellFormat::Integer => {
+                let digits = format!("{:.0}", v.abs());
+                let mut out = String::new();
+                for (i, ch) in digits.chars().enumerate() {
+                    if i > 0 && (digits.len() - i) % 3 == 0 {
+                        out.push(',');
+                    }
+                    out.push(ch);
+                }
+                if v < 0.0 && out != "0" {
+                    out.insert(0, '-');
+                }
+                out
+            }
+            CellFormat::NanosUtc => {
+                let secs = (v / 1e9).floor();
+                DateTime::from_timestamp(secs as i64, (v - secs * 1e9) as u32)
+                    .map(|t| t.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+                    .unwrap_or_default()
+            }
+        }
+    }
+}
+
+/// Sort `rows` by `column`: numeric columns by value with NaN last in either direction,
+/// string columns as text.
+pub fn sort_rows(mat: &MyMmMatrix, rows: &mut [usize], column: &str, ascending: bool) {
+    if let Some(j) = mat.find_index_f64(column) {
+        rows.sort_by(|&a, &b| {
+            let (x, y) = (mat.data_f64[(a, j)], mat.data_f64[(b, j)]);
+            match (x.is_nan(), y.is_nan()) {
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                _ if ascending => x.total_cmp(&y),
+                _ => y.total_cmp(&x),
+            }
+        });
+    } else {
+        rows.sort_by(|&a, &b| {
+            let ord = mat.cell(a, column).cmp(&mat.cell(b, column));
+            if ascending {
+                ord
+            } else {
+                ord.reverse()
+            }
+        });
+    }
+}
+
+/// A column filter as typed under the header. Numeric columns accept `>x`, `>=x`, `<x`, `<=x`,
+/// `=x` and `a..b` (inclusive); anything else is a case-insensitive substring of the cell.
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Gt(f64),
+    Ge(f64),
+    Lt(f64),
+    Le(f64),
+    Eq(f64),
+    Between(f64, f64),
+    Text(String),
+}
+
+impl Filter {
+    fn parse(text: &str, numeric: bool) -> Option<Filter> {
+        let t = text.trim();
+        if t.is_empty() {
+            return None;
+        }
+        let num = |s: &str| s.trim().parse::<f64>().ok();
+        if numeric {
+            let parsed = if let Some((a, b)) = t.split_once("..") {
+                num(a).zip(num(b)).map(|(a, b)| Filter::Between(a, b))
+            } else if let Some(x) = t.strip_prefix(">=") {
+                num(x).map(Filter::Ge)
+            } else if let Some(x) = t.strip_prefix("<=") {
+                num(x).map(Filter::Le)
+            } else if let Some(x) = t.strip_prefix('>') {
+                num(x).map(Filter::Gt)
+            } else if let Some(x) = t.strip_prefix('<') {
+                num(x).map(Filter::Lt)
+            } else if let Some(x) = t.strip_prefix('=') {
+                num(x).map(Filter::Eq)
+            } else {
+                None
+            };
+            if parsed.is_some() {
+                return parsed;
+            }
+        }
+        Some(Filter::Text(t.to_lowercase()))
+    }
+
+    /// `value` is the raw number for numeric columns; `shown` is the rendered cell.
+    fn matches(&self, value: Option<f64>, shown: &str) -> bool {
+        let cmp = |f: &dyn Fn(f64) -> bool| value.is_some_and(|v| !v.is_nan() && f(v));
+        match self {
+            Filter::Gt(x) => cmp(&|v| v > *x),
+            Filter::Ge(x) => cmp(&|v| v >= *x),
+            Filter::Lt(x) => cmp(&|v| v < *x),
+            Filter::Le(x) => cmp(&|v| v <= *x),
+            Filter::Eq(x) => cmp(&|v| v == *x),
+            Filter::Between(a, b) => cmp(&|v| v >= a.min(*b) && v <= a.max(*b)),
+            Filter::Text(t) => shown.to_lowercase().contains(t.as_str()),
+        }
+    }
+}
+
+/// Width of each pinned column, so the sticky offsets line up.
+const PINNED_WIDTH_EM: f64 = 7.0;
+
+/// Grid over every row of `mat`. `pinned` columns stay on the left while scrolling and
+/// cannot be hidden; the rest come from `groups` (header groups, e.g. factor themes) or, when
+/// `groups` is empty, from every other column of `mat`. Numeric cells use their entry in
+/// `formats`, else `default_format`. `titles` are header tooltips. With `row_numbers`, a
+/// leading `#` column shows each row's 1-based position in `mat`.
+#[component]
+pub fn DataGrid(
+    mat: MyMmMatrix,
+    #[props(default)] pinned: Vec<String>,
+    #[props(default)] groups: Vec<(String, Vec<String>)>,
+    #[props(default)] formats: Vec<(String, CellFormat)>,
+    #[props(default)] default_format: CellFormat,
+    #[props(default)] titles: Vec<(String, String)>,
+    #[props(default = 50)] page_size: usize,
+    #[props(default)] row_numbers: bool,
+) -> Element {
+    let mut sort: Signal<Option<(String, bool)>> = use_signal(|| None);
+    let mut filters: Signal<Vec<(String, String)>> = use_signal(Vec::new);
+    let mut hidden: Signal<Vec<String>> = use_signal(Vec::new);
+    let mut page = use_signal(|| 0_usize);
+    let mut per_page = use_signal(|| page_size.max(1));
+
+    let pinned: Vec<String> = pinned
+        .into_iter()
+        .filter(|c| mat.find_index_str(c).is_some() || mat.find_index_f64(c).is_some())
+        .collect();
+    let groups: Vec<(String, Vec<String>)> = if groups.is_empty() {
+        vec![(
+            String::new(),
+            mat.column_names()
+                .into_iter()
+                .filter(|c| !pinned.contains(c))
+                .collect(),
+        )]
+    } else {
+        groups
+    };
+    let all_cols: Vec<String> = groups.iter().flat_map(|(_, c)| c.iter().cloned()).collect();
+    let shown_groups: Vec<(String, Vec<String>)> = groups
+        .iter()
+        .map(|(g, cols)| {
+            let cols: Vec<String> = cols
+                .iter()
+                .filter(|c| !hidden().contains(c))
+                .cloned()
+                .collect();
+            (g.clone(), cols)
+        })
+        .filter(|(_, cols)| !cols.is_empty())
+        .collect();
+    let cols: Vec<String> = shown_groups
+        .iter()
+        .flat_map(|(_, c)| c.iter().cloned())
+        .collect();
+    let show_groups = shown_groups.iter().any(|(g, _)| !g.is_empty());
+
+    let format_of = |c: &str| {
+        formats
+            .iter()
+            .find(|(name, _)| name == c)
+            .map_or(default_format, |(_, f)| *f)
+    };
+    let render = |row: usize, c: &str| match mat.find_index_f64(c) {
+        Some(j) => format_of(c).render(mat.data_f64[(row, j)]),
+        None => mat.cell(row, c),
+    };
+    let title_of = |c: &str| {
+        titles
+            .iter()
+            .find(|(name, _)| name == c)
+            .map(|(_, t)| t.clone())
+            .unwrap_or_default()
+    };
+
+    // Filter, sort, then slice out the page.
+    let active: Vec<(String, Filter)> = filters()
+        .iter()
+        .filter_map(|(c, text)| {
+            Filter::parse(text, mat.find_index_f64(c).is_some()).map(|f| (c.clone(), f))
+        })
+        .collect();
+    let mut rows: Vec<usize> = (0..mat.nrows())
+        .filter(|&i| {
+            active.iter().all(|(c, f)| {
+                let value = mat.find_index_f64(c).map(|j| mat.data_f64[(i, j)]);
+                f.matches(value, &render(i, c))
+            })
+        })
+        .collect();
+    if let Some((c, asc)) = sort() {
+        sort_rows(&mat, &mut rows, &c, asc);
+    }
+    let n_pages = rows.len().div_ceil(per_page()).max(1);
+    let current = page().min(n_pages - 1);
+    let page_rows: Vec<usize> = rows
+        .iter()
+        .skip(current * per_page())
+        .take(per_page())
+        .copied()
+        .collect();
+    let n_filtered = rows.len();
+    let n_total = mat.nrows();
+
+    let filter_text = move |c: &str| {
+        filters()
+            .iter()
+            .find(|(name, _)| name == c)
+            .map(|(_, t)| t.clone())
+            .unwrap_or_default()
+    };
+    let mut set_filter = move |c: String, text: String| {
+        filters.with_mut(|f| {
+            f.retain(|(name, _)| *name != c);
+            if !text.trim().is_empty() {
+                f.push((c, text));
+            }
+        });
+        page.set(0);
+    };
+    let mut click_sort = move |c: String| {
+        // Ascending, then descending, then back to the matrix order.
+        let next = match sort() {
+            Some((prev, true)) if prev == c => Some((c, false)),
+            Some((prev, false)) if prev == c => None,
+            _ => Some((c, true)),
+        };
+        sort.set(next);
+        page.set(0);
+    };
+    let arrow = move |c: &str| match sort() {
+        Some((s, true)) if s == c => " \u{25B2}",
+        Some((s, false)) if s == c => " \u{25BC}",
+        _ => "",
+    };
+    // Sticky offset of the `slot`-th leading column (the `#` column comes first).
+    let pin_style = |slot: usize| {
+        format!(
+            "position: sticky; left: {}em; z-index: 1; min-width: {PINNED_WIDTH_EM}em; max-width: {PINNED_WIDTH_EM}em;",
+            PINNED_WIDTH_EM * slot as f64
+        )
+    };
+    let offset = usize::from(row_numbers);
+    let lead = offset + pinned.len();
+
+    rsx! {
+        div { class: "grid-toolbar",
+            span { "{n_filtered} of {n_total} rows" }
+            details { class: "grid-columns",
+                summary { "Columns ({cols.len()}/{all_cols.len()})" }
+                for c in all_cols.iter().cloned() {
+                    label { key: "{c}",
+                        input {
+                            r#type: "checkbox",
+                            checked: !hidden().contains(&c),
+                            onchange: {
+                                let c = c.clone();
+                                move |evt: FormEvent| {
+                                    hidden.with_mut(|h| {
+                                        h.retain(|x| *x != c);
+                                        if !evt.checked() {
+                                            h.push(c.clone());
+                                        }
+                                    });
+                                }
+                            },
+                        }
+                        " {c}"
+                    }
+                }
+            }
+            if !filters().is_empty() {
+                button {
+                    class: "ma-btn",
+                    onclick: move |_| {
+                        filters.set(Vec::new());
+                        page.set(0);
+                    },
+                    "Clear filters"
+                }
+            }
+        }
+        div { class: "trade-table-wrap",
+            table { class: "data-grid",
+                thead {
+                    if show_groups {
+                        tr {
+                            if lead > 0 {
+                                th { class: "grid-pinned", colspan: lead, style: pin_style(0), "" }
+                            }
+                            for (g, members) in shown_groups.iter() {
+                                th { colspan: members.len(), "{g}" }
+                            }
+                        }
+                    }
+                    tr {
+                        if row_numbers {
+                            th { class: "grid-pinned", style: pin_style(0), "#" }
+                        }
+                        for (k, c) in pinned.iter().cloned().enumerate() {
+                            th {
+                                class: "grid-pinned grid-sortable",
+                                style: pin_style(k + offset),
+                                title: title_of(&c),
+                                onclick: {
+                                    let c = c.clone();
+                                    move |_| click_sort(c.clone())
+                                },
+                                "{c}{arrow(&c)}"
+                            }
+                        }
+                        for c in cols.iter().cloned() {
+                            th {
+                                class: "grid-sortable",
+                                title: title_of(&c),
+                                onclick: {
+                                    let c = c.clone();
+                                    move |_| click_sort(c.clone())
+                                },
+                                "{c}{arrow(&c)}"
+                            }
+                        }
+                    }
+                    tr { class: "grid-filters",
+                        if row_numbers {
+                            th { class: "grid-pinned", style: pin_style(0), "" }
+                        }
+                        for (k, c) in pinned.iter().chain(cols.iter()).cloned().enumerate() {
+                            th {
+                                class: if k < pinned.len() { "grid-pinned" } else { "" },
+                                style: if k < pinned.len() { pin_style(k + offset) } else { String::new() },
+                                input {
+                                    r#type: "text",
+                                    placeholder: if mat.find_index_f64(&c).is_some() { ">0, a..b" } else { "contains" },
+                                    value: "{filter_text(&c)}",
+                                    oninput: {
+                                        let c = c.clone();
+                                        move |evt: FormEvent| set_filter(c.clone(), evt.value())
+                                    },
+                                }
+                            }
+                        }
+                    }
+                }
+                tbody {
+                    for i in page_rows {
+                        tr { class: "ind-trade", key: "{i}",
+                            if row_numbers {
+                                td { class: "grid-pinned", style: pin_style(0), "{i + 1}" }
+                            }
+                            for (k, c) in pinned.iter().enumerate() {
+                                td { class: "grid-pinned", style: pin_style(k + offset), "{render(i, c)}" }
+                            }
+                            for c in cols.iter() {
+                                td { class: if mat.find_index_f64(c).is_some() { "grid-num" } else { "" },
+                                    "{render(i, c)}"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        div { class: "grid-pager",
+            button {
+                class: "ma-btn",
+                disabled: current == 0,
+                onclick: move |_| page.set(current.saturating_sub(1)),
+                "Prev"
+            }
+            span { " Page {current + 1} of {n_pages} " }
+            button {
+                class: "ma-btn",
+                disabled: current + 1 >= n_pages,
+                onclick: move |_| page.set(current + 1),
+                "Next"
+            }
+            label { " Rows per page " }
+            select {
+                value: "{per_page}",
+                onchange: move |evt| {
+                    if let Ok(v) = evt.value().parse::<usize>() {
+                        per_page.set(v.max(1));
+                        page.set(0);
+                    }
+                },
+                for n in PAGE_SIZES {
+                    option { value: "{n}", "{n}" }
+                }
+                if !PAGE_SIZES.contains(&per_page()) {
+                    option { value: "{per_page}", "{per_page}" }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_filters_parse_comparisons_and_ranges() {
+        assert_eq!(Filter::parse(" >0.1", true), Some(Filter::Gt(0.1)));
+        assert_eq!(Filter::parse(">= -2", true), Some(Filter::Ge(-2.0)));
+        assert_eq!(Filter::parse("<5", true), Some(Filter::Lt(5.0)));
+        assert_eq!(Filter::parse("<=1e3", true), Some(Filter::Le(1000.0)));
+        assert_eq!(Filter::parse("=1", true), Some(Filter::Eq(1.0)));
+        assert_eq!(
+            Filter::parse("-0.5..0.5", true),
+            Some(Filter::Between(-0.5, 0.5))
+        );
+        assert_eq!(Filter::parse("", true), None);
+        // Anything that is not a number falls back to a text match.
+        assert_eq!(
+            Filter::parse(">abc", true),
+            Some(Filter::Text(">abc".to_string()))
+        );
+        assert_eq!(
+            Filter::parse("1..", true),
+            Some(Filter::Text("1..".to_string()))
+        );
+        assert_eq!(
+            Filter::parse(">0.1", false),
+            Some(Filter::Text(">0.1".to_string()))
+        );
+    }
+
+    #[test]
+    fn filters_match_values_and_skip_missing_ones() {
+        let f = Filter::parse("0.5..0", true).unwrap();
+        assert!(f.matches(Some(0.25), ""));
+        assert!(f.matches(Some(0.5), ""));
+        assert!(!f.matches(Some(0.75), ""));
+        assert!(!f.matches(Some(f64::NAN), ""));
+        assert!(!Filter::Gt(0.0).matches(None, "1"));
+        assert!(Filter::parse("US", false).unwrap().matches(None, "usa"));
+    }
+}
@@ -1,66 +1,184 @@
-use crate::ops::{multi_type_mat::MyMmMatrix, MyMatrix};
+pub mod grid;
+
+use crate::charts::{
+    clustering::cluster_color,
+    series::{BarChart, SeriesChart},
+};
+use crate::helpers::catalog::catalog;
+use crate::ops::{
+    calendar::Exchange,
+    microstructure::MicrostructureReport,
+    multi_type_mat::{ClusterFit, MyMmMatrix},
+    quality::{IssueKind, QualityReport},
+    stats::DescriptiveStats,
+    MyMatrix,
+};
 use dioxus::prelude::*;
+use grid::{CellFormat, DataGrid};
+use nalgebra::DMatrix;
 use serde::{Deserialize, Serialize};
+fn stat_cell(v: f64, decimals: usize) -> String {
+    if v.is_finite() {
+        format!("{v:.decimals$}")
+    } else {
+        "n/a".to_string()
+    }
+}
+
+/// Descriptive statistics of the selected single-asset series (`ops::stats`). `stoch_vol` is a
+/// volatility model's name and its annualized next-bin forecast (`ops::garch`).
 #[component]
-pub fn SalesTable(props: MyMatrix) -> Element {
+pub fn SalesTable(
+    stats: DescriptiveStats,
+    #[props(default)] stoch_vol: Option<(String, f64)>,
+) -> Element {
+    let opt = |v: Option<f64>, decimals: usize| stat_cell(v.unwrap_or(f64::NAN), decimals);
+    let mut rows: Vec<(String, String)> = vec![
+        ("Returns (n)".to_string(), stats.n.to_string()),
+        ("Mean return".to_string(), stat_cell(stats.mean, 6)),
+        ("Volatility (SD)".to_string(), stat_cell(stats.sd, 6)),
+        ("Annualized return".to_string(), stat_cell(stats.ann_return, 4)),
+        ("Annualized volatility".to_string(), stat_cell(stats.ann_vol, 4)),
+        ("Skewness".to_string(), stat_cell(stats.skewness, 4)),
+        ("Excess kurtosis".to_string(), stat_cell(stats.excess_kurtosis, 4)),
+        (
+            "Jarque-Bera (p-value)".to_string(),
+            format!(
+                "{} ({})",
+                stat_cell(stats.jarque_bera, 2),
+                stat_cell(stats.jb_pvalue, 4)
+            ),
+        ),
+    ];
+    rows.extend(
+        stats
+            .autocorr
+            .iter()
+            .map(|(lag, rho)| (format!("Autocorrelation (lag {lag})"), stat_cell(*rho, 4))),
+    );
+    rows.extend([
+        ("Hurst exponent".to_string(), stat_cell(stats.hurst, 4)),
+        match stoch_vol {
+            Some((model, vol)) => (
+                format!("Stoch Vol. ({model}, next bin, ann.)"),
+                stat_cell(vol, 4),
+            ),
+            None => ("Stoch Vol.".to_string(), "n/a".to_string()),
+        },
+        ("Total volume".to_string(), opt(stats.total_volume, 0)),
+        ("Trade count".to_string(), opt(stats.trade_count, 0)),
+        ("Avg trade size".to_string(), opt(stats.avg_trade_size, 2)),
+    ]);
     rsx! {
         table { class: "table_cls",
             thead {
                 tr {
                     th { class: "text-left-header", "Descriptive Statistics Table"}
-                    th { class: "text-right-header", }
+                    th { class: "text-right-header", "{stats.source.label()}" }
                 }
             }
             tbody { class: "table-hover",
-                tr {
-                    td { class: "text-left", "Mean"}
-                    td { class: "text-right", "{props.descrips.0}"}
-                }
-                tr {
-                    td { class: "text-left", "Volatility(SD)"}
-                    td { class: "text-right", "{props.descrips.1}"}
-                }
-                tr {
-                    td { class: "text-left",  "Volume"}
-                    td { class: "text-right",  "{props.descrips.2}"}
-                }
-                tr {
-                    td { class: "text-left", "Grouping" }
-                    td { class: "text-right", "{props.descrips.3}"}
-                }
-                tr {
-                    td { class: "text-left", "Stoch Vol." }
-                    td { class: "text-right", "{props.descrips.4}"}
-                }
-                tr {
-                    td {class: "text-left", "Buy Score"}
-                    td {class: "text-right","{props.descrips.5}"}
+                for (name, value) in rows {
+                    tr {
+                        td { class: "text-left", "{name}" }
+                        td { class: "text-right", "{value}" }
+                    }
                 }
             }
         }
     }
 }
 
+/// Trade prints (price, size, time stamp) in a `DataGrid`; the time stamp is the difference of
+/// the matrix's last two columns, in nanoseconds.
 //#[component]
 pub fn TradeDisplay(trades: MyMatrix) -> Element {
-    let nrow = trades.data.nrows() - 1;
-    println!("row number{:?}", nrow);
+    let data = &trades.data;
+    let mat = if data.ncols() >= 4 {
+        MyMmMatrix {
+            data_f64: DMatrix::from_fn(data.nrows(), 3, |i, j| match j {
+                0 => data[(i, 0)],
+                1 => data[(i, 1)],
+                _ => data[(i, 3)] - data[(i, 2)],
+            }),
+            colnames_enum_f64: Some(vec![
+                (0, "Price".to_string()),
+                (1, "Size".to_string()),
+                (2, "Time-Stamp".to_string()),
+            ]),
+            ..MyMmMatrix::new(0, 0)
+        }
+    } else {
+        MyMmMatrix::new(0, 0)
+    };
+    rsx! {
+        DataGrid {
+            mat,
+            formats: vec![
+                ("Size".to_string(), CellFormat::Integer),
+                ("Time-Stamp".to_string(), CellFormat::NanosUtc),
+            ],
+            page_size: 100,
+        }
+    }
+}
+
+/// Summary of `ops::quality` findings for the trade and bin series, plus the first flagged rows.
+#[component]
+pub fn QualityPanel(trades: QualityReport, bins: QualityReport) -> Element {
+    let max_listed = 50;
+    let listed: Vec<(String, usize, String, String)> = trades
+        .issues
+        .iter()
+        .chain(bins.issues.iter())
+        .take(max_listed)
+        .enumerate()
+        .map(|(i, issue)| {
+            let source = if i < trades.issues.len() {
+                trades.source.clone()
+            } else {
+                bins.source.clone()
+            };
+            (source, issue.row, issue.kind.as_str().to_string(), issue.detail.clone())
+        })
+        .collect();
     rsx! {
-        table {
-            class: "trade-table",
+        table { class: "kv-table",
             thead {
                 tr {
-                    th { "Price" }
-                    th { "Size" }
-                    th { "Time-Stamp" }
+                    th { "Data Quality" }
+                    th { "Trades ({trades.rows} rows)" }
+                    th { "Bins ({bins.rows} rows)" }
                 }
             }
             tbody {
-                for (_i,trade) in trades.data.view((0,0),(nrow,4)).row_iter().enumerate() {
-                    tr {class: "ind-trade",
-                            td { "{trade[0]}" } // Price
-                            td { "{trade[1]}" } // Size
-                            td { "{MyMatrix::convert_nano_to_datetime(trade[3]-trade[2]).unwrap()}" } // TS Recv
+                for kind in IssueKind::ALL.iter() {
+                    tr {
+                        th { "{kind.as_str()}" }
+                        td { "{trades.count(*kind)}" }
+                        td { "{bins.count(*kind)}" }
+                    }
+                }
+            }
+        }
+        if !listed.is_empty() {
+            table { class: "trade-table",
+                thead {
+                    tr {
+                        th { "Series" }
+                        th { "Row" }
+                        th { "Issue" }
+                        th { "Detail" }
+                    }
+                }
+                tbody {
+                    for (source, row, kind, detail) in listed {
+                        tr { class: "ind-trade",
+                            td { "{source}" }
+                            td { "{row}" }
+                            td { "{kind}" }
+                            td { "{detail}" }
+                        }
                     }
                 }
             }
@@ -68,82 +186,180 @@ pub fn TradeDisplay(trades: MyMatrix) -> Element {
     }
 }
 
+/// Trade-print microstructure measures (`ops::microstructure`): summary table, order-flow
+/// imbalance per bin and the trade-size histogram.
+#[component]
+pub fn MicrostructurePanel(report: MicrostructureReport, exchange: Exchange) -> Element {
+    if report.n_trades < 2 {
+        return rsx!(div { "No trades loaded" });
+    }
+    let dist = |b: Option<crate::charts::boxplot::BoxStats>, decimals: usize| match b {
+        Some(b) => format!(
+            "mean {}, median {}, 95th pct {}",
+            stat_cell(b.mean, decimals),
+            stat_cell(b.median, decimals),
+            stat_cell(b.p95, decimals)
+        ),
+        None => "n/a".to_string(),
+    };
+    let rows: Vec<(&str, String)> = vec![
+        ("Trades", report.n_trades.to_string()),
+        ("Buyer-initiated volume (tick rule)", stat_cell(report.buy_share * 100.0, 1) + "%"),
+        (
+            "Roll spread",
+            format!(
+                "{} ({} bps)",
+                stat_cell(report.roll_spread, 6),
+                stat_cell(report.roll_spread_bps, 2)
+            ),
+        ),
+        ("Amihud illiquidity (|ret| per $1M)", stat_cell(report.amihud, 6)),
+        (
+            "Kyle's lambda (t, R\u{b2})",
+            format!(
+                "{:.3e} ({}, {})",
+                report.kyle_lambda,
+                stat_cell(report.kyle_t, 2),
+                stat_cell(report.kyle_r2, 3)
+            ),
+        ),
+        ("Trade size", dist(report.size_dist, 1)),
+        ("Inter-arrival (s)", dist(report.inter_arrival, 4)),
+        ("Inter-arrival CV", stat_cell(report.inter_arrival_cv, 3)),
+        (
+            "Same-timestamp prints",
+            stat_cell(report.simultaneous_share * 100.0, 1) + "%",
+        ),
+    ];
+    let x_labels: Vec<String> = report
+        .bins
+        .iter()
+        .map(|b| exchange.axis_label(b.start))
+        .collect();
+    let imbalance: Vec<f64> = report.bins.iter().map(|b| b.imbalance).collect();
+    let (size_labels, size_counts): (Vec<String>, Vec<f64>) = report
+        .size_hist
+        .iter()
+        .map(|(l, c)| (l.clone(), *c as f64))
+        .unzip();
+    rsx! {
+        table { class: "kv-table",
+            tbody {
+                for (name, value) in rows {
+                    tr { th { "{name}" } td { "{value}" } }
+                }
+            }
+        }
+        SeriesChart {
+            title: "Order-flow imbalance per bin, (buy - sell) / (buy + sell)".to_string(),
+            x_labels,
+            series: vec![("Imbalance".to_string(), imbalance)],
+        }
+        BarChart {
+            title: "Trade size distribution (trades per size bucket)".to_string(),
+            labels: size_labels,
+            values: size_counts,
+        }
+    }
+}
+
 #[derive(Debug, Props, PartialEq, Clone, Serialize, Deserialize)]
 pub struct MyMmMatrixandFacs {
     pub mat: MyMmMatrix,
-    pub cross_factors: Option<Vec<String>>,
-    pub idiosyn_factors: Option<Vec<String>>,
+    /// (theme, columns) header groups, usually from `FactorCatalog::group_by_theme`.
+    pub groups: Vec<(String, Vec<String>)>,
     pub id_cols: Vec<String>,
 }
-//In the prop need:
-//      - cross-sectional factor count
-//      - idiosyncratic factor count
+/// Factor matrix in a `DataGrid`: `id_cols` pinned on the left, factor columns under their
+/// theme headers with catalog definitions as tooltips.
 #[component]
 pub fn MultiFactorDisplay(props: MyMmMatrixandFacs) -> Element {
-    let nrow = props.mat.data_f64.nrows();
-    let cf = props.cross_factors.unwrap();
-    let idif = props.idiosyn_factors.unwrap();
-    let idc = props.id_cols;
-    let str_data = props.mat.data_str;
-    let float_data = props.mat.data_f64;
-    let str_cols = props.mat.colnames_enum_str.unwrap();
-    let float_cols = props.mat.colnames_enum_f64.unwrap();
-    let mut count = use_signal(|| 0);
-    let render_cell = |row: usize, column_name: &String| -> String {
-        if let Some((j, _)) = str_cols.iter().find(|(_, s)| s == column_name) {
-            str_data
-                .get((row, *j))
-                .map(|v| v.to_string())
-                .unwrap_or_default()
-        } else if let Some((j, _)) = float_cols.iter().find(|(_, s)| s == column_name) {
-            float_data
-                .get((row, *j))
-                .map(|v| v.to_string())
-                .unwrap_or_default()
-        } else {
-            String::new()
-        }
-    };
-    fn render_cell_6(s: String) -> String {
-        if let Ok(x) = s.parse::<f64>() {
-            format!("{:.6}", x)
-        } else {
-            s
+    let cat = catalog();
+    let titles: Vec<(String, String)> = props
+        .groups
+        .iter()
+        .flat_map(|(_, cols)| cols.iter())
+        .map(|c| (c.clone(), cat.tooltip(c)))
+        .collect();
+    rsx! {
+        DataGrid {
+            mat: props.mat,
+            pinned: props.id_cols,
+            groups: props.groups,
+            titles,
+            default_format: CellFormat::Fixed(6),
         }
     }
+}
 
-    rsx! {
-        div {class: "trade-table-wrap",
-        table {class: "trade-table-mf",
-            thead {
-                tr {
-                    th{colspan: idc.len(), ""}
-                    th {colspan: cf.len(),"Cross-Sectional Factors"}
-                    th {colspan: idif.len(),"Idiosyncratic Factors"}
+/// Securities in each cluster (gvkey/iid) with the cluster's mean of every clustered column,
+/// in the columns' own units.
+#[component]
+pub fn ClusterMembership(mat: MyMmMatrix, fit: ClusterFit) -> Element {
+    let mut groups: Vec<(String, f64, Vec<usize>)> = fit
+        .members()
+        .into_iter()
+        .enumerate()
+        .filter(|(_, rows)| !rows.is_empty())
+        .map(|(c, rows)| (format!("Cluster {}", c + 1), c as f64, rows))
+        .collect();
+    let noise = fit.noise();
+    if !noise.is_empty() {
+        groups.push(("Noise".to_string(), -1.0, noise));
+    }
+    let col_idx: Vec<Option<usize>> = fit
+        .columns
+        .iter()
+        .map(|c| mat.find_index_f64(c))
+        .collect();
+    let profile = |rows: &[usize]| -> Vec<String> {
+        col_idx
+            .iter()
+            .map(|j| {
+                let vals: Vec<f64> = j
+                    .map(|j| rows.iter().map(|&i| mat.data_f64[(i, j)]).collect())
+                    .unwrap_or_default();
+                let vals: Vec<f64> = vals.into_iter().filter(|v| v.is_finite()).collect();
+                if vals.is_empty() {
+                    String::new()
+                } else {
+                    format!("{:.4}", vals.iter().sum::<f64>() / vals.len() as f64)
                 }
-                tr {
-                    for nm in idc.iter() {
-                        th { id: "secid", "{nm}" }
-                    }
-                    for nm in cf.iter() {
-                        th { id: "fact", "{nm}" }
-                    }
-                    for nm in idif.iter() {
-                        th { id: "fact", "{nm}"}
+            })
+            .collect()
+    };
+    let id = |i: usize| format!("{}/{}", mat.cell(i, "gvkey"), mat.cell(i, "iid"));
+
+    rsx! {
+        div { class: "trade-table-wrap",
+            table { class: "trade-table",
+                thead {
+                    tr {
+                        th { "Cluster" }
+                        th { "Size" }
+                        th { "Securities (gvkey/iid)" }
+                        for c in fit.columns.iter() {
+                            th { "{c}" }
+                        }
                     }
                 }
-            }
-            tbody {
-                for i in 1..nrow {
-                    tr {class: "ind-trade",
-                        for nm in idc.iter() { td { id: "secid", "{render_cell(i, nm)}" } }
-                        for nm in cf.iter() { td { id: "fact", "{render_cell_6(render_cell(i, nm))}" } }
-                        for nm in idif.iter() { td { id: "fact", "{render_cell_6(render_cell(i, nm))}" } }
+                tbody {
+                    for (name, label, rows) in groups.iter() {
+                        tr { class: "ind-trade",
+                            td {
+                                span { style: "color: {cluster_color(*label)};", "\u{25CF} " }
+                                "{name}"
+                            }
+                            td { "{rows.len()}" }
+                            td { {rows.iter().map(|&i| id(i)).collect::<Vec<_>>().join(", ")} }
+                            for v in profile(rows) {
+                                td { "{v}" }
+                            }
+                        }
                     }
                 }
             }
         }
-        }
     }
 }
 
@@ -1,15 +1,35 @@
-use crate::charts::clustering::{CharPlot, NmsPca, PcaChart};
-use crate::helpers::{self, dropdownlist};
-use crate::tables::MyMmMatrixandFacsBuilder_Error_Repeated_field_idiosyn_factors;
+use crate::charts::{
+    clustering::{CharPlot, LoadingsBiplot, NmsPca, PcaChart},
+    series::{BarChart, SeriesChart},
+};
+use crate::helpers::{
+    self,
+    catalog::catalog,
+    dropdownlist::{self, DynOption},
+    formula::{DerivedFactor, DerivedFactorEditor},
+    prep::{FactorPrep, PrepTransform, NEUTRALIZE_BY},
+};
 #[cfg(feature = "server")]
 use crate::{
-    dbinst::{duckstore, SharedDuck},
+    charts::clustering::SIZE_SOURCE_COL,
+    datasets::panel::load_factor_panel,
     helpers::plrs,
 };
 use crate::{
-    ops::multi_type_mat::MyMmMatrix,
+    datasets::{
+        console::SqlConsole, factor_schema, screener::ScreenerPanel, DatasetsPanel, FactorSchema,
+        ID_COLUMNS,
+    },
+    ops::multi_type_mat::{
+        ClusterAlgo, ClusterConfig, ClusterFit, MyMmMatrix, PcaConfig, PcaFit,
+    },
     prompting::PromptBox,
-    tables::{MultiFactorDisplay, SecurityComp},
+    research::{
+        correlation::FactorCorrelationPanel, countries::CountryComparisonPanel,
+        fama_macbeth::FamaMacBethPanel, ic::IcPanel,
+        quantiles::QuantilePanel, FactorQuery,
+    },
+    tables::{ClusterMembership, MultiFactorDisplay, SecurityComp},
 };
 use chrono::{NaiveDate, TimeZone, Utc};
 use dioxus::prelude::*;
@@ -24,98 +44,32 @@ use std::ops::Deref;
 //Create features if not pre-computed
 //Create filters for the top factors table on date, factors, bins, Securiti
 
-#[cfg(feature = "server")]
-mod dbfac {
-    use super::*;
-    use dioxus::prelude::ServerFnError;
-    use duckdb::Connection;
-    use polars::prelude::*;
-    use std::sync::{Arc, Mutex};
-    use wrds_io;
-
-    /// Start an in-memory DuckDB instance via the words_db helper and return the connection.
-    /// Later, we can promote this to a global `OnceCell<Arc<Connection>>` if needed.
-
-    pub async fn db_inst(factors_path: String) -> Result<SharedDuck, ServerFnError> {
-        let conn = wrds_io::instantiatedb::duckdbinst::start_duck_db("8GB", 14)
-            .await
-            .map_err(|e| ServerFnError::new(format!("duckdb start error: {:?}", e)))?;
-        let arc_conn = Arc::new(Mutex::new(conn.try_clone().unwrap()));
-        if factors_path == "".to_string() {
-            wrds_io::instantiatedb::duckdbinst::DbType::EquityFactorsMonthly
-                .ingest(arc_conn.clone(), &factors_path)
-                .await
-                .map_err(|e| {
-                    ServerFnError::new(format!("ingest equity factors monthly parquet: {:?}", e))
-                })?;
-        } else {
-            //fundamentals data
-            //if factors_path == "temp2".to_string() {
-            wrds_io::instantiatedb::duckdbinst::DbType::EquityFactorsMonthly
-                .ingest(arc_conn.clone(), &factors_path)
-                .await
-                .map_err(|e| {
-                    ServerFnError::new(format!("ingest equity factors monthly parquet: {:?}", e))
-                })?;
-        } /*else { //header data
-              wrds_io::instantiatedb::duckdbinst::DbType::
-                  .ingest(arc_conn.clone(), &factors_path)
-                  .await
-                  .map_err(|e| {
-                      ServerFnError::new(format!("ingest equity factors monthly parquet: {:?}", e))
-                  })?;
-          }*/
-        Ok(arc_conn)
-    }
-    pub async fn query_factors_range(
-        conn: Arc<Mutex<Connection>>,
-        country: &str,
-        factors: Vec<String>,
-        start_iso: NaiveDate,
-        end_iso: NaiveDate,
-    ) -> PolarsResult<DataFrame> {
-        let df_rows =
-            wrds_io::finance_data_structs::equity_factors::EquityFactorsMonthly::read_range(
-                conn,
-                (start_iso, end_iso),
-            )
-            .await
-            .map_err(|e| PolarsError::ComputeError(format!("read_range: {e:?}").into()))?;
-        tracing::debug!("set data rows");
-        let mut fac_df = <wrds_io::finance_data_structs::equity_factors::EquityFactorsMonthly as wrds_io::finance_data_structs::ToPolars>::df_from_rows(&df_rows)
-            .map_err(|e| PolarsError::ComputeError(format!("df_from_rows: {e:?}").into()))?;
-        tracing::debug!("Initiate DataFrame");
-        let factors_expr: Vec<Expr> = factors.iter().map(|c| col(c.as_str())).collect();
-        tracing::debug!("shape: {:?}", &fac_df.shape());
-        fac_df = fac_df
-            .lazy()
-            .select(factors_expr)
-            .filter(col("excntry").eq(lit(country)))
-            .sort_by_exprs(
-                vec![col("excntry"), col("gvkey"), col("date")],
-                Default::default(),
-            )
-            .collect()
-            .map_err(|e| PolarsError::ComputeError(format!("collect: {e:?}").into()))?;
-        tracing::debug!("shape: {:?}", &fac_df.shape());
-
-        Ok(fac_df)
-    }
-}
-
 // Return a serializable matrix; keep connection on server side
 #[server]
 pub async fn fetch_factors_matrix(
     country: String,
     factors: Vec<String>,
+    derived: Vec<DerivedFactor>,
     start_iso: NaiveDate,
     end_iso: NaiveDate,
     factors_path: String,
     gby: Option<Vec<String>>,
+    securities: Option<Vec<String>>,
+    prep: Option<FactorPrep>,
 ) -> Result<(MyMmMatrix, MyMmMatrix), ServerFnError> {
     #[cfg(not(feature = "server"))]
     {
-        let _ = (country, factors, start_iso, end_iso, factors_path, gby);
+        let _ = (
+            country,
+            factors,
+            derived,
+            start_iso,
+            end_iso,
+            factors_path,
+            gby,
+            securities,
+            prep,
+        );
         return Err(ServerFnError::new(
             "fetch_factors_matrix requires the `server` feature",
         ));
@@ -124,23 +78,34 @@ pub async fn fetch_factors_matrix(
     #[cfg(feature = "server")]
     {
         tracing::debug!("Retrieving Factors");
-        if duckstore::get().await.is_none() {
-            tracing::debug!("build data from parquet file");
-            _ = duckstore::set(dbfac::db_inst(factors_path).await?).await;
-        } else {
-            tracing::debug!("Data already loaded into memory no need to rebuild from parquet");
+        // The grouped matrix carries each firm's mean raw `dolvol` for the CharPlot bubble
+        // sizes, whether or not `dolvol` itself was selected.
+        let mut factors = factors;
+        let add_size = gby.is_some() && !factors.iter().any(|f| f == SIZE_SOURCE_COL);
+        if add_size {
+            factors.push(SIZE_SOURCE_COL.to_string());
         }
-        tracing::debug!("Factors sub-sample");
-        let mut df = dbfac::query_factors_range(
-            duckstore::get().await.unwrap(),
-            country.as_str(),
+        let keep_raw: &[&str] = if gby.is_some() { &[SIZE_SOURCE_COL] } else { &[] };
+        let mut df = load_factor_panel(
+            &[country],
             factors,
+            &derived,
             start_iso,
             end_iso,
+            factors_path.as_str(),
+            securities,
+            prep.unwrap_or_default(),
+            keep_raw,
         )
-        .await
-        .map_err(|e| ServerFnError::new(e.to_string()))?;
-        let mat1 = MyMmMatrix::from_polars_dataframe(&mut df)
+        .await?;
+        if add_size {
+            df = df
+                .drop(SIZE_SOURCE_COL)
+                .map_err(|e| ServerFnError::new(e.to_string()))?;
+        }
+        let raw_size = format!("{SIZE_SOURCE_COL}_raw");
+        let mut rows_df = df.drop_many([raw_size.as_str()]);
+        let mat1 = MyMmMatrix::from_polars_dataframe(&mut rows_df)
             .map_err(|e| ServerFnError::new(e.to_string()))?;
         tracing::debug!("data float shape{:?}", mat1.colnames_enum_f64);
         tracing::debug!("data str shape{:?}", mat1.colnames_enum_str);
@@ -166,8 +131,20 @@ pub async fn fetch_factors_matrix(
                     _ => {}
                 }
             }
+            // Firms with no observations of a column keep NaN, which the charts skip.
+            let means: Vec<Expr> = nms
+                .iter()
+                .map(|c| {
+                    let name = format!("{c}_mean");
+                    col(name.as_str())
+                        .cast(DataType::Float64)
+                        .fill_null(lit(f64::NAN))
+                        .alias(name.as_str())
+                })
+                .collect();
             df = plrs::group_mean(df.lazy(), group_cols, nms)
                 .map_err(|e| ServerFnError::new(e.to_string()))?
+                .with_columns(means)
                 .collect()
                 .map_err(|e| ServerFnError::new(e.to_string()))?;
             mat2 = MyMmMatrix::from_polars_dataframe(&mut df)
@@ -177,6 +154,24 @@ pub async fn fetch_factors_matrix(
     }
 }
 
+/// Cluster the per-firm matrix on a blocking thread.
+#[server]
+pub async fn fit_clusters(mat: MyMmMatrix, cfg: ClusterConfig) -> Result<ClusterFit, ServerFnError> {
+    #[cfg(not(feature = "server"))]
+    {
+        let _ = (mat, cfg);
+        return Err(ServerFnError::new("fit_clusters requires the `server` feature"));
+    }
+
+    #[cfg(feature = "server")]
+    {
+        tokio::task::spawn_blocking(move || mat.cluster(&cfg))
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?
+            .map_err(ServerFnError::new)
+    }
+}
+
 #[component]
 pub fn MultiAsset() -> Element {
     let mut selected = use_signal(|| "TUR".to_string());
@@ -187,7 +182,7 @@ pub fn MultiAsset() -> Element {
             String::from("gvkey"),
             String::from("iid"),
             String::from("excntry"),
-            String::from("dolvol"),
+            String::from("log_dolvol"),
             String::from("rvolhl_21d"),
             String::from("ret_60_12"),
             String::from("ret_3_1"),
@@ -197,6 +192,14 @@ pub fn MultiAsset() -> Element {
             String::from("capx_gr3"),
         ]
     });
+    // Formula columns; a derived factor is fetched whenever its name is in `factor_list`.
+    // Dollar volume is heavily skewed, so the defaults regress on its log.
+    let mut derived: Signal<Vec<DerivedFactor>> = use_signal(|| {
+        vec![DerivedFactor {
+            name: "log_dolvol".to_string(),
+            formula: "log(dolvol)".to_string(),
+        }]
+    });
     let mut idcols: Signal<Vec<String>> = use_signal(|| Vec::new());
     let mut idiofac: Signal<Vec<String>> = use_signal(|| Vec::new());
     let mut crossfac: Signal<Vec<String>> = use_signal(|| Vec::new());
@@ -204,11 +207,24 @@ pub fn MultiAsset() -> Element {
     let mut vis_val = use_signal(|| "".to_string());
     let mut start_date = use_signal(|| Utc::now().to_rfc3339());
     let mut end_date = use_signal(|| Utc::now().to_rfc3339());
-    let start_naivedate = use_signal(|| Utc.with_ymd_and_hms(2024, 1, 1, 0, 1, 1).unwrap());
-    let end_naivedate = use_signal(|| Utc::now());
+    let mut start_naivedate = use_signal(|| Utc.with_ymd_and_hms(2024, 1, 1, 0, 1, 1).unwrap());
+    let mut end_naivedate = use_signal(|| Utc::now());
     // No client-side connection cache; DB lives server-side only
     // Build a comma-separated list efficiently (no leading comma) when needed
     // (displayed below the Factors dropdown)
+    // Per-date preprocessing of the factor columns (winsorize percentiles are in percent).
+    let mut winsorize = use_signal(|| false);
+    let mut winsor_lo = use_signal(|| 1.0_f64);
+    let mut winsor_hi = use_signal(|| 99.0_f64);
+    let mut transform = use_signal(PrepTransform::default);
+    let mut neutralize: Signal<Option<String>> = use_signal(|| None);
+    let current_prep = move || FactorPrep {
+        winsorize: winsorize
+            .peek()
+            .then(|| (*winsor_lo.peek() / 100.0, *winsor_hi.peek() / 100.0)),
+        neutralize: neutralize.peek().clone(),
+        transform: *transform.peek(),
+    };
     let mut mat_fac: Signal<Option<MyMmMatrix>> = use_signal(|| None);
     let mut gp_mat_fac: Signal<Option<MyMmMatrix>> = use_signal(|| None);
 
@@ -216,21 +232,112 @@ pub fn MultiAsset() -> Element {
         "~/Dropbox/Desktop/tesero-sol/software_development/trading/data/raw_files/parquet/factors/global/tests/mothly_factors_2024_2025.parquet"
             .to_string();
 
+    // Countries (with security counts), date range and factor columns of the loaded file.
+    // Nothing is read until the user scans the file or submits, so opening the view doesn't
+    // load the dataset.
+    let mut discover = use_signal(|| 0);
+    let discovery = use_resource({
+        let path = factors_path.clone();
+        move || {
+            let path = path.clone();
+            async move {
+                if discover() == 0 {
+                    return Ok(None);
+                }
+                factor_schema(path).await.map(Some)
+            }
+        }
+    });
+    // Move the query window onto the data if the default range misses it entirely.
+    use_effect(move || {
+        if let Some(Ok(Some(FactorSchema {
+            date_bounds: Some((lo, hi)),
+            ..
+        }))) = &*discovery.read()
+        {
+            let start = start_naivedate.peek().date_naive();
+            let end = end_naivedate.peek().date_naive();
+            if end < *lo || start > *hi {
+                start_naivedate.set(Utc.from_utc_datetime(&lo.and_hms_opt(0, 0, 0).unwrap()));
+                end_naivedate.set(Utc.from_utc_datetime(&hi.and_hms_opt(0, 0, 0).unwrap()));
+            }
+        }
+    });
+    let factor_option = |name: &str| {
+        let def = catalog().get(name);
+        DynOption {
+            value: name.to_string(),
+            label: match def {
+                Some(f) => format!("{name} ({})", f.full_name),
+                None => name.to_string(),
+            },
+            title: def.map(|f| f.tooltip()),
+        }
+    };
+    let (country_options, date_bounds, factor_options) = match &*discovery.read() {
+        Some(Ok(Some(schema))) => (
+            schema
+                .countries
+                .iter()
+                .map(|(code, n)| DynOption::new(code.clone(), format!("{code} ({n})")))
+                .collect::<Vec<_>>(),
+            schema.date_bounds,
+            schema
+                .columns
+                .iter()
+                .map(|c| factor_option(c.as_str()))
+                .collect::<Vec<_>>(),
+        ),
+        _ => (
+            vec![DynOption::new(selected(), selected())],
+            None,
+            dropdownlist::SelectOptionProps::global_stock_returns()
+                .iter()
+                .map(|c| factor_option(c))
+                .collect(),
+        ),
+    };
+
+    let country_codes: Vec<String> = country_options.iter().map(|o| o.value.clone()).collect();
+    let screen_columns: Vec<String> = factor_options
+        .iter()
+        .map(|o| o.value.clone())
+        .filter(|c| !ID_COLUMNS.contains(&c.as_str()))
+        .collect();
+    let screen_path = factors_path.clone();
+    let formula_columns: Vec<String> = factor_options.iter().map(|o| o.value.clone()).collect();
+
+    // Filters as of this render, handed to the research panels.
+    let research_query = FactorQuery {
+        country: selected(),
+        factors: factor_list(),
+        derived: derived(),
+        start: start_naivedate().date_naive(),
+        end: end_naivedate().date_naive(),
+        path: factors_path.clone(),
+        prep: current_prep(),
+    };
+
     // Run the server fetch in the background; use_resource won't suspend initial render
     let mut submit_count = use_signal(|| 0);
     let resource = use_resource(move || {
         let path = factors_path.clone();
         async move {
-            // Only rerun when submit_count changes.
-            let _ = submit_count();
+            // Only rerun when submit_count changes, and not before the first submit.
+            if submit_count() == 0 {
+                return Ok(None);
+            }
             // Read other values without subscribing, so they don't trigger reruns.
             let country = selected.peek().clone();
             let factors = factor_list.peek().clone();
+            let derived = derived.peek().clone();
             let start = start_naivedate.peek().date_naive();
             let end = end_naivedate.peek().date_naive();
+            let prep = current_prep();
             fetch_factors_matrix(
                 country,
                 factors,
+                derived,
                 start,
                 end,
                 path,
@@ -239,14 +346,17 @@ pub fn MultiAsset() -> Element {
                     String::from("iid"),
                     String::from("excntry"),
                 ]),
+                None,
+                Some(prep),
             )
             .await
+            .map(Some)
         }
     });
     use_effect({
         let resource = resource.clone();
         move || {
-            if let Some(Ok(mat)) = resource.read().deref() {
+            if let Some(Ok(Some(mat))) = resource.read().deref() {
                 mat_fac.set(Some(mat.0.clone()));
                 gp_mat_fac.set(Some(mat.1.clone()));
                 tracing::debug!("Succesfully update the matrix");
@@ -255,81 +365,76 @@ pub fn MultiAsset() -> Element {
     });
 
     //pca comp var
+    let mut pca_cfg = use_signal(PcaConfig::default);
+    let mut pc_axes = use_signal(|| (0_usize, 1_usize));
+    let mut pca_fit: Signal<Option<PcaFit>> = use_signal(|| None);
     let mut pca_nms: Signal<Option<NmsPca>> = use_signal(|| None);
     use_effect(move || {
+        let cfg = pca_cfg();
         let Some(arr) = gp_mat_fac() else {
+            pca_fit.set(None);
             pca_nms.set(None);
             return;
         };
 
-        if arr.data_f64.nrows() < 2 || arr.data_f64.ncols() < 2 {
-            pca_nms.set(None);
-            return;
-        }
-
-        match MyMmMatrix::pca_fit_transform_dmatrix(arr.data_f64.clone(), 2, vec![0, 1, 2].into()) {
-            Ok((records, components)) => {
-                if records.nrows() == 0 || records.ncols() < 2 {
-                    pca_nms.set(None);
-                    return;
-                }
-                tracing::debug!("Components {:?}", components);
+        match arr.pca(&cfg) {
+            Ok(fit) => {
+                tracing::debug!("Components {:?}", fit.components);
                 pca_nms.set(Some(NmsPca {
-                    components,
-                    records,
+                    components: fit.components.clone(),
+                    records: fit.scores.clone(),
                     labels: None,
-                    nms: Vec::new(),
+                    nms: (0..arr.nrows()).map(|i| arr.cell(i, "gvkey")).collect(),
+                    axes: (0, 1),
+                    explained: fit.explained_ratio(),
                 }));
+                pca_fit.set(Some(fit));
             }
             Err(e) => {
                 tracing::error!("PCA fit/transform failed: {e}");
+                pca_fit.set(None);
                 pca_nms.set(None);
             }
         }
-        match MyMmMatrix::kmeans_clusters(arr.data_f64) {
-            Ok(labs) => {
-                if let Some(pca) = pca_nms.write().as_mut() {
-                    pca.labels = Some(labs);
-                }
-            }
-            Err(e) => {
-                tracing::error!("Kmeans fit/transform failed: {e}");
-            }
+    });
+    // Clusters use the same columns as the PCA. Fitting every k is too slow for the browser, so
+    // it runs on the server and a newer config cancels the pending fit.
+    let mut cluster_cfg = use_signal(ClusterConfig::default);
+    let cluster_run = use_resource(move || async move {
+        let cfg = ClusterConfig {
+            columns: pca_cfg().columns,
+            ..cluster_cfg()
+        };
+        let arr = gp_mat_fac()?;
+        let fit = fit_clusters(arr, cfg).await.map_err(|e| e.to_string());
+        if let Err(e) = &fit {
+            tracing::error!("Clustering failed: {e}");
         }
+        Some(fit)
     });
+    let cluster_fit = move || cluster_run.read().clone().flatten();
+    let cluster_labels = cluster_fit().and_then(|f| f.ok()).map(|f| f.label_array());
+    let pca_candidates = gp_mat_fac()
+        .map(|m| m.pca_candidate_columns())
+        .unwrap_or_default();
+    let n_components = pca_fit().map(|f| f.components.nrows()).unwrap_or(0);
 
     rsx! {
         div { class: "card",
             div { class: "multi-card-div",
                 label { "Countries" }
-                select {
-                    value: "{selected()}",
-                    onchange: move |evt| selected.set(evt.value()),
-                    option { value: "TUR", "TUR" }
-                    option { value: "CHN", "CHN" }
-                    option { value: "HKG", "HKG" }
-                    option { value: "DNK", "DNK" }
-                    option { value: "PRT", "PRT" }
-                    option { value: "COL", "COL" }
-                    option { value: "IDN", "IDN" }
-                    option { value: "CHL", "CHL" }
-                    option { value: "HUN", "HUN" }
-                    option { value: "JPN", "JPN" }
-                    option { value: "GBR", "GBR" }
-                    option { value: "ITA", "ITA" }
-                    option { value: "NLD", "NLD" }
-                    option { value: "SWE", "SWE" }
-                    option { value: "AUT", "AUT" }
-                    option { value: "FRA", "FRA" }
-                    option { value: "NZL", "NZL" }
-                    option { value: "KOR", "KOR" }
-                    option { value: "DEU", "DEU" }
-                    option { value: "AUS", "AUS" }
-               }
+                dropdownlist::DynSelectOptions {
+                    value: selected(),
+                    options: country_options,
+                    onchange: move |evt: FormEvent| selected.set(evt.value()),
+                }
+                if let Some((lo, hi)) = date_bounds {
+                    p { "Data from {lo} to {hi}" }
+                }
                 label { "Factors"}
-                dropdownlist::SelectOptions {
+                dropdownlist::DynSelectOptions {
                     value: factor(),
-                    options: dropdownlist::SelectOptionProps::global_stock_returns(),
+                    options: factor_options,
                     onchange: move |evt: FormEvent| {
                         let val = evt.value();
                         factor.set(val.clone());
@@ -348,6 +453,26 @@ pub fn MultiAsset() -> Element {
                     label { "Selected" }
                     p {style: "padding-right: 5%;", "{factor_list().join(\", \")}" }
                 }
+                DerivedFactorEditor {
+                    columns: formula_columns,
+                    derived: derived(),
+                    onadd: move |d: DerivedFactor| {
+                        factor_list.with_mut(|v| {
+                            if !v.contains(&d.name) {
+                                v.push(d.name.clone());
+                            }
+                        });
+                        // Re-adding a name replaces its formula.
+                        derived.with_mut(|v| match v.iter_mut().find(|x| x.name == d.name) {
+                            Some(x) => *x = d,
+                            None => v.push(d),
+                        });
+                    },
+                    onremove: move |n: String| {
+                        factor_list.with_mut(|v| v.retain(|f| *f != n));
+                        derived.with_mut(|v| v.retain(|d| d.name != n));
+                    },
+                }
             }
             div { class: "multi-card-div",
                 label { "Bin Size" }
@@ -372,16 +497,96 @@ pub fn MultiAsset() -> Element {
                     value: "{end_date().as_str()}",
                     oninput: move |e| end_date.set(e.value()),
                 }
+                label { "Preprocessing (per date)" }
+                div {
+                    input {
+                        r#type: "checkbox",
+                        checked: winsorize(),
+                        onchange: move |e| winsorize.set(e.checked()),
+                    }
+                    " Winsorize at "
+                    input {
+                        r#type: "number",
+                        min: "0",
+                        max: "50",
+                        step: "0.5",
+                        style: "width: 4em;",
+                        value: "{winsor_lo}",
+                        oninput: move |e| {
+                            if let Ok(v) = e.value().parse::<f64>() {
+                                winsor_lo.set(v);
+                            }
+                        },
+                    }
+                    "% / "
+                    input {
+                        r#type: "number",
+                        min: "50",
+                        max: "100",
+                        step: "0.5",
+                        style: "width: 4em;",
+                        value: "{winsor_hi}",
+                        oninput: move |e| {
+                            if let Ok(v) = e.value().parse::<f64>() {
+                                winsor_hi.set(v);
+                            }
+                        },
+                    }
+                    "%"
+                }
+                label { "Transform" }
+                select {
+                    value: "{transform().as_str()}",
+                    onchange: move |evt| transform.set(PrepTransform::from_label(&evt.value())),
+                    for t in PrepTransform::ALL {
+                        option { value: "{t.as_str()}", "{t.label()}" }
+                    }
+                }
+                label { "Neutralize by" }
+                select {
+                    value: "{neutralize().unwrap_or_default()}",
+                    onchange: move |evt| {
+                        let v = evt.value();
+                        neutralize.set((!v.is_empty()).then_some(v));
+                    },
+                    option { value: "", "none" }
+                    for g in NEUTRALIZE_BY {
+                        option { value: "{g}", "{g}" }
+                    }
+                }
                 div{
                     style: "padding-top: 2vh;",
                     button {
                         class: "ma-btn",
-                        onclick: move |_| *submit_count.write() += 1,
+                        onclick: move |_| *discover.write() += 1,
+                        "scan file"
+                    }
+                    button {
+                        class: "ma-btn",
+                        onclick: move |_| {
+                            // The first submit loads the dataset anyway, so fill the pickers too.
+                            if discover() == 0 {
+                                discover.set(1);
+                            }
+                            *submit_count.write() += 1;
+                        },
                         "submit {submit_count}"
                     }
                 }
             }
         }
+        div { class: "multi-asset-h2-wrap",
+            h2 {class: "multi-asset-h2", "Datasets"}
+        }
+        DatasetsPanel {}
+        div { class: "multi-asset-h2-wrap",
+            h2 {class: "multi-asset-h2", "SQL Console"}
+        }
+        SqlConsole {}
+        div { class: "multi-asset-h2-wrap",
+            h2 {class: "multi-asset-h2", "Screener"}
+        }
+        ScreenerPanel { path: screen_path, country: selected(), columns: screen_columns }
         div { class: "multi-asset-h2-wrap",
             h2 {class: "multi-asset-h2", "Factor Statistics by Firm"}
         }
@@ -396,48 +601,25 @@ pub fn MultiAsset() -> Element {
                         .filter(|name| mat.find_index_f64(name.as_str()).is_some())
                         .collect();
 
-                    let mut cross_factors: Vec<String> = factor_list()
-                        .iter()
-                        .filter(|name| {
-                            dropdownlist::SelectOptionsProps::crossectional_factors()
-                                .contains(&name.as_str())
-                        })
-                        .map(|name| format!("{name}_mean"))
-                        .filter(|name| mat.find_index_f64(name.as_str()).is_some())
-                        .collect();
-
-                    let mut idiosyn_factors: Vec<String> = factor_list()
-                        .iter()
-                        .filter(|name| {
-                            dropdownlist::SelectOptionsProps::idiosyncratic_factors()
-                                .contains(&name.as_str())
-                        })
-                        .map(|name| format!("{name}_mean"))
-                        .filter(|name| mat.find_index_f64(name.as_str()).is_some())
-                        .collect();
-
-                    // If the category lists are empty/out of date, fall back to "whatever mean columns exist".
-                    if cross_factors.is_empty() && idiosyn_factors.is_empty() {
-                        cross_factors = if !selected_mean.is_empty() {
-                            selected_mean
-                        } else {
-                            mat.colnames_enum_f64
-                                .as_ref()
-                                .map(|cols| {
-                                    cols.iter()
-                                        .map(|(_, name)| name.clone())
-                                        .filter(|name| name.ends_with("_mean"))
-                                        .collect::<Vec<String>>()
-                                })
-                                .unwrap_or_default()
-                        };
-                    }
+                    // Fall back to every `*_mean` column when none of the selected factors survived grouping.
+                    let shown = if !selected_mean.is_empty() {
+                        selected_mean
+                    } else {
+                        mat.colnames_enum_f64
+                            .as_ref()
+                            .map(|cols| {
+                                cols.iter()
+                                    .map(|(_, name)| name.clone())
+                                    .filter(|name| name.ends_with("_mean"))
+                                    .collect::<Vec<String>>()
+                            })
+                            .unwrap_or_default()
+                    };
 
                         rsx!(
                             MultiFactorDisplay {
                             mat,
-                            cross_factors: Some(cross_factors),
-                            idiosyn_factors: Some(idiosyn_factors),
+                            groups: catalog().group_by_theme(&shown),
                             id_cols: vec![
                                 "gvkey".to_string(),
                                 "iid".to_string(),
@@ -448,10 +630,237 @@ pub fn MultiAsset() -> Element {
                     },
                     None => match resource.read().deref() {
                         Some(Err(e)) => rsx!(div { class: "error", "Factor load failed: {e}" }),
+                        Some(Ok(None)) => rsx!(div { "Press submit to load the factors." }),
                         _ => rsx!(div { "Loading factors…" }),
                     }
                 }
             }
+        div { class: "multi-asset-h2-wrap",
+            h2 {class: "multi-asset-h2", "Fama-MacBeth Regressions"}
+        }
+        FamaMacBethPanel { query: research_query.clone() }
+        div { class: "multi-asset-h2-wrap",
+            h2 {class: "multi-asset-h2", "Quantile Portfolios"}
+        }
+        QuantilePanel { query: research_query.clone() }
+        div { class: "multi-asset-h2-wrap",
+            h2 {class: "multi-asset-h2", "Information Coefficients"}
+        }
+        IcPanel { query: research_query.clone() }
+        div { class: "multi-asset-h2-wrap",
+            h2 {class: "multi-asset-h2", "Cross-Country Comparison"}
+        }
+        CountryComparisonPanel {
+            query: research_query.clone(),
+            available: country_codes,
+        }
+        div { class: "multi-asset-h2-wrap",
+            h2 {class: "multi-asset-h2", "Factor Correlations"}
+        }
+        FactorCorrelationPanel { query: research_query.clone() }
+        div { class: "multi-asset-h2-wrap",
+            h2 {class: "multi-asset-h2", "Principal Components"}
+        }
+        div { class: "card",
+            div { class: "multi-card-div",
+                label { "Components" }
+                input {
+                    r#type: "number",
+                    min: 1,
+                    max: pca_candidates.len().max(1) as i64,
+                    value: "{pca_cfg().k}",
+                    oninput: move |evt| {
+                        if let Ok(k) = evt.value().parse::<usize>() {
+                            pca_cfg.with_mut(|c| c.k = k.max(1));
+                        }
+                    },
+                }
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: pca_cfg().standardize,
+                        onchange: move |evt| pca_cfg.with_mut(|c| c.standardize = evt.checked()),
+                    }
+                    " Standardize columns"
+                }
+                label { "X axis" }
+                select {
+                    value: "{pc_axes().0}",
+                    onchange: move |evt| {
+                        if let Ok(c) = evt.value().parse::<usize>() {
+                            pc_axes.with_mut(|a| a.0 = c);
+                        }
+                    },
+                    for c in 0..n_components {
+                        option { value: "{c}", "PC{c + 1}" }
+                    }
+                }
+                label { "Y axis" }
+                select {
+                    value: "{pc_axes().1}",
+                    onchange: move |evt| {
+                        if let Ok(c) = evt.value().parse::<usize>() {
+                            pc_axes.with_mut(|a| a.1 = c);
+                        }
+                    },
+                    for c in 0..n_components {
+                        option { value: "{c}", "PC{c + 1}" }
+                    }
+                }
+            }
+            div { class: "multi-card-div",
+                label { "Columns" }
+                for name in pca_candidates.clone() {
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: pca_cfg().columns.is_empty() || pca_cfg().columns.contains(&name),
+                            onchange: {
+                                let name = name.clone();
+                                let all = pca_candidates.clone();
+                                move |evt: FormEvent| {
+                                    pca_cfg.with_mut(|c| {
+                                        if c.columns.is_empty() {
+                                            c.columns = all.clone();
+                                        }
+                                        c.columns.retain(|n| *n != name);
+                                        if evt.checked() {
+                                            c.columns.push(name.clone());
+                                        }
+                                    });
+                                }
+                            },
+                        }
+                        " {name}"
+                    }
+                }
+            }
+        }
+        if let Some(fit) = pca_fit() {
+            div { style: "display: flex; flex-direction: row; gap: 2%;",
+                div { style: "width: 49%;",
+                    BarChart {
+                        title: "Scree: explained variance by component".to_string(),
+                        labels: (1..=fit.eigenvalues.len()).map(|c| format!("PC{c}")).collect::<Vec<_>>(),
+                        values: fit.explained_ratio(),
+                    }
+                }
+                div { style: "width: 49%;",
+                    if let Some(nms) = pca_nms() {
+                        LoadingsBiplot {
+                            pca_nms: NmsPca { axes: pc_axes(), ..nms },
+                            names: fit.columns.clone(),
+                        }
+                    }
+                }
+            }
+        }
+        div { class: "multi-asset-h2-wrap",
+            h2 {class: "multi-asset-h2", "Clusters"}
+        }
+        div { class: "card",
+            div { class: "multi-card-div",
+                label { "Algorithm" }
+                select {
+                    value: "{cluster_cfg().algo.as_str()}",
+                    onchange: move |evt| cluster_cfg.with_mut(|c| c.algo = ClusterAlgo::from_label(&evt.value())),
+                    for a in ClusterAlgo::ALL {
+                        option { value: "{a.as_str()}", "{a.label()}" }
+                    }
+                }
+                if cluster_cfg().algo == ClusterAlgo::Dbscan {
+                    label { "Radius (eps)" }
+                    input {
+                        r#type: "number",
+                        min: 0.01,
+                        step: 0.05,
+                        value: "{cluster_cfg().eps}",
+                        oninput: move |evt| {
+                            if let Ok(v) = evt.value().parse::<f64>() {
+                                cluster_cfg.with_mut(|c| c.eps = v.max(0.01));
+                            }
+                        },
+                    }
+                    label { "Min points" }
+                    input {
+                        r#type: "number",
+                        min: 1,
+                        value: "{cluster_cfg().min_points}",
+                        oninput: move |evt| {
+                            if let Ok(v) = evt.value().parse::<usize>() {
+                                cluster_cfg.with_mut(|c| c.min_points = v.max(1));
+                            }
+                        },
+                    }
+                } else {
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: cluster_cfg().k.is_none(),
+                            onchange: move |evt| {
+                                cluster_cfg.with_mut(|c| c.k = if evt.checked() { None } else { Some(3) });
+                            },
+                        }
+                        " Pick k by silhouette"
+                    }
+                    if let Some(k) = cluster_cfg().k {
+                        label { "Clusters" }
+                        input {
+                            r#type: "number",
+                            min: 1,
+                            value: "{k}",
+                            oninput: move |evt| {
+                                if let Ok(v) = evt.value().parse::<usize>() {
+                                    cluster_cfg.with_mut(|c| c.k = Some(v.max(1)));
+                                }
+                            },
+                        }
+                    }
+                    label { "Largest k tried" }
+                    input {
+                        r#type: "number",
+                        min: 2,
+                        max: 20,
+                        value: "{cluster_cfg().k_max}",
+                        oninput: move |evt| {
+                            if let Ok(v) = evt.value().parse::<usize>() {
+                                cluster_cfg.with_mut(|c| c.k_max = v.clamp(2, 20));
+                            }
+                        },
+                    }
+                }
+            }
+        }
+        match cluster_fit() {
+            Some(Ok(fit)) => {
+                let ks: Vec<String> = fit.diagnostics.iter().map(|d| d.k.to_string()).collect();
+                let inertia: Vec<f64> = fit.diagnostics.iter().map(|d| d.inertia).collect();
+                let silhouette: Vec<f64> = fit.diagnostics.iter().map(|d| d.silhouette).collect();
+                rsx! {
+                    p { "{fit.algo.label()}: {fit.n_clusters} cluster(s), mean silhouette {fit.silhouette:.3}" }
+                    if !fit.diagnostics.is_empty() {
+                        div { style: "display: flex; flex-direction: row; gap: 2%;",
+                            div { style: "width: 49%;",
+                                SeriesChart {
+                                    title: "Elbow: within-cluster sum of squares by k".to_string(),
+                                    x_labels: ks.clone(),
+                                    series: vec![("inertia".to_string(), inertia)],
+                                }
+                            }
+                            div { style: "width: 49%;",
+                                SeriesChart {
+                                    title: "Mean silhouette by k".to_string(),
+                                    x_labels: ks,
+                                    series: vec![("silhouette".to_string(), silhouette)],
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            Some(Err(e)) => rsx! { div { class: "error", "Clustering failed: {e}" } },
+            None => rsx! {},
+        }
         section { class: "grid-wrapper",
                 PromptBox { }
             }
@@ -490,11 +899,14 @@ pub fn MultiAsset() -> Element {
 		                                            width: 100%;
 		                                            height: 100%;
 		                                            display: flex;
-	                                                flex-direction: row;
+	                                                flex-direction: column;
 	                                                justify-content: center;
 		                                            align-items: start;
 		                                        ",
-                                                CharPlot { mat }
+                                                CharPlot {
+                                                    mat,
+                                                    clusters: cluster_fit().and_then(|f| f.ok()).map(|f| f.labels),
+                                                }
                                             }
                                         ),
                                         None => rsx!(
@@ -511,19 +923,26 @@ pub fn MultiAsset() -> Element {
                                         ),
                                     },
                                     "PCA" => match pca_nms() {
-                                        Some(mat) => rsx!(
-                                            div {
-                                                style: "
-		                                            width: 100%;
-		                                            height: 100%;
-		                                            display: flex;
-	                                                flex-direction: row;
-	                                                justify-content: center;
-		                                            align-items: start;
-		                                        ",
-                                                PcaChart { pca_nms: mat }
-                                            }
-                                        ),
+                                        Some(mat) => {
+                                            let mat = NmsPca {
+                                                axes: pc_axes(),
+                                                labels: cluster_labels.clone(),
+                                                ..mat
+                                            };
+                                            rsx!(
+                                                div {
+                                                    style: "
+		                                                width: 100%;
+		                                                height: 100%;
+		                                                display: flex;
+	                                                    flex-direction: row;
+	                                                    justify-content: center;
+		                                                align-items: start;
+		                                            ",
+                                                    PcaChart { pca_nms: mat }
+                                                }
+                                            )
+                                        }
                                         None => rsx!(
                                             div {
                                                 style: "
@@ -577,7 +996,10 @@ pub fn MultiAsset() -> Element {
                 h3 { "Security Groups"}
                 div {
                     style: "margin-top: 3vh;",
-                    SecurityComp {}
+                    match (gp_mat_fac(), cluster_fit()) {
+                        (Some(mat), Some(Ok(fit))) => rsx! { ClusterMembership { mat, fit } },
+                        _ => rsx! { SecurityComp {} },
+                    }
                 }
                 }
             }
@@ -589,24 +1011,10 @@ pub fn MultiAsset() -> Element {
                     Some(mat) => rsx!(
                         MultiFactorDisplay {
                             mat,
-                            cross_factors: Some(
-                                factor_list()
-                                    .iter()
-                                    .filter(|c| {
-                                        dropdownlist::SelectOptionsProps::crossectional_factors()
-                                            .contains(&c.as_str())
-                                    })
-                                    .cloned()
-                                    .collect::<Vec<String>>(),
-                            ),
-                            idiosyn_factors: Some(
-                                factor_list()
-                                    .iter()
-                                    .filter(|c| {
-                                        dropdownlist::SelectOptionsProps::idiosyncratic_factors()
-                                            .contains(&c.as_str())
-                                    })
-                                    .cloned()
+                            groups: catalog().group_by_theme(
+                                &factor_list()
+                                    .into_iter()
+                                    .filter(|c| !ID_COLUMNS.contains(&c.as_str()))
                                     .collect::<Vec<String>>(),
                             ),
                             id_cols: vec![
@@ -619,6 +1027,7 @@ pub fn MultiAsset() -> Element {
                     ),
                     None => match resource.read().deref() {
                         Some(Err(e)) => rsx!(div { class: "error", "Factor load failed: {e}" }),
+                        Some(Ok(None)) => rsx!(div { "Press submit to load the factors." }),
                         _ => rsx!(div { "Loading factors…" }),
                     },
                 }
@@ -1,6 +1,56 @@
 use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A set of securities handed to the Portfolio view, e.g. the result of a screen.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Universe {
+    /// What produced the universe (the screen's rules).
+    pub name: String,
+    pub country: String,
+    pub date: Option<String>,
+    /// (gvkey, iid) in rank order.
+    pub members: Vec<(String, String)>,
+}
+
+/// Shared universe, provided by `Home` so the Multi-Asset and Portfolio tabs see the same one.
+#[derive(Clone, Copy)]
+pub struct UniverseCtx(pub Signal<Option<Universe>>);
 
 #[component]
 pub fn Portfolio() -> Element {
-    rsx! {}
+    let mut universe = use_context::<UniverseCtx>();
+    rsx! {
+        div { class: "card",
+            h3 { "Universe" }
+            match universe.0() {
+                Some(u) => rsx! {
+                    p {
+                        "{u.members.len()} securities in {u.country}"
+                        if let Some(d) = &u.date { " as of {d}" }
+                        ": {u.name} "
+                        button { class: "ma-btn", onclick: move |_| universe.0.set(None), "Clear" }
+                    }
+                    table { class: "trade-table",
+                        thead {
+                            tr {
+                                th { "#" }
+                                th { "gvkey" }
+                                th { "iid" }
+                            }
+                        }
+                        tbody {
+                            for (i, (gvkey, iid)) in u.members.iter().enumerate() {
+                                tr { class: "ind-trade",
+                                    td { "{i + 1}" }
+                                    td { "{gvkey}" }
+                                    td { "{iid}" }
+                                }
+                            }
+                        }
+                    }
+                },
+                None => rsx! { p { "No universe yet. Run a screen in the Multi-Asset view and send it here." } },
+            }
+        }
+    }
 }
@@ -1,18 +1,27 @@
 use crate::model_request::{momentum_lstm::*, *};
 use crate::news::Fetch;
-use crate::surr_queries::{query_surr_trade_bin_db, query_surr_trademsg_db};
+use crate::ingest::bin_size_secs;
+use crate::surr_queries::{default_surreal_url, query_surr_trade_bin_db, query_surr_trademsg_db};
 use crate::{
     charts::{
         candle_stick::{CandlesChart, LcMatrix},
         clustering::ScatterPlot,
+        series::SeriesChart,
         single_asset_lc::PlottersChart,
         ChartType,
     },
-    ops::MyMatrix,
+    ops::{
+        calendar::Exchange,
+        garch::{self, VolModel},
+        microstructure,
+        quality::{check_bins, check_trades, QualityParams},
+        stats::{bin_returns, bin_stats, trade_stats, StatsSource},
+        MyMatrix,
+    },
     prompting::PromptBox,
-    tables::{SalesTable, TradeDisplay},
+    tables::{MicrostructurePanel, QualityPanel, SalesTable, TradeDisplay},
 };
-use chrono::{DateTime, Utc};
+use chrono::Utc;
 use dioxus::prelude::*;
 use serde::Deserialize;
 //use ml_backend::surreal_queries::{make_db,DbParams};
@@ -60,9 +69,13 @@ pub fn SingleAsset() -> Element {
     let mut instrument = use_signal(|| 8147);
     let mut bin_size = use_signal(|| "5m".to_string());
     let mut chart_type = use_signal(|| 0);
-    let url = use_signal(|| {
-        String::from("https://quant-platform-06cb0tpcrpsspao10de28go15s.aws-use1.surreal.cloud/rpc")
-    });
+    let mut drop_flagged = use_signal(|| false);
+    let mut stats_source = use_signal(StatsSource::default);
+    let mut vol_model = use_signal(VolModel::default);
+    let mut vol_horizon = use_signal(|| 10usize);
+    let mut exchange = use_signal(Exchange::default);
+    let mut last_n_days = use_signal(|| 5usize);
+    let url = use_signal(default_surreal_url);
     let user = use_signal(|| String::from("root"));
     let pass = use_signal(|| String::from("root"));
     let ns = use_signal(|| String::from("equities"));
@@ -114,18 +127,13 @@ pub fn SingleAsset() -> Element {
     });
     let get_data_button = {
         let log_in = move |_| {
-            let date_fmt = "%Y-%m-%dT%H:%M:%S%z";
-            date1.set(
-                DateTime::parse_from_str((start_date().clone() + ":00+0000").as_str(), date_fmt)
-                    .unwrap()
-                    .into(),
-            );
+            if let Some(start) = exchange().parse_local(&start_date()) {
+                date1.set(start);
+            }
             println!("{:?}", date1);
-            date2.set(
-                DateTime::parse_from_str((end_date().clone() + ":00+0000").as_str(), date_fmt)
-                    .unwrap()
-                    .into(),
-            );
+            if let Some(end) = exchange().parse_local(&end_date()) {
+                date2.set(end);
+            }
             println!("{:?}", date2);
             spawn(async move {
                 if let Ok(resp) = query_surr_trademsg_db(
@@ -165,10 +173,67 @@ pub fn SingleAsset() -> Element {
         };
         Some(rsx!(button { onclick: log_in, "Get Data" }))
     };
-    let data = response.read();
+    // Quality reports and the (optionally cleaned) matrices are recomputed only when the data,
+    // bin size, exchange or drop toggle change, not on every render.
+    let trades_quality = use_memo(move || check_trades(&response.read(), QualityParams::default()));
+    let bins_quality = use_memo(move || {
+        check_bins(
+            &response2.read(),
+            bin_size_secs(bin_size().as_str()).unwrap_or(300),
+            exchange(),
+            QualityParams::default(),
+        )
+    });
+    let trades_view = use_memo(move || {
+        if drop_flagged() {
+            response.read().drop_rows(&trades_quality.read().flagged_rows())
+        } else {
+            response.read().clone()
+        }
+    });
+    let bins_view = use_memo(move || {
+        if drop_flagged() {
+            response2.read().drop_rows(&bins_quality.read().flagged_rows())
+        } else {
+            response2.read().clone()
+        }
+    });
+    let micro_report = use_memo(move || {
+        microstructure::analyze(
+            &trades_view.read(),
+            bin_size_secs(bin_size().as_str()).unwrap_or(300),
+            exchange(),
+        )
+    });
+    let (data, data2) = (trades_view(), bins_view());
     let descrips = data.snapshot(0).unwrap_or_default();
-    let data2 = response2.read();
     let descrips2 = data2.snapshot(1).unwrap_or_default();
+    // Volatility model on the bin returns, with each return's bin time as its label.
+    let vol_fit = use_memo(move || {
+        let bins = bins_view.read();
+        let series = bin_returns(&bins);
+        let returns: Vec<f64> = series.iter().map(|(_, r)| *r).collect();
+        let labels: Vec<String> = match bins.find_index("bin") {
+            Some(j) => series
+                .iter()
+                .map(|(i, _)| exchange().axis_label(bins.data[(*i, j)] as i64))
+                .collect(),
+            None => series.iter().map(|(i, _)| i.to_string()).collect(),
+        };
+        garch::fit(vol_model(), &returns, vol_horizon()).map(|fit| (fit, labels))
+    });
+    let vol_ann = exchange().annualization_factor(bin_size_secs(bin_size().as_str()).unwrap_or(300));
+    let stoch_vol = vol_fit()
+        .ok()
+        .and_then(|(fit, _)| fit.forecast.first().map(|v| (fit.model.label().to_string(), v * vol_ann)));
+    let stats = match stats_source() {
+        StatsSource::Trades => trade_stats(&data, exchange()),
+        StatsSource::Bins => bin_stats(
+            &data2,
+            bin_size_secs(bin_size().as_str()).unwrap_or(300),
+            exchange(),
+        ),
+    };
     rsx! {
         div { class: "card",
             div {
@@ -194,6 +259,16 @@ pub fn SingleAsset() -> Element {
                     option { value: "1hour", "1 hour" }
                     option { value: "1day", "1 day" }
                 }
+                label {"Exchange"}
+                    select {
+                    value: "{exchange().as_str()}",
+                    onchange: move |evt| {
+                        exchange.set(Exchange::from_label(&evt.value()));
+                    },
+                    for ex in Exchange::ALL {
+                        option { value: "{ex.as_str()}", "{ex.as_str()}" }
+                    }
+                }
             }
             div {
                 label { "Start Date" }
@@ -218,6 +293,27 @@ pub fn SingleAsset() -> Element {
                 }
             }
             div {
+                label { "Last N Trading Days" }
+                input {
+                    r#type: "number",
+                    min: 1,
+                    value: "{last_n_days()}",
+                    oninput: move |evt| {
+                        if let Ok(val) = evt.value().parse::<usize>() {
+                            last_n_days.set(val.max(1));
+                        }
+                    }
+                }
+                button {
+                    onclick: move |_| {
+                        let ex = exchange();
+                        if let Some((start, end)) = ex.last_n_trading_days(last_n_days(), Utc::now()) {
+                            start_date.set(ex.format_local(start));
+                            end_date.set(ex.format_local(end));
+                        }
+                    },
+                    "Use Range"
+                }
             }
             div {
                 label { "Chart Type" }
@@ -236,7 +332,14 @@ pub fn SingleAsset() -> Element {
             section { class: "grid-section-1x",
                 section { class: "grid-section-2x",
                     div { class: "grid-item",
-                        SalesTable { data: data.data.clone(), descrips: descrips }
+                        select {
+                            value: "{stats_source().as_str()}",
+                            onchange: move |evt| stats_source.set(StatsSource::from_label(&evt.value())),
+                            for s in StatsSource::ALL {
+                                option { value: "{s.as_str()}", "{s.label()}" }
+                            }
+                        }
+                        SalesTable { stats, stoch_vol }
                     }
                     div { class: "grid-item",
                         ScatterPlot { data: data.data.clone(), descrips: descrips }
@@ -255,13 +358,15 @@ pub fn SingleAsset() -> Element {
                                 PlottersChart {
                                     matrix: data2.clone(),
                                     y_axis: "mean_price".to_string(),
+                                    exchange: exchange(),
                                 }
                             },
                         1 => rsx! {
                                 CandlesChart {
                                     matrix: data2.clone(),
                                     y_axis: "mean_price".to_string(),
-                                    parallel: true
+                                    parallel: true,
+                                    exchange: exchange(),
                             }
                         },
                         _ => rsx! {
@@ -270,6 +375,95 @@ pub fn SingleAsset() -> Element {
                     }
                 }
             }
+            section { class: "grid-section-1x",
+                div { class: "grid-full-chart",
+                    label { "Volatility model" }
+                    select {
+                        value: "{vol_model().as_str()}",
+                        onchange: move |evt| vol_model.set(VolModel::from_label(&evt.value())),
+                        for m in VolModel::ALL {
+                            option { value: "{m.as_str()}", "{m.label()}" }
+                        }
+                    }
+                    label { " Forecast bins " }
+                    input {
+                        r#type: "number",
+                        min: 1,
+                        max: 500,
+                        value: "{vol_horizon()}",
+                        oninput: move |evt| {
+                            if let Ok(v) = evt.value().parse::<usize>() {
+                                vol_horizon.set(v.clamp(1, 500));
+                            }
+                        }
+                    }
+                    match vol_fit() {
+                        Ok((fit, labels)) => {
+                            let n = fit.cond_vol.len();
+                            let x_labels: Vec<String> = labels
+                                .into_iter()
+                                .chain((1..=fit.forecast.len()).map(|k| format!("+{k}")))
+                                .collect();
+                            let fitted: Vec<f64> = fit
+                                .cond_vol
+                                .iter()
+                                .map(|v| v * vol_ann)
+                                .chain(fit.forecast.iter().map(|_| f64::NAN))
+                                .collect();
+                            let forecast: Vec<f64> = (0..n)
+                                .map(|_| f64::NAN)
+                                .chain(fit.forecast.iter().map(|v| v * vol_ann))
+                                .collect();
+                            rsx! {
+                                SeriesChart {
+                                    title: format!("{} conditional volatility (annualized)", fit.model.label()),
+                                    x_labels,
+                                    series: vec![
+                                        ("Conditional vol".to_string(), fitted),
+                                        ("Forecast".to_string(), forecast),
+                                    ],
+                                }
+                                table { class: "kv-table",
+                                    tbody {
+                                        for (name, value) in fit.params.iter() {
+                                            tr { th { "{name}" } td { {format!("{value:.6e}")} } }
+                                        }
+                                        tr { th { "Persistence" } td { {format!("{:.4}", fit.persistence)} } }
+                                        tr { th { "Long-run vol (ann.)" } td { {format!("{:.4}", fit.long_run_vol * vol_ann)} } }
+                                        tr { th { "Log-likelihood" } td { {format!("{:.2}", fit.loglik)} } }
+                                        tr { th { "AIC / BIC" } td { {format!("{:.2} / {:.2}", fit.aic, fit.bic)} } }
+                                        tr { th { "Returns" } td { "{n}" } }
+                                        if !fit.converged {
+                                            tr { th { "Note" } td { "optimizer hit its iteration limit" } }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        Err(e) => rsx! { p { "Volatility model: {e}" } },
+                    }
+                }
+            }
+        }
+        section { class: "grid-wrapper",
+            div { class: "grid-section-1x-center",
+                h3 { class: "qh3-term", "Data Quality" }
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: drop_flagged(),
+                        onchange: move |evt| drop_flagged.set(evt.checked()),
+                    }
+                    " Drop flagged rows before analysis"
+                }
+                QualityPanel { trades: trades_quality(), bins: bins_quality() }
+            }
+        }
+        section { class: "grid-wrapper",
+            div { class: "grid-section-1x-center",
+                h3 { class: "qh3-term", "Microstructure" }
+                MicrostructurePanel { report: micro_report(), exchange: exchange() }
+            }
         }
         section { class: "grid-wrapper",
                 PromptBox { }
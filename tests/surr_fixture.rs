@@ -0,0 +1,199 @@
+#![cfg(all(feature = "server", feature = "mem-db"))]
+// End-to-end runs of the `query_surr_*` server functions against the seeded `mem://` fixture.
+use chrono::{Duration, TimeZone, Utc};
+use og_euler_anunoby::ops::microstructure::TS_OFFSET_COL;
+use og_euler_anunoby::surr_queries::fixture::{
+    fixture_anchor, fixture_db, synthetic_trades, FIXTURE_INSTRUMENTS, MEM_URL,
+};
+use og_euler_anunoby::surr_queries::{
+    query_surr_filtered, query_surr_trade_bin_db, query_surr_trademsg_db, FilterLogic, SortKey,
+    SurrFilter,
+};
+use std::sync::LazyLock;
+use tokio::runtime::Runtime;
+
+const NS: &str = "equities";
+const DB: &str = "historical";
+
+/// Every `#[tokio::test]` gets its own runtime, but the cached fixture's engine runs on the
+/// runtime that seeded it, so the async tests share one runtime that outlives them all.
+fn on_shared_runtime<F: std::future::Future>(test: F) -> F::Output {
+    static RUNTIME: LazyLock<Runtime> = LazyLock::new(|| Runtime::new().unwrap());
+    RUNTIME.block_on(test)
+}
+
+fn creds() -> (String, String, String, String, String) {
+    (
+        MEM_URL.to_string(),
+        "root".to_string(),
+        "root".to_string(),
+        NS.to_string(),
+        DB.to_string(),
+    )
+}
+
+#[test]
+fn synthetic_trades_are_reproducible() {
+    let a = synthetic_trades(FIXTURE_INSTRUMENTS[0], fixture_anchor());
+    let b = synthetic_trades(FIXTURE_INSTRUMENTS[0], fixture_anchor());
+    assert!(!a.is_empty());
+    assert_eq!(a.len(), b.len());
+    assert!(a
+        .iter()
+        .zip(&b)
+        .all(|(x, y)| x.data.price == y.data.price && x.data.ts_recv == y.data.ts_recv));
+    let last = a.iter().map(|t| t.data.ts_recv).max().unwrap();
+    assert!(last <= fixture_anchor().timestamp_nanos_opt().unwrap());
+}
+
+#[test]
+fn each_namespace_gets_its_own_seeded_instance() {
+    on_shared_runtime(async {
+        let count = |db: surrealdb::Surreal<surrealdb::engine::any::Any>| async move {
+            let n: Option<i64> = db
+                .query("SELECT count() FROM trades GROUP ALL")
+                .await
+                .unwrap()
+                .take((0, "count"))
+                .unwrap();
+            n.unwrap_or(0)
+        };
+        let a = fixture_db("fixture_test_a", "one").await.unwrap();
+        let b = fixture_db("fixture_test_b", "two").await.unwrap();
+        let (na, nb) = (count(a).await, count(b).await);
+        assert!(na > 0);
+        assert_eq!(na, nb);
+        // Asking again returns the already seeded instance rather than seeding twice.
+        let again = fixture_db("fixture_test_a", "one").await.unwrap();
+        assert_eq!(count(again).await, na);
+    });
+}
+
+#[test]
+fn trades_query_returns_one_instrument_in_time_order() {
+    on_shared_runtime(async {
+        let (url, user, pass, ns, db) = creds();
+        let end = fixture_anchor();
+        let m = query_surr_trademsg_db(
+            url,
+            user,
+            pass,
+            ns,
+            db,
+            "bin_1m".to_string(),
+            end - Duration::days(1),
+            end,
+            FIXTURE_INSTRUMENTS[0] as i64,
+        )
+        .await
+        .unwrap();
+        assert!(m.data.nrows() > 0);
+        let ts = m.find_index("ts_recv").unwrap();
+        let col = m.data.column(ts);
+        assert!(col.iter().zip(col.iter().skip(1)).all(|(a, b)| a <= b));
+        // Prices come back in dollars, not Databento fixed point.
+        let px = m.find_index("price").unwrap();
+        assert!(m.data.column(px).iter().all(|p| *p > 1.0 && *p < 1_000.0));
+        // The exact nanosecond offsets start at the first print.
+        let offset = m.find_index(TS_OFFSET_COL).unwrap();
+        assert_eq!(m.data[(0, offset)], 0.0);
+    });
+}
+
+#[test]
+fn bin_query_returns_the_requested_bin_size() {
+    on_shared_runtime(async {
+        let (url, user, pass, ns, db) = creds();
+        let end = fixture_anchor();
+        let m = query_surr_trade_bin_db(
+            url,
+            user,
+            pass,
+            ns,
+            db,
+            "bin".to_string(),
+            end - Duration::days(1),
+            end,
+            FIXTURE_INSTRUMENTS[1] as i64,
+            "30m".to_string(),
+        )
+        .await
+        .unwrap();
+        // One trading session of 30 minute bins.
+        assert_eq!(m.data.nrows(), 13);
+        let p1 = m.find_index("p1").unwrap();
+        assert!(m.data.column(p1).iter().all(|p| *p > 1.0 && *p < 1_000.0));
+    });
+}
+
+#[test]
+fn filtered_query_sorts_and_limits_in_the_database() {
+    on_shared_runtime(async {
+        let (url, user, pass, ns, db) = creds();
+        let filter = SurrFilter {
+            int: vec![(
+                "instrument_id".to_string(),
+                FIXTURE_INSTRUMENTS[0] as i64,
+                FilterLogic::And,
+            )],
+            string: vec![(
+                "bin_size".to_string(),
+                "1hour".to_string(),
+                FilterLogic::And,
+            )],
+            time_range: Some((
+                "bin".to_string(),
+                Utc.with_ymd_and_hms(2024, 3, 11, 0, 0, 0).unwrap(),
+                fixture_anchor(),
+                FilterLogic::And,
+            )),
+            ..SurrFilter::default()
+        };
+        let sort = vec![SortKey {
+            column: "p1".to_string(),
+            descending: true,
+        }];
+        let m = query_surr_filtered(
+            url,
+            user,
+            pass,
+            ns,
+            db,
+            "equities_returns".to_string(),
+            vec!["bin".to_string(), "p1".to_string()],
+            filter,
+            sort,
+            Some(5),
+        )
+        .await
+        .unwrap();
+        assert_eq!(m.data_f64.nrows(), 5);
+        let p1 = m.data_f64.column(m.find_index_f64("p1").unwrap());
+        assert!(p1.iter().zip(p1.iter().skip(1)).all(|(a, b)| a >= b));
+    });
+}
+
+#[test]
+fn filtered_query_rejects_unselected_sort_keys() {
+    on_shared_runtime(async {
+        let (url, user, pass, ns, db) = creds();
+        let sort = vec![SortKey {
+            column: "ts_recv".to_string(),
+            descending: false,
+        }];
+        let err = query_surr_filtered(
+            url,
+            user,
+            pass,
+            ns,
+            db,
+            "trades".to_string(),
+            vec!["price".to_string()],
+            SurrFilter::default(),
+            sort,
+            Some(10),
+        )
+        .await;
+        assert!(err.is_err());
+    });
+}